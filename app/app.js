@@ -8,6 +8,8 @@ const state = {
   timers: [],
   selectedTimerId: null,
   marks: [],
+  marksTimerId: null,
+  marksCursor: null,
   todos: [],
   insertedTodoIds: new Set(),
   compactInsertedTodoIds: new Set(),
@@ -15,6 +17,9 @@ const state = {
   compactPrecision: "minute", // "minute" or "second"
 };
 
+const STATS_RANGE_DAYS = 7;
+const MARK_PAGE_SIZE = 100;
+const MARK_PAGE_MAX = 1000;
 const COMPACT_MODE_KEY = "countdown_todo_compact_mode";
 const COMPACT_PRECISION_KEY = "countdown_todo_compact_precision";
 
@@ -29,6 +34,8 @@ const compactTodoDropdown = $("compact-todo-dropdown");
 const compactRemaining = $("compact-remaining");
 const toggleCompactButton = $("toggle-compact");
 const toastNode = $("toast");
+const statsPanel = $("stats-panel");
+const statsBody = $("stats-body");
 
 let toastTimer = null;
 
@@ -83,6 +90,13 @@ function formatDuration(totalMinutes) {
   return `${hours}小时${minutes}分`;
 }
 
+function formatLocalDate(date) {
+  const yyyy = date.getFullYear();
+  const mm = String(date.getMonth() + 1).padStart(2, "0");
+  const dd = String(date.getDate()).padStart(2, "0");
+  return `${yyyy}-${mm}-${dd}`;
+}
+
 function markSuccessToast(mark) {
   if (!mark) {
     return "已打卡";
@@ -456,6 +470,19 @@ function renderMarks() {
 
     markListNode.append(li);
   }
+
+  if (state.marksCursor) {
+    const li = document.createElement("li");
+    const more = document.createElement("button");
+    more.className = "btn-ghost btn-sm";
+    more.textContent = "加载更早的记录";
+    more.onclick = async () => {
+      await loadMoreMarks();
+      renderMarks();
+    };
+    li.append(more);
+    markListNode.append(li);
+  }
 }
 
 function renderTodos() {
@@ -580,6 +607,37 @@ function renderAll() {
   renderCompactBar();
 }
 
+function renderStats(stats) {
+  statsBody.innerHTML = "";
+  for (const day of stats.days) {
+    const row = document.createElement("tr");
+    for (const value of [
+      day.date,
+      formatDuration(day.minutes_logged),
+      day.mark_count,
+      day.todos_completed,
+    ]) {
+      const cell = document.createElement("td");
+      cell.textContent = value;
+      row.appendChild(cell);
+    }
+    statsBody.appendChild(row);
+  }
+}
+
+async function refreshStats() {
+  const today = new Date();
+  const firstDay = new Date(today);
+  firstDay.setDate(today.getDate() - (STATS_RANGE_DAYS - 1));
+
+  const stats = await invokeEnvelope("stats_daily", {
+    from_date: formatLocalDate(firstDay),
+    to_date: formatLocalDate(today),
+    utc_offset_minutes: -today.getTimezoneOffset(),
+  });
+  renderStats(stats);
+}
+
 async function refreshTimers() {
   state.timers = await invokeEnvelope("timer_list", {
     include_archived: false,
@@ -594,16 +652,41 @@ async function refreshTimers() {
   }
 }
 
+async function loadMoreMarks() {
+  if (!state.selectedTimerId || !state.marksCursor) {
+    return;
+  }
+  try {
+    const page = await invokeEnvelope("mark_page_by_timer", {
+      timer_id: state.selectedTimerId,
+      cursor: state.marksCursor,
+      limit: MARK_PAGE_SIZE,
+      newest_first: true,
+    });
+    state.marks = state.marks.concat(page.items);
+    state.marksCursor = page.next_cursor;
+  } catch (_error) {
+    // already handled in invokeEnvelope
+  }
+}
+
 async function refreshMarksAndTodos() {
   if (!state.selectedTimerId) {
     state.marks = [];
+    state.marksCursor = null;
     state.todos = [];
     return;
   }
 
-  state.marks = await invokeEnvelope("mark_list_by_timer", {
+  const loaded = state.marksTimerId === state.selectedTimerId ? state.marks.length : 0;
+  const page = await invokeEnvelope("mark_page_by_timer", {
     timer_id: state.selectedTimerId,
+    limit: Math.min(Math.max(MARK_PAGE_SIZE, loaded), MARK_PAGE_MAX),
+    newest_first: true,
   });
+  state.marks = page.items;
+  state.marksTimerId = state.selectedTimerId;
+  state.marksCursor = page.next_cursor;
   state.todos = await invokeEnvelope("todo_list_by_timer", {
     timer_id: state.selectedTimerId,
   });
@@ -772,6 +855,19 @@ toggleCompactButton.addEventListener("click", () => {
   setCompactMode(!state.compactMode);
 });
 
+$("toggle-stats").addEventListener("click", async () => {
+  const opening = !statsPanel.classList.contains("open");
+  statsPanel.classList.toggle("open", opening);
+  if (!opening) {
+    return;
+  }
+  try {
+    await refreshStats();
+  } catch (_error) {
+    // already handled in invokeEnvelope
+  }
+});
+
 $("open-data-dir").addEventListener("click", async () => {
   try {
     await invokeEnvelope("open_data_dir");
@@ -780,6 +876,41 @@ $("open-data-dir").addEventListener("click", async () => {
   }
 });
 
+let domainEventRefresh = null;
+
+function scheduleDomainEventRefresh() {
+  if (domainEventRefresh) {
+    return;
+  }
+  domainEventRefresh = setTimeout(async () => {
+    domainEventRefresh = null;
+    try {
+      await refreshTimers();
+      await refreshMarksAndTodos();
+      renderAll();
+    } catch (_error) {
+      // already handled in invokeEnvelope
+    }
+  }, 50);
+}
+
+function reminderMessage(reminder) {
+  if (reminder.kind === "overdue") {
+    return `${reminder.timer_name} 已超时 ${-reminder.remaining_minutes} 分钟`;
+  }
+  return `${reminder.timer_name} 距离截止还剩 ${reminder.remaining_minutes} 分钟`;
+}
+
+async function subscribeDomainEvents() {
+  const listen = window.__TAURI__?.event?.listen;
+  if (!listen) {
+    return;
+  }
+  await listen("domain-event", scheduleDomainEventRefresh);
+  await listen("data-synced", scheduleDomainEventRefresh);
+  await listen("reminder-due", (event) => showToast(reminderMessage(event.payload)));
+}
+
 setInterval(() => {
   if (state.timers.length > 0) {
     renderTimers();
@@ -793,6 +924,7 @@ setInterval(() => {
     await refreshTimers();
     await refreshMarksAndTodos();
     renderAll();
+    await subscribeDomainEvents();
 
     const compactModeEnabled = localStorage.getItem(COMPACT_MODE_KEY) === "1";
     setCompactMode(compactModeEnabled, false);
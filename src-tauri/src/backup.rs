@@ -0,0 +1,230 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::calendar;
+use crate::error::{AppError, AppResult};
+use crate::model::EpochMinutes;
+
+pub const BACKUPS_DIR: &str = "backups";
+pub const DEFAULT_KEEP: usize = 10;
+pub const DEFAULT_EVERY_WRITES: usize = 50;
+
+const MILLIS_PER_DAY: i64 = 24 * 60 * 60 * 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupPolicy {
+    pub keep: usize,
+    pub every_writes: usize,
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        Self {
+            keep: DEFAULT_KEEP,
+            every_writes: DEFAULT_EVERY_WRITES,
+        }
+    }
+}
+
+impl BackupPolicy {
+    pub fn new(keep: usize, every_writes: usize) -> AppResult<Self> {
+        if keep == 0 {
+            return Err(AppError::Validation(
+                "backup rotation must keep at least one snapshot".to_string(),
+            ));
+        }
+        Ok(Self { keep, every_writes })
+    }
+
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let keep = env_count("COUNTDOWN_TODO_BACKUP_KEEP").unwrap_or(defaults.keep);
+        let every_writes =
+            env_count("COUNTDOWN_TODO_BACKUP_EVERY_WRITES").unwrap_or(defaults.every_writes);
+        Self::new(keep, every_writes).unwrap_or(defaults)
+    }
+}
+
+fn env_count(name: &str) -> Option<usize> {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub id: String,
+    pub created_at_minute: EpochMinutes,
+    pub files: Vec<String>,
+}
+
+pub fn list_backups(root: &Path) -> AppResult<Vec<BackupInfo>> {
+    let backups_dir = root.join(BACKUPS_DIR);
+    if !backups_dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let entries = fs::read_dir(&backups_dir)
+        .map_err(|error| AppError::Internal(format!("failed to read {backups_dir:?}: {error}")))?;
+    let mut backups = Vec::new();
+    for entry in entries {
+        let entry = entry.map_err(|error| {
+            AppError::Internal(format!("failed to read {backups_dir:?}: {error}"))
+        })?;
+        let id = entry.file_name().to_string_lossy().to_string();
+        if entry.path().is_dir() && parse_backup_id(&id).is_some() {
+            backups.push(backup_info(&entry.path(), id)?);
+        }
+    }
+
+    backups.sort_by(|left, right| left.id.cmp(&right.id));
+    Ok(backups)
+}
+
+pub fn create_snapshot(root: &Path, file_names: &[&str]) -> AppResult<BackupInfo> {
+    let backups_dir = root.join(BACKUPS_DIR);
+    fs::create_dir_all(&backups_dir).map_err(|error| {
+        AppError::Internal(format!("failed to create {backups_dir:?}: {error}"))
+    })?;
+
+    let mut millis = current_millis();
+    let mut id = format_backup_id(millis);
+    while backups_dir.join(&id).exists() {
+        millis += 1;
+        id = format_backup_id(millis);
+    }
+
+    let partial_dir = backups_dir.join(format!(".{id}.partial"));
+    fs::create_dir_all(&partial_dir).map_err(|error| {
+        AppError::Internal(format!("failed to create {partial_dir:?}: {error}"))
+    })?;
+    for file_name in file_names {
+        let source = root.join(file_name);
+        if !source.exists() {
+            continue;
+        }
+        if let Err(error) = fs::copy(&source, partial_dir.join(file_name)) {
+            let _ = fs::remove_dir_all(&partial_dir);
+            return Err(AppError::Internal(format!(
+                "failed to write backup copy of {source:?}: {error}"
+            )));
+        }
+    }
+
+    let snapshot_dir = backups_dir.join(&id);
+    fs::rename(&partial_dir, &snapshot_dir).map_err(|error| {
+        AppError::Internal(format!(
+            "failed to rename {partial_dir:?} to {snapshot_dir:?}: {error}"
+        ))
+    })?;
+    backup_info(&snapshot_dir, id)
+}
+
+pub fn prune(root: &Path, keep: usize) -> AppResult<()> {
+    let backups = list_backups(root)?;
+    let excess = backups.len().saturating_sub(keep);
+    for backup in backups.into_iter().take(excess) {
+        let path = root.join(BACKUPS_DIR).join(&backup.id);
+        fs::remove_dir_all(&path)
+            .map_err(|error| AppError::Internal(format!("failed to remove {path:?}: {error}")))?;
+    }
+    Ok(())
+}
+
+pub fn snapshot_dir(root: &Path, backup_id: &str) -> AppResult<PathBuf> {
+    let path = root.join(BACKUPS_DIR).join(backup_id);
+    if parse_backup_id(backup_id).is_none() || !path.is_dir() {
+        return Err(AppError::NotFound(format!("backup {backup_id}")));
+    }
+    Ok(path)
+}
+
+fn backup_info(path: &Path, id: String) -> AppResult<BackupInfo> {
+    let entries = fs::read_dir(path)
+        .map_err(|error| AppError::Internal(format!("failed to read {path:?}: {error}")))?;
+    let mut files = Vec::new();
+    for entry in entries {
+        let entry = entry
+            .map_err(|error| AppError::Internal(format!("failed to read {path:?}: {error}")))?;
+        files.push(entry.file_name().to_string_lossy().to_string());
+    }
+    files.sort();
+
+    Ok(BackupInfo {
+        created_at_minute: parse_backup_id(&id).unwrap_or_default() / 60_000,
+        id,
+        files,
+    })
+}
+
+fn current_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+fn format_backup_id(millis: i64) -> String {
+    let day = millis.div_euclid(MILLIS_PER_DAY);
+    let (year, month, day_of_month) = calendar::civil_from_day(day);
+    let rest = millis.rem_euclid(MILLIS_PER_DAY);
+    let seconds = rest / 1000;
+    format!(
+        "{year:04}{month:02}{day_of_month:02}-{:02}{:02}{:02}-{:03}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60,
+        rest % 1000
+    )
+}
+
+fn parse_backup_id(id: &str) -> Option<i64> {
+    let mut parts = id.split('-');
+    let (date, time, millis) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() || date.len() != 8 || time.len() != 6 || millis.len() != 3 {
+        return None;
+    }
+    if !id.chars().all(|char| char.is_ascii_digit() || char == '-') {
+        return None;
+    }
+
+    let day =
+        calendar::parse_day(&format!("{}-{}-{}", &date[..4], &date[4..6], &date[6..])).ok()?;
+    let hours: i64 = time[..2].parse().ok()?;
+    let minutes: i64 = time[2..4].parse().ok()?;
+    let seconds: i64 = time[4..].parse().ok()?;
+    let millis: i64 = millis.parse().ok()?;
+    if hours >= 24 || minutes >= 60 || seconds >= 60 {
+        return None;
+    }
+    Some(day * MILLIS_PER_DAY + ((hours * 60 + minutes) * 60 + seconds) * 1000 + millis)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_backup_id, parse_backup_id, BackupPolicy};
+
+    #[test]
+    fn round_trips_timestamped_backup_ids() {
+        let millis = 1_792_337_445_123;
+        let id = format_backup_id(millis);
+        assert_eq!(id, "20261018-153045-123");
+        assert_eq!(parse_backup_id(&id), Some(millis));
+        assert_eq!(parse_backup_id("../timers"), None);
+        assert_eq!(parse_backup_id("20261018-253045-123"), None);
+    }
+
+    #[test]
+    fn rejects_policy_without_rotations() {
+        assert!(BackupPolicy::new(0, 10).is_err());
+        assert_eq!(
+            BackupPolicy::new(3, 0)
+                .expect("policy should be valid")
+                .keep,
+            3
+        );
+    }
+}
@@ -0,0 +1,347 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::ExitCode;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use countdown_todo_core::backup::BackupPolicy;
+use countdown_todo_core::command::{
+    CommandApi, Envelope, ExportDataCommand, ExportIcsCommand, ExportMarkdownCommand,
+    ExportTodoTxtCommand, ExportWarriorCommand, ImportDataCommand, ImportTimeEntriesCommand,
+    ImportTodoTxtCommand, ImportWarriorCommand, MergeDataDirCommand,
+};
+use countdown_todo_core::dataset::ImportMode;
+use countdown_todo_core::merge::MergeSide;
+use countdown_todo_core::reminder::ReminderPolicy;
+use countdown_todo_core::repository::CsvStore;
+use countdown_todo_core::service::AppService;
+use countdown_todo_core::timesheet::{SkippedRow, TimesheetReport};
+
+const USAGE: &str = "\
+Usage: countdown-todo [--data-dir DIR] [--device NAME] <command> [options]
+
+Commands:
+  export-markdown --from YYYY-MM-DD --to YYYY-MM-DD [--timer ID] [--utc-offset MINUTES] [--output FILE]
+  export-ics [--timer ID] [--include-archived true|false] [--alarms 1440,60,0] [--output FILE]
+  import-todotxt --timer ID --input FILE [--utc-offset MINUTES]
+  export-todotxt --timer ID [--utc-offset MINUTES] [--output FILE]
+  import-time-entries --input FILE [--utc-offset MINUTES]
+  export-taskwarrior [--timer ID] [--output FILE]
+  import-taskwarrior --input FILE [--timer ID]
+  export-timewarrior [--timer ID] [--output FILE]
+  import-timewarrior --input FILE [--timer ID]
+  export-data [--output FILE]
+  import-data --input FILE [--mode merge|replace]
+  merge-data --from DIR
+  sync
+
+The data directory defaults to $COUNTDOWN_TODO_DATA_DIR.
+With --device (or $COUNTDOWN_TODO_DEVICE) changes go to that device's sync log.
+Export commands open the data directory read-only and never rotate backups, so they
+also work while the desktop app holds the data directory lock.
+$COUNTDOWN_TODO_BACKUP_KEEP and $COUNTDOWN_TODO_BACKUP_EVERY_WRITES set the backup policy.";
+
+struct Args {
+    command: String,
+    options: HashMap<String, String>,
+}
+
+impl Args {
+    fn parse(raw: impl Iterator<Item = String>) -> Result<Self, String> {
+        let mut command = None;
+        let mut options = HashMap::new();
+        let mut raw = raw.peekable();
+        while let Some(arg) = raw.next() {
+            if arg == "--help" || arg == "-h" {
+                return Err(USAGE.to_string());
+            }
+            if let Some(name) = arg.strip_prefix("--") {
+                let value = raw
+                    .next()
+                    .ok_or_else(|| format!("option --{name} requires a value"))?;
+                options.insert(name.to_string(), value);
+            } else if command.is_none() {
+                command = Some(arg);
+            } else {
+                return Err(format!("unexpected argument '{arg}'"));
+            }
+        }
+        Ok(Self {
+            command: command.ok_or_else(|| USAGE.to_string())?,
+            options,
+        })
+    }
+
+    fn optional(&self, name: &str) -> Option<String> {
+        self.options.get(name).cloned()
+    }
+
+    fn required(&self, name: &str) -> Result<String, String> {
+        self.optional(name)
+            .ok_or_else(|| format!("missing required option --{name}"))
+    }
+
+    fn flag(&self, name: &str) -> Result<bool, String> {
+        self.optional(name).map_or(Ok(false), |value| {
+            value
+                .parse()
+                .map_err(|_| format!("invalid --{name} '{value}', expected true or false"))
+        })
+    }
+
+    fn utc_offset(&self) -> Result<i64, String> {
+        self.optional("utc-offset").map_or(Ok(0), |value| {
+            value
+                .parse()
+                .map_err(|_| format!("invalid --utc-offset '{value}'"))
+        })
+    }
+
+    fn data_dir(&self) -> Result<PathBuf, String> {
+        self.optional("data-dir")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("COUNTDOWN_TODO_DATA_DIR").map(PathBuf::from))
+            .ok_or_else(|| "set --data-dir or COUNTDOWN_TODO_DATA_DIR".to_string())
+    }
+
+    fn device(&self) -> Option<String> {
+        self.optional("device")
+            .or_else(|| std::env::var("COUNTDOWN_TODO_DEVICE").ok())
+            .filter(|device| !device.is_empty())
+    }
+}
+
+fn unwrap_envelope<T>(envelope: Envelope<T>) -> Result<T, String> {
+    match (envelope.data, envelope.error) {
+        (Some(data), _) => Ok(data),
+        (None, Some(error)) => Err(format!("{}: {}", error.code, error.message)),
+        (None, None) => Err("command returned no data".to_string()),
+    }
+}
+
+fn read_input(args: &Args) -> Result<String, String> {
+    let path = args.required("input")?;
+    std::fs::read_to_string(&path).map_err(|error| format!("failed to read {path}: {error}"))
+}
+
+fn write_output(args: &Args, text: String) -> Result<(), String> {
+    match args.optional("output") {
+        Some(path) => {
+            std::fs::write(&path, text).map_err(|error| format!("failed to write {path}: {error}"))
+        }
+        None => {
+            print!("{text}");
+            Ok(())
+        }
+    }
+}
+
+fn current_minute() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| (elapsed.as_secs() / 60) as i64)
+        .unwrap_or_default()
+}
+
+fn print_timesheet_report(report: &TimesheetReport) {
+    println!(
+        "imported {} marks, created {} timers, skipped {} rows",
+        report.marks.len(),
+        report.created_timers.len(),
+        report.skipped.len()
+    );
+    print_skipped(&report.skipped);
+}
+
+fn print_skipped(skipped: &[SkippedRow]) {
+    for row in skipped {
+        println!("  row {}: {}", row.row, row.reason);
+    }
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let data_dir = args.data_dir()?;
+    let store = if args.command.starts_with("export-") {
+        CsvStore::open_read_only(data_dir)
+    } else {
+        match args.device() {
+            Some(device) => CsvStore::with_device(data_dir, &device),
+            None => CsvStore::with_backup_policy(data_dir, BackupPolicy::from_env()),
+        }
+    }
+    .map_err(|error| error.to_string())?;
+    let service = AppService::open(store).map_err(|error| error.to_string())?;
+    let mut api = CommandApi::new(service);
+
+    match args.command.as_str() {
+        "export-markdown" => {
+            let markdown = unwrap_envelope(api.export_markdown(ExportMarkdownCommand {
+                timer_id: args.optional("timer"),
+                from_date: args.required("from")?,
+                to_date: args.required("to")?,
+                utc_offset_minutes: args.utc_offset()?,
+            }))?;
+            write_output(&args, markdown)
+        }
+        "export-ics" => {
+            let offsets_minutes = args
+                .optional("alarms")
+                .map(|value| ReminderPolicy::parse(&value).map(|policy| policy.offsets_minutes))
+                .transpose()
+                .map_err(|error| error.to_string())?;
+            let ics = unwrap_envelope(api.export_ics(ExportIcsCommand {
+                timer_id: args.optional("timer"),
+                include_archived: args.flag("include-archived")?,
+                offsets_minutes,
+            }))?;
+            write_output(&args, ics)
+        }
+        "import-todotxt" => {
+            let todos = unwrap_envelope(api.todo_import_todotxt(ImportTodoTxtCommand {
+                timer_id: args.required("timer")?,
+                text: read_input(&args)?,
+                utc_offset_minutes: args.utc_offset()?,
+                now_minute: current_minute(),
+            }))?;
+            println!("imported {} todos", todos.len());
+            Ok(())
+        }
+        "export-todotxt" => {
+            let text = unwrap_envelope(api.todo_export_todotxt(ExportTodoTxtCommand {
+                timer_id: args.required("timer")?,
+                utc_offset_minutes: args.utc_offset()?,
+            }))?;
+            write_output(&args, text)
+        }
+        "import-time-entries" => {
+            let report = unwrap_envelope(api.import_time_entries(ImportTimeEntriesCommand {
+                text: read_input(&args)?,
+                utc_offset_minutes: args.utc_offset()?,
+                now_minute: current_minute(),
+            }))?;
+            print_timesheet_report(&report);
+            Ok(())
+        }
+        "export-taskwarrior" => {
+            let json = unwrap_envelope(api.export_taskwarrior(ExportWarriorCommand {
+                timer_id: args.optional("timer"),
+            }))?;
+            write_output(&args, json)
+        }
+        "import-taskwarrior" => {
+            let report = unwrap_envelope(api.import_taskwarrior(ImportWarriorCommand {
+                timer_id: args.optional("timer"),
+                text: read_input(&args)?,
+                now_minute: current_minute(),
+            }))?;
+            println!(
+                "imported {} todos, created {} timers, skipped {} tasks",
+                report.todos.len(),
+                report.created_timers.len(),
+                report.skipped.len()
+            );
+            print_skipped(&report.skipped);
+            Ok(())
+        }
+        "export-timewarrior" => {
+            let data = unwrap_envelope(api.export_timewarrior(ExportWarriorCommand {
+                timer_id: args.optional("timer"),
+            }))?;
+            write_output(&args, data)
+        }
+        "import-timewarrior" => {
+            let report = unwrap_envelope(api.import_timewarrior(ImportWarriorCommand {
+                timer_id: args.optional("timer"),
+                text: read_input(&args)?,
+                now_minute: current_minute(),
+            }))?;
+            print_timesheet_report(&report);
+            Ok(())
+        }
+        "export-data" => {
+            let json = unwrap_envelope(api.export_data(ExportDataCommand {
+                now_minute: current_minute(),
+            }))?;
+            write_output(&args, json)
+        }
+        "import-data" => {
+            let mode = match args.optional("mode").as_deref() {
+                None | Some("merge") => ImportMode::Merge,
+                Some("replace") => ImportMode::Replace,
+                Some(other) => {
+                    return Err(format!(
+                        "invalid --mode '{other}', expected merge or replace"
+                    ))
+                }
+            };
+            let report = unwrap_envelope(api.import_data(ImportDataCommand {
+                text: read_input(&args)?,
+                mode,
+            }))?;
+            println!(
+                "imported {} entities, {} unchanged, {} marks relinked",
+                report.imported, report.unchanged, report.relinked_marks
+            );
+            for remapped in report.remapped {
+                println!(
+                    "  {} {} -> {}",
+                    remapped.entity_type, remapped.from, remapped.to
+                );
+            }
+            Ok(())
+        }
+        "merge-data" => {
+            let report = unwrap_envelope(api.merge_data_dir(MergeDataDirCommand {
+                other_dir: args.required("from")?,
+            }))?;
+            println!(
+                "added {} entities, {} duplicates, {} marks relinked",
+                report.added, report.deduplicated, report.relinked_marks
+            );
+            for conflict in report.conflicts {
+                println!(
+                    "  conflict {} {}: kept {}",
+                    conflict.entity_type,
+                    conflict.id,
+                    match conflict.kept {
+                        MergeSide::Left => "local",
+                        MergeSide::Right => "incoming",
+                    }
+                );
+            }
+            for remapped in report.remapped {
+                println!(
+                    "  {} {} -> {}",
+                    remapped.entity_type, remapped.from, remapped.to
+                );
+            }
+            Ok(())
+        }
+        "sync" => {
+            let report = unwrap_envelope(api.sync_data())?;
+            println!(
+                "devices: {}{}",
+                report.devices.join(", "),
+                if report.changed { " (updated)" } else { "" }
+            );
+            for conflict in report.conflicts {
+                println!(
+                    "  conflict {} {}: kept {}, discarded {}",
+                    conflict.entity_type, conflict.entity_id, conflict.kept, conflict.discarded
+                );
+            }
+            Ok(())
+        }
+        other => Err(format!("unknown command '{other}'\n\n{USAGE}")),
+    }
+}
+
+fn main() -> ExitCode {
+    let result = Args::parse(std::env::args().skip(1)).and_then(run);
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{message}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,177 @@
+use crate::error::{AppError, AppResult};
+use crate::model::EpochMinutes;
+
+pub const MINUTES_PER_DAY: EpochMinutes = 24 * 60;
+
+pub fn day_index(minute: EpochMinutes, utc_offset_minutes: EpochMinutes) -> i64 {
+    (minute + utc_offset_minutes).div_euclid(MINUTES_PER_DAY)
+}
+
+pub fn day_start_minute(day: i64, utc_offset_minutes: EpochMinutes) -> EpochMinutes {
+    day * MINUTES_PER_DAY - utc_offset_minutes
+}
+
+pub fn split_by_day(
+    start_minute: EpochMinutes,
+    end_minute: EpochMinutes,
+    utc_offset_minutes: EpochMinutes,
+) -> Vec<(i64, EpochMinutes)> {
+    let mut parts = Vec::new();
+    let mut cursor = start_minute;
+
+    while cursor < end_minute {
+        let day = day_index(cursor, utc_offset_minutes);
+        let next_day_start = day_start_minute(day + 1, utc_offset_minutes);
+        let part_end = end_minute.min(next_day_start);
+        parts.push((day, part_end - cursor));
+        cursor = part_end;
+    }
+
+    parts
+}
+
+pub fn civil_from_day(day: i64) -> (i64, u32, u32) {
+    let shifted = day + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day_of_month = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day_of_month)
+}
+
+pub fn day_from_civil(year: i64, month: u32, day_of_month: u32) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = i64::from(month);
+    let month_index = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * month_index + 2) / 5 + i64::from(day_of_month) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+pub fn format_day(day: i64) -> String {
+    let (year, month, day_of_month) = civil_from_day(day);
+    format!("{year:04}-{month:02}-{day_of_month:02}")
+}
+
+pub fn parse_day(value: &str) -> AppResult<i64> {
+    let invalid = || AppError::Validation(format!("invalid date '{value}', expected YYYY-MM-DD"));
+    let mut parts = value.trim().splitn(3, '-');
+    let year = parts
+        .next()
+        .and_then(|part| part.parse::<i64>().ok())
+        .ok_or_else(invalid)?;
+    let month = parts
+        .next()
+        .and_then(|part| part.parse::<u32>().ok())
+        .ok_or_else(invalid)?;
+    let day_of_month = parts
+        .next()
+        .and_then(|part| part.parse::<u32>().ok())
+        .ok_or_else(invalid)?;
+
+    if !(1..=12).contains(&month) || day_of_month == 0 {
+        return Err(invalid());
+    }
+    let day = day_from_civil(year, month, day_of_month);
+    if civil_from_day(day) != (year, month, day_of_month) {
+        return Err(invalid());
+    }
+    Ok(day)
+}
+
+pub fn format_utc_stamp(minute: EpochMinutes) -> String {
+    let (year, month, day_of_month) = civil_from_day(minute.div_euclid(MINUTES_PER_DAY));
+    let minute_of_day = minute.rem_euclid(MINUTES_PER_DAY);
+    format!(
+        "{year:04}{month:02}{day_of_month:02}T{:02}{:02}00Z",
+        minute_of_day / 60,
+        minute_of_day % 60
+    )
+}
+
+pub fn parse_utc_stamp(value: &str) -> AppResult<EpochMinutes> {
+    let invalid = || {
+        AppError::Validation(format!(
+            "invalid timestamp '{value}', expected YYYYMMDDTHHMMSSZ"
+        ))
+    };
+    let value = value.trim();
+    let digits = |range: std::ops::Range<usize>| {
+        value
+            .get(range)
+            .filter(|part| part.bytes().all(|byte| byte.is_ascii_digit()))
+            .and_then(|part| part.parse::<i64>().ok())
+            .ok_or_else(invalid)
+    };
+    if value.len() != 16 || &value[8..9] != "T" || &value[15..] != "Z" {
+        return Err(invalid());
+    }
+    let day = parse_day(&format!(
+        "{}-{}-{}",
+        digits(0..4)?,
+        digits(4..6)?,
+        digits(6..8)?
+    ))
+    .map_err(|_| invalid())?;
+    let (hour, minute, second) = (digits(9..11)?, digits(11..13)?, digits(13..15)?);
+    if hour > 23 || minute > 59 || second > 59 {
+        return Err(invalid());
+    }
+    Ok(day * MINUTES_PER_DAY + hour * 60 + minute)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        day_index, format_day, format_utc_stamp, parse_day, parse_utc_stamp, split_by_day,
+        MINUTES_PER_DAY,
+    };
+
+    #[test]
+    fn converts_between_days_and_dates() {
+        assert_eq!(format_day(0), "1970-01-01");
+        assert_eq!(format_day(20_744), "2026-10-18");
+        assert_eq!(parse_day("2026-10-18").expect("date should parse"), 20_744);
+        assert_eq!(
+            parse_day("2024-02-29").map(format_day).as_deref(),
+            Ok("2024-02-29")
+        );
+        assert!(parse_day("2026-02-30").is_err());
+        assert!(parse_day("yesterday").is_err());
+    }
+
+    #[test]
+    fn applies_utc_offset_to_day_boundaries() {
+        assert_eq!(day_index(MINUTES_PER_DAY - 60, 0), 0);
+        assert_eq!(day_index(MINUTES_PER_DAY - 60, 120), 1);
+        assert_eq!(day_index(30, -60), -1);
+    }
+
+    #[test]
+    fn splits_segments_at_local_midnight() {
+        let start = MINUTES_PER_DAY - 90;
+        assert_eq!(split_by_day(start, start + 120, 0), vec![(0, 90), (1, 30)]);
+        assert_eq!(split_by_day(start, start + 120, 60), vec![(0, 30), (1, 90)]);
+        assert!(split_by_day(start, start, 0).is_empty());
+    }
+
+    #[test]
+    fn round_trips_utc_stamps() {
+        let minute = 20_744 * MINUTES_PER_DAY + 9 * 60 + 5;
+        assert_eq!(format_utc_stamp(minute), "20261018T090500Z");
+        assert_eq!(parse_utc_stamp("20261018T090559Z"), Ok(minute));
+        assert!(parse_utc_stamp("20261018T250000Z").is_err());
+        assert!(parse_utc_stamp("2026-10-18T09:05Z").is_err());
+    }
+}
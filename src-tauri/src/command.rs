@@ -1,6 +1,26 @@
+use std::path::Path;
+
 use serde::Serialize;
 
-use crate::model::{EpochMinutes, Mark, Timer, Todo, TodoStatus};
+use crate::backup::BackupInfo;
+use crate::dataset::{DataImportReport, ImportMode};
+use crate::event::{DomainEvent, EventSubscriber, RebuildSummary};
+use crate::history::{HistoryStatus, HistoryStep};
+use crate::merge::MergeReport;
+use crate::model::{
+    Category, EpochMinutes, Mark, MarkTemplate, Project, ProjectSummary, TagUsage, Timer, Todo,
+    TodoStatus,
+};
+use crate::page::{Page, PageRequest};
+use crate::query::QueryResults;
+use crate::reminder::{ReminderEvent, ReminderPolicy};
+use crate::report::CategoryReport;
+use crate::search::{SearchHit, SearchQuery};
+use crate::stats::DailyStats;
+use crate::sync::SyncReport;
+use crate::taskwarrior::TaskImportReport;
+use crate::timeline::TimelineEntry;
+use crate::timesheet::TimesheetReport;
 use crate::{AppError, AppService, Store};
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
@@ -56,6 +76,49 @@ pub struct ArchiveTimerCommand {
     pub now_minute: EpochMinutes,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnarchiveTimerCommand {
+    pub timer_id: String,
+    pub now_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeleteTimerCommand {
+    pub timer_id: String,
+    pub cascade: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SetTimerProjectCommand {
+    pub timer_id: String,
+    pub project_id: Option<String>,
+    pub now_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CreateProjectCommand {
+    pub name: String,
+    pub now_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RenameProjectCommand {
+    pub project_id: String,
+    pub name: String,
+    pub now_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeleteProjectCommand {
+    pub project_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PollRemindersCommand {
+    pub now_minute: EpochMinutes,
+    pub offsets_minutes: Option<Vec<EpochMinutes>>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub struct CreateTodoCommand {
     pub timer_id: String,
@@ -81,6 +144,234 @@ pub struct CreateMarkCommand {
     pub marked_at_minute: EpochMinutes,
     pub description: String,
     pub todo_ids: Vec<String>,
+    pub tags: Vec<String>,
+    pub category_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CreateMarkFromTemplateCommand {
+    pub timer_id: String,
+    pub template_id: String,
+    pub marked_at_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CreateTemplateCommand {
+    pub name: String,
+    pub body: String,
+    pub now_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UpdateTemplateCommand {
+    pub template_id: String,
+    pub name: String,
+    pub body: String,
+    pub now_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeleteTemplateCommand {
+    pub template_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SetMarkCategoryCommand {
+    pub mark_id: String,
+    pub category_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CreateCategoryCommand {
+    pub name: String,
+    pub now_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RenameCategoryCommand {
+    pub category_id: String,
+    pub name: String,
+    pub now_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DeleteCategoryCommand {
+    pub category_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CategoryReportCommand {
+    pub timer_id: Option<String>,
+    pub from_minute: EpochMinutes,
+    pub to_minute: EpochMinutes,
+    pub utc_offset_minutes: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PageByTimerCommand {
+    pub timer_id: String,
+    pub since_minute: Option<EpochMinutes>,
+    pub until_minute: Option<EpochMinutes>,
+    pub cursor: Option<String>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+    pub newest_first: bool,
+}
+
+impl PageByTimerCommand {
+    fn page_request(self) -> (String, PageRequest) {
+        (
+            self.timer_id,
+            PageRequest {
+                since_minute: self.since_minute,
+                until_minute: self.until_minute,
+                cursor: self.cursor,
+                offset: self.offset,
+                limit: self.limit,
+                newest_first: self.newest_first,
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportIcsCommand {
+    pub timer_id: Option<String>,
+    pub include_archived: bool,
+    pub offsets_minutes: Option<Vec<EpochMinutes>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportTodoTxtCommand {
+    pub timer_id: String,
+    pub text: String,
+    pub utc_offset_minutes: EpochMinutes,
+    pub now_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportTimeEntriesCommand {
+    pub text: String,
+    pub utc_offset_minutes: EpochMinutes,
+    pub now_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportTodoTxtCommand {
+    pub timer_id: String,
+    pub utc_offset_minutes: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportWarriorCommand {
+    pub timer_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportDataCommand {
+    pub now_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportDataCommand {
+    pub text: String,
+    pub mode: ImportMode,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MergeDataDirCommand {
+    pub other_dir: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ImportWarriorCommand {
+    pub timer_id: Option<String>,
+    pub text: String,
+    pub now_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ExportMarkdownCommand {
+    pub timer_id: Option<String>,
+    pub from_date: String,
+    pub to_date: String,
+    pub utc_offset_minutes: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TimelineCommand {
+    pub since_minute: EpochMinutes,
+    pub until_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QueryCommand {
+    pub expression: String,
+    pub now_minute: EpochMinutes,
+    pub utc_offset_minutes: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchCommand {
+    pub text: String,
+    pub timer_id: Option<String>,
+    pub since_minute: Option<EpochMinutes>,
+    pub until_minute: Option<EpochMinutes>,
+    pub status: Option<TodoStatus>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EntityHistoryCommand {
+    pub entity_type: String,
+    pub entity_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RestoreBackupCommand {
+    pub backup_id: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DailyStatsCommand {
+    pub from_date: String,
+    pub to_date: String,
+    pub utc_offset_minutes: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SetTimerTagsCommand {
+    pub timer_id: String,
+    pub tags: Vec<String>,
+    pub now_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SetTodoTagsCommand {
+    pub todo_id: String,
+    pub tags: Vec<String>,
+    pub now_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ListMarksByTagCommand {
+    pub tag: String,
+    pub since_minute: Option<EpochMinutes>,
+    pub until_minute: Option<EpochMinutes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RenameTagCommand {
+    pub from: String,
+    pub to: String,
+    pub now_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MergeTagsCommand {
+    pub sources: Vec<String>,
+    pub target: String,
+    pub now_minute: EpochMinutes,
 }
 
 pub struct CommandApi<S: Store> {
@@ -92,6 +383,10 @@ impl<S: Store> CommandApi<S> {
         Self { service }
     }
 
+    pub fn subscribe(&mut self, subscriber: Box<dyn EventSubscriber>) {
+        self.service.subscribe(subscriber);
+    }
+
     pub fn timer_create(&mut self, request: CreateTimerCommand) -> Envelope<Timer> {
         match self
             .service
@@ -124,10 +419,105 @@ impl<S: Store> CommandApi<S> {
         }
     }
 
+    pub fn timer_unarchive(&mut self, request: UnarchiveTimerCommand) -> Envelope<Timer> {
+        match self
+            .service
+            .unarchive_timer(&request.timer_id, request.now_minute)
+        {
+            Ok(timer) => Envelope::success(timer),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn timer_delete(&mut self, request: DeleteTimerCommand) -> Envelope<Timer> {
+        match self
+            .service
+            .delete_timer(&request.timer_id, request.cascade)
+        {
+            Ok(timer) => Envelope::success(timer),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
     pub fn timer_list(&self, include_archived: bool) -> Envelope<Vec<Timer>> {
         Envelope::success(self.service.list_timers(include_archived))
     }
 
+    pub fn reminder_poll(&mut self, request: PollRemindersCommand) -> Envelope<Vec<ReminderEvent>> {
+        let policy = match request.offsets_minutes {
+            Some(offsets_minutes) => match ReminderPolicy::new(offsets_minutes) {
+                Ok(policy) => policy,
+                Err(error) => return Envelope::failure(error),
+            },
+            None => ReminderPolicy::default(),
+        };
+
+        match self.service.poll_reminders(request.now_minute, &policy) {
+            Ok(events) => Envelope::success(events),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn timer_set_project(&mut self, request: SetTimerProjectCommand) -> Envelope<Timer> {
+        match self.service.set_timer_project(
+            &request.timer_id,
+            request.project_id.as_deref(),
+            request.now_minute,
+        ) {
+            Ok(timer) => Envelope::success(timer),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn timer_list_by_project(
+        &self,
+        project_id: Option<&str>,
+        include_archived: bool,
+    ) -> Envelope<Vec<Timer>> {
+        match self
+            .service
+            .list_timers_by_project(project_id, include_archived)
+        {
+            Ok(timers) => Envelope::success(timers),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn project_create(&mut self, request: CreateProjectCommand) -> Envelope<Project> {
+        match self
+            .service
+            .create_project(request.name, request.now_minute)
+        {
+            Ok(project) => Envelope::success(project),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn project_rename(&mut self, request: RenameProjectCommand) -> Envelope<Project> {
+        match self
+            .service
+            .rename_project(&request.project_id, request.name, request.now_minute)
+        {
+            Ok(project) => Envelope::success(project),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn project_delete(&mut self, request: DeleteProjectCommand) -> Envelope<Project> {
+        match self.service.delete_project(&request.project_id) {
+            Ok(project) => Envelope::success(project),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn project_list(&self) -> Envelope<Vec<Project>> {
+        Envelope::success(self.service.list_projects())
+    }
+
+    pub fn project_summary_list(&self, now_minute: EpochMinutes) -> Envelope<Vec<ProjectSummary>> {
+        Envelope::success(self.service.list_project_summaries(now_minute))
+    }
+
     pub fn todo_create(&mut self, request: CreateTodoCommand) -> Envelope<Todo> {
         match self
             .service
@@ -162,12 +552,22 @@ impl<S: Store> CommandApi<S> {
         }
     }
 
+    pub fn todo_page_by_timer(&self, request: PageByTimerCommand) -> Envelope<Page<Todo>> {
+        let (timer_id, page_request) = request.page_request();
+        match self.service.page_todos_by_timer(&timer_id, &page_request) {
+            Ok(page) => Envelope::success(page),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
     pub fn mark_create(&mut self, request: CreateMarkCommand) -> Envelope<Mark> {
-        match self.service.create_mark(
+        match self.service.create_mark_with_details(
             &request.timer_id,
             request.marked_at_minute,
             request.description,
             request.todo_ids,
+            request.tags,
+            request.category_id,
         ) {
             Ok(mark) => Envelope::success(mark),
             Err(error) => Envelope::failure(error),
@@ -180,156 +580,1496 @@ impl<S: Store> CommandApi<S> {
             Err(error) => Envelope::failure(error),
         }
     }
-}
 
-fn map_error(error: AppError) -> CommandError {
-    match error {
-        AppError::Validation(message) => CommandError {
-            code: "E_VALIDATION",
-            message,
-            detail: None,
-        },
-        AppError::NotFound(message) => CommandError {
-            code: "E_NOT_FOUND",
-            message,
-            detail: None,
-        },
-        AppError::Conflict(message) => CommandError {
-            code: "E_CONFLICT",
-            message,
-            detail: None,
-        },
-        AppError::Internal(message) if message.contains("csv") => CommandError {
-            code: "E_CSV_PARSE",
-            message,
-            detail: None,
-        },
-        AppError::Internal(message) if message.contains("read") || message.contains("write") => {
-            CommandError {
-                code: "E_IO",
-                message,
-                detail: None,
-            }
+    pub fn mark_page_by_timer(&self, request: PageByTimerCommand) -> Envelope<Page<Mark>> {
+        let (timer_id, page_request) = request.page_request();
+        match self.service.page_marks_by_timer(&timer_id, &page_request) {
+            Ok(page) => Envelope::success(page),
+            Err(error) => Envelope::failure(error),
         }
-        AppError::Internal(message) => CommandError {
-            code: "E_INTERNAL",
-            message,
-            detail: None,
-        },
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use crate::command::{
-        ArchiveTimerCommand, CommandApi, CreateMarkCommand, CreateTimerCommand, CreateTodoCommand,
-        DeleteTodoCommand,
-    };
+    pub fn mark_create_from_template(
+        &mut self,
+        request: CreateMarkFromTemplateCommand,
+    ) -> Envelope<Mark> {
+        match self.service.create_mark_from_template(
+            &request.timer_id,
+            &request.template_id,
+            request.marked_at_minute,
+        ) {
+            Ok(mark) => Envelope::success(mark),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn template_create(&mut self, request: CreateTemplateCommand) -> Envelope<MarkTemplate> {
+        match self
+            .service
+            .create_template(request.name, request.body, request.now_minute)
+        {
+            Ok(template) => Envelope::success(template),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn template_update(&mut self, request: UpdateTemplateCommand) -> Envelope<MarkTemplate> {
+        match self.service.update_template(
+            &request.template_id,
+            request.name,
+            request.body,
+            request.now_minute,
+        ) {
+            Ok(template) => Envelope::success(template),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn template_delete(&mut self, request: DeleteTemplateCommand) -> Envelope<MarkTemplate> {
+        match self.service.delete_template(&request.template_id) {
+            Ok(template) => Envelope::success(template),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn template_list(&self) -> Envelope<Vec<MarkTemplate>> {
+        Envelope::success(self.service.list_templates())
+    }
+
+    pub fn mark_set_category(&mut self, request: SetMarkCategoryCommand) -> Envelope<Mark> {
+        match self
+            .service
+            .set_mark_category(&request.mark_id, request.category_id.as_deref())
+        {
+            Ok(mark) => Envelope::success(mark),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn category_create(&mut self, request: CreateCategoryCommand) -> Envelope<Category> {
+        match self
+            .service
+            .create_category(request.name, request.now_minute)
+        {
+            Ok(category) => Envelope::success(category),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn category_rename(&mut self, request: RenameCategoryCommand) -> Envelope<Category> {
+        match self
+            .service
+            .rename_category(&request.category_id, request.name, request.now_minute)
+        {
+            Ok(category) => Envelope::success(category),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn category_delete(&mut self, request: DeleteCategoryCommand) -> Envelope<Category> {
+        match self.service.delete_category(&request.category_id) {
+            Ok(category) => Envelope::success(category),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn category_list(&self) -> Envelope<Vec<Category>> {
+        Envelope::success(self.service.list_categories())
+    }
+
+    pub fn report_by_category(&self, request: CategoryReportCommand) -> Envelope<CategoryReport> {
+        match self.service.category_report(
+            request.timer_id.as_deref(),
+            request.from_minute,
+            request.to_minute,
+            request.utc_offset_minutes,
+        ) {
+            Ok(report) => Envelope::success(report),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn event_history(&self, request: EntityHistoryCommand) -> Envelope<Vec<DomainEvent>> {
+        match self
+            .service
+            .entity_history(&request.entity_type, &request.entity_id)
+        {
+            Ok(events) => Envelope::success(events),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn event_rebuild(&mut self) -> Envelope<RebuildSummary> {
+        match self.service.rebuild_from_events() {
+            Ok(summary) => Envelope::success(summary),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn undo(&mut self) -> Envelope<HistoryStep> {
+        match self.service.undo() {
+            Ok(step) => Envelope::success(step),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn redo(&mut self) -> Envelope<HistoryStep> {
+        match self.service.redo() {
+            Ok(step) => Envelope::success(step),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn history_status(&self) -> Envelope<HistoryStatus> {
+        Envelope::success(self.service.history_status())
+    }
+
+    pub fn backup_list(&self) -> Envelope<Vec<BackupInfo>> {
+        match self.service.list_backups() {
+            Ok(backups) => Envelope::success(backups),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn backup_create(&mut self) -> Envelope<BackupInfo> {
+        match self.service.create_backup() {
+            Ok(backup) => Envelope::success(backup),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn backup_restore(&mut self, request: RestoreBackupCommand) -> Envelope<BackupInfo> {
+        match self.service.restore_backup(&request.backup_id) {
+            Ok(backup) => Envelope::success(backup),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn stats_daily(&self, request: DailyStatsCommand) -> Envelope<DailyStats> {
+        match self.service.daily_stats(
+            &request.from_date,
+            &request.to_date,
+            request.utc_offset_minutes,
+        ) {
+            Ok(stats) => Envelope::success(stats),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn timer_set_tags(&mut self, request: SetTimerTagsCommand) -> Envelope<Timer> {
+        match self
+            .service
+            .set_timer_tags(&request.timer_id, request.tags, request.now_minute)
+        {
+            Ok(timer) => Envelope::success(timer),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn todo_set_tags(&mut self, request: SetTodoTagsCommand) -> Envelope<Todo> {
+        match self
+            .service
+            .set_todo_tags(&request.todo_id, request.tags, request.now_minute)
+        {
+            Ok(todo) => Envelope::success(todo),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn timer_list_by_tag(&self, tag: &str) -> Envelope<Vec<Timer>> {
+        match self.service.list_timers_by_tag(tag) {
+            Ok(timers) => Envelope::success(timers),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn todo_list_by_tag(&self, tag: &str) -> Envelope<Vec<Todo>> {
+        match self.service.list_todos_by_tag(tag) {
+            Ok(todos) => Envelope::success(todos),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn mark_list_by_tag(&self, request: ListMarksByTagCommand) -> Envelope<Vec<Mark>> {
+        match self.service.list_marks_by_tag(
+            &request.tag,
+            request.since_minute,
+            request.until_minute,
+        ) {
+            Ok(marks) => Envelope::success(marks),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn search(&self, request: SearchCommand) -> Envelope<Vec<SearchHit>> {
+        match self.service.search(&SearchQuery {
+            text: request.text,
+            timer_id: request.timer_id,
+            since_minute: request.since_minute,
+            until_minute: request.until_minute,
+            status: request.status,
+            limit: request.limit,
+        }) {
+            Ok(hits) => Envelope::success(hits),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn query(&self, request: QueryCommand) -> Envelope<QueryResults> {
+        match self.service.query(
+            &request.expression,
+            request.now_minute,
+            request.utc_offset_minutes,
+        ) {
+            Ok(results) => Envelope::success(results),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn export_markdown(&self, request: ExportMarkdownCommand) -> Envelope<String> {
+        match self.service.export_markdown(
+            request.timer_id.as_deref(),
+            &request.from_date,
+            &request.to_date,
+            request.utc_offset_minutes,
+        ) {
+            Ok(markdown) => Envelope::success(markdown),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn export_ics(&self, request: ExportIcsCommand) -> Envelope<String> {
+        let policy = match request.offsets_minutes {
+            Some(offsets_minutes) => match ReminderPolicy::new(offsets_minutes) {
+                Ok(policy) => policy,
+                Err(error) => return Envelope::failure(error),
+            },
+            None => ReminderPolicy::default(),
+        };
+
+        match self.service.export_ics(
+            request.timer_id.as_deref(),
+            request.include_archived,
+            &policy,
+        ) {
+            Ok(ics) => Envelope::success(ics),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn todo_import_todotxt(&mut self, request: ImportTodoTxtCommand) -> Envelope<Vec<Todo>> {
+        match self.service.import_todotxt(
+            &request.timer_id,
+            &request.text,
+            request.utc_offset_minutes,
+            request.now_minute,
+        ) {
+            Ok(todos) => Envelope::success(todos),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn todo_export_todotxt(&self, request: ExportTodoTxtCommand) -> Envelope<String> {
+        match self
+            .service
+            .export_todotxt(&request.timer_id, request.utc_offset_minutes)
+        {
+            Ok(text) => Envelope::success(text),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn import_time_entries(
+        &mut self,
+        request: ImportTimeEntriesCommand,
+    ) -> Envelope<TimesheetReport> {
+        match self.service.import_time_entries(
+            &request.text,
+            request.utc_offset_minutes,
+            request.now_minute,
+        ) {
+            Ok(report) => Envelope::success(report),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn export_taskwarrior(&self, request: ExportWarriorCommand) -> Envelope<String> {
+        match self.service.export_taskwarrior(request.timer_id.as_deref()) {
+            Ok(json) => Envelope::success(json),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn import_taskwarrior(
+        &mut self,
+        request: ImportWarriorCommand,
+    ) -> Envelope<TaskImportReport> {
+        match self.service.import_taskwarrior(
+            request.timer_id.as_deref(),
+            &request.text,
+            request.now_minute,
+        ) {
+            Ok(report) => Envelope::success(report),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn export_timewarrior(&self, request: ExportWarriorCommand) -> Envelope<String> {
+        match self.service.export_timewarrior(request.timer_id.as_deref()) {
+            Ok(data) => Envelope::success(data),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn import_timewarrior(
+        &mut self,
+        request: ImportWarriorCommand,
+    ) -> Envelope<TimesheetReport> {
+        match self.service.import_timewarrior(
+            request.timer_id.as_deref(),
+            &request.text,
+            request.now_minute,
+        ) {
+            Ok(report) => Envelope::success(report),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn export_data(&self, request: ExportDataCommand) -> Envelope<String> {
+        match self.service.export_data(request.now_minute) {
+            Ok(json) => Envelope::success(json),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn import_data(&mut self, request: ImportDataCommand) -> Envelope<DataImportReport> {
+        match self.service.import_data(&request.text, request.mode) {
+            Ok(report) => Envelope::success(report),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn merge_data_dir(&mut self, request: MergeDataDirCommand) -> Envelope<MergeReport> {
+        match self.service.merge_data_dir(Path::new(&request.other_dir)) {
+            Ok(report) => Envelope::success(report),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn sync_data(&mut self) -> Envelope<SyncReport> {
+        match self.service.sync() {
+            Ok(report) => Envelope::success(report),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn timeline(&self, request: TimelineCommand) -> Envelope<Vec<TimelineEntry>> {
+        match self
+            .service
+            .timeline(request.since_minute, request.until_minute)
+        {
+            Ok(entries) => Envelope::success(entries),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn tag_list(&self) -> Envelope<Vec<TagUsage>> {
+        Envelope::success(self.service.list_tags())
+    }
+
+    pub fn tag_rename(&mut self, request: RenameTagCommand) -> Envelope<TagUsage> {
+        match self
+            .service
+            .rename_tag(&request.from, &request.to, request.now_minute)
+        {
+            Ok(usage) => Envelope::success(usage),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+
+    pub fn tag_merge(&mut self, request: MergeTagsCommand) -> Envelope<TagUsage> {
+        match self
+            .service
+            .merge_tags(&request.sources, &request.target, request.now_minute)
+        {
+            Ok(usage) => Envelope::success(usage),
+            Err(error) => Envelope::failure(error),
+        }
+    }
+}
+
+fn map_error(error: AppError) -> CommandError {
+    match error {
+        AppError::Validation(message) => CommandError {
+            code: "E_VALIDATION",
+            message,
+            detail: None,
+        },
+        AppError::NotFound(message) => CommandError {
+            code: "E_NOT_FOUND",
+            message,
+            detail: None,
+        },
+        AppError::Conflict(message) => CommandError {
+            code: "E_CONFLICT",
+            message,
+            detail: None,
+        },
+        AppError::Internal(message) if message.contains("csv") => CommandError {
+            code: "E_CSV_PARSE",
+            message,
+            detail: None,
+        },
+        AppError::Internal(message) if message.contains("read") || message.contains("write") => {
+            CommandError {
+                code: "E_IO",
+                message,
+                detail: None,
+            }
+        }
+        AppError::Internal(message) => CommandError {
+            code: "E_INTERNAL",
+            message,
+            detail: None,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::{
+        ArchiveTimerCommand, CommandApi, CreateMarkCommand, CreateMarkFromTemplateCommand,
+        CreateProjectCommand, CreateTemplateCommand, CreateTimerCommand, CreateTodoCommand,
+        DailyStatsCommand, DeleteTimerCommand, DeleteTodoCommand, EntityHistoryCommand,
+        ExportDataCommand, ExportIcsCommand, ExportMarkdownCommand, ExportTodoTxtCommand,
+        ExportWarriorCommand, ImportDataCommand, ImportTimeEntriesCommand, ImportTodoTxtCommand,
+        ImportWarriorCommand, ListMarksByTagCommand, PageByTimerCommand, PollRemindersCommand,
+        QueryCommand, SearchCommand, SetTimerProjectCommand, TimelineCommand,
+        UpdateTodoStatusCommand,
+    };
+    use crate::dataset::ImportMode;
+    use crate::model::TodoStatus;
     use crate::repository::InMemoryStore;
     use crate::service::AppService;
+    use crate::timeline::TimelineKind;
+
+    #[test]
+    fn tests_returns_success_envelope_for_timer_create() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
+
+        let response = command_api.timer_create(CreateTimerCommand {
+            name: "phase3".to_string(),
+            target_at_minute: 300,
+            now_minute: 100,
+        });
+
+        assert!(response.ok);
+        assert!(response.error.is_none());
+        assert_eq!(response.data.expect("data should exist").name, "phase3");
+    }
+
+    #[test]
+    fn tests_maps_not_found_for_mark_create() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
+
+        let response = command_api.mark_create(CreateMarkCommand {
+            timer_id: "missing".to_string(),
+            marked_at_minute: 100,
+            description: "orphan mark".to_string(),
+            todo_ids: vec![],
+            tags: vec![],
+            category_id: None,
+        });
+
+        assert!(!response.ok);
+        let error = response.error.expect("error should exist");
+        assert_eq!(error.code, "E_NOT_FOUND");
+    }
+
+    #[test]
+    fn tests_maps_validation_error_for_empty_timer_name() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
+
+        let response = command_api.timer_create(CreateTimerCommand {
+            name: "  ".to_string(),
+            target_at_minute: 200,
+            now_minute: 100,
+        });
+
+        assert!(!response.ok);
+        let error = response.error.expect("error should exist");
+        assert_eq!(error.code, "E_VALIDATION");
+    }
+
+    #[test]
+    fn tests_drives_todo_and_archive_flow() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
+
+        let timer = command_api
+            .timer_create(CreateTimerCommand {
+                name: "flow".to_string(),
+                target_at_minute: 500,
+                now_minute: 100,
+            })
+            .data
+            .expect("timer should exist");
+
+        let todo_response = command_api.todo_create(CreateTodoCommand {
+            timer_id: timer.id.clone(),
+            title: "link todo".to_string(),
+            now_minute: 110,
+        });
+        assert!(todo_response.ok);
+
+        let archive_response = command_api.timer_archive(ArchiveTimerCommand {
+            timer_id: timer.id,
+            now_minute: 120,
+        });
+        assert!(archive_response.ok);
+    }
+
+    #[test]
+    fn tests_deletes_todo_successfully() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
+
+        let timer = command_api
+            .timer_create(CreateTimerCommand {
+                name: "flow".to_string(),
+                target_at_minute: 500,
+                now_minute: 100,
+            })
+            .data
+            .expect("timer should exist");
+
+        let todo = command_api
+            .todo_create(CreateTodoCommand {
+                timer_id: timer.id,
+                title: "delete me".to_string(),
+                now_minute: 110,
+            })
+            .data
+            .expect("todo should exist");
+
+        let delete_response = command_api.todo_delete(DeleteTodoCommand { todo_id: todo.id });
+        assert!(delete_response.ok);
+        assert!(delete_response.error.is_none());
+    }
+
+    #[test]
+    fn tests_maps_conflict_for_guarded_timer_delete() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
+
+        let timer = command_api
+            .timer_create(CreateTimerCommand {
+                name: "flow".to_string(),
+                target_at_minute: 500,
+                now_minute: 100,
+            })
+            .data
+            .expect("timer should exist");
+        command_api.todo_create(CreateTodoCommand {
+            timer_id: timer.id.clone(),
+            title: "keep me".to_string(),
+            now_minute: 110,
+        });
+
+        let refused = command_api.timer_delete(DeleteTimerCommand {
+            timer_id: timer.id.clone(),
+            cascade: false,
+        });
+        assert!(!refused.ok);
+        assert_eq!(
+            refused.error.expect("error should exist").code,
+            "E_CONFLICT"
+        );
+
+        let cascaded = command_api.timer_delete(DeleteTimerCommand {
+            timer_id: timer.id,
+            cascade: true,
+        });
+        assert!(cascaded.ok);
+    }
+
+    #[test]
+    fn tests_filters_timer_list_by_project() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
+
+        let project = command_api
+            .project_create(CreateProjectCommand {
+                name: "release".to_string(),
+                now_minute: 100,
+            })
+            .data
+            .expect("project should exist");
+        let timer = command_api
+            .timer_create(CreateTimerCommand {
+                name: "ship".to_string(),
+                target_at_minute: 500,
+                now_minute: 100,
+            })
+            .data
+            .expect("timer should exist");
+
+        let assigned = command_api.timer_set_project(SetTimerProjectCommand {
+            timer_id: timer.id.clone(),
+            project_id: Some(project.id.clone()),
+            now_minute: 110,
+        });
+        assert!(assigned.ok);
+
+        let listed = command_api
+            .timer_list_by_project(Some(&project.id), false)
+            .data
+            .expect("timers should exist");
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, timer.id);
+
+        let missing = command_api.timer_set_project(SetTimerProjectCommand {
+            timer_id: timer.id,
+            project_id: Some("project-missing".to_string()),
+            now_minute: 120,
+        });
+        assert_eq!(
+            missing.error.expect("error should exist").code,
+            "E_NOT_FOUND"
+        );
+    }
+
+    #[test]
+    fn tests_rejects_invalid_tag_on_mark_create() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
+
+        let timer = command_api
+            .timer_create(CreateTimerCommand {
+                name: "flow".to_string(),
+                target_at_minute: 500,
+                now_minute: 100,
+            })
+            .data
+            .expect("timer should exist");
+
+        let rejected = command_api.mark_create(CreateMarkCommand {
+            timer_id: timer.id.clone(),
+            marked_at_minute: 110,
+            description: "bad tag".to_string(),
+            todo_ids: vec![],
+            tags: vec!["two words".to_string()],
+            category_id: None,
+        });
+        assert_eq!(
+            rejected.error.expect("error should exist").code,
+            "E_VALIDATION"
+        );
+
+        command_api.mark_create(CreateMarkCommand {
+            timer_id: timer.id,
+            marked_at_minute: 120,
+            description: "ready for #review".to_string(),
+            todo_ids: vec![],
+            tags: vec!["backend".to_string()],
+            category_id: None,
+        });
+        let tagged = command_api
+            .mark_list_by_tag(ListMarksByTagCommand {
+                tag: "backend".to_string(),
+                since_minute: None,
+                until_minute: None,
+            })
+            .data
+            .expect("marks should exist");
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].tags, vec!["backend", "review"]);
+    }
 
     #[test]
-    fn tests_returns_success_envelope_for_timer_create() {
+    fn tests_polls_reminders_once_per_threshold() {
         let service = AppService::new(InMemoryStore::default());
         let mut command_api = CommandApi::new(service);
 
-        let response = command_api.timer_create(CreateTimerCommand {
-            name: "phase3".to_string(),
-            target_at_minute: 300,
+        command_api.timer_create(CreateTimerCommand {
+            name: "release".to_string(),
+            target_at_minute: 500,
             now_minute: 100,
         });
 
-        assert!(response.ok);
-        assert!(response.error.is_none());
-        assert_eq!(response.data.expect("data should exist").name, "phase3");
+        let first = command_api
+            .reminder_poll(PollRemindersCommand {
+                now_minute: 445,
+                offsets_minutes: Some(vec![60]),
+            })
+            .data
+            .expect("events should exist");
+        assert_eq!(first.len(), 1);
+
+        let repeated = command_api
+            .reminder_poll(PollRemindersCommand {
+                now_minute: 446,
+                offsets_minutes: Some(vec![60]),
+            })
+            .data
+            .expect("events should exist");
+        assert!(repeated.is_empty());
+
+        let invalid = command_api.reminder_poll(PollRemindersCommand {
+            now_minute: 446,
+            offsets_minutes: Some(vec![-1]),
+        });
+        assert_eq!(
+            invalid.error.expect("error should exist").code,
+            "E_VALIDATION"
+        );
     }
 
     #[test]
-    fn tests_maps_not_found_for_mark_create() {
+    fn tests_creates_mark_from_saved_template() {
         let service = AppService::new(InMemoryStore::default());
         let mut command_api = CommandApi::new(service);
 
-        let response = command_api.mark_create(CreateMarkCommand {
-            timer_id: "missing".to_string(),
-            marked_at_minute: 100,
-            description: "orphan mark".to_string(),
-            todo_ids: vec![],
+        let timer = command_api
+            .timer_create(CreateTimerCommand {
+                name: "release".to_string(),
+                target_at_minute: 5_000,
+                now_minute: 100,
+            })
+            .data
+            .expect("timer should exist");
+        let invalid = command_api.template_create(CreateTemplateCommand {
+            name: "broken".to_string(),
+            body: "{timer} {mood}".to_string(),
+            now_minute: 100,
         });
+        assert_eq!(
+            invalid.error.expect("error should exist").code,
+            "E_VALIDATION"
+        );
 
-        assert!(!response.ok);
-        let error = response.error.expect("error should exist");
-        assert_eq!(error.code, "E_NOT_FOUND");
+        let template = command_api
+            .template_create(CreateTemplateCommand {
+                name: "standup".to_string(),
+                body: "{timer}: {duration}".to_string(),
+                now_minute: 100,
+            })
+            .data
+            .expect("template should exist");
+        assert_eq!(
+            command_api.template_list().data.expect("list should exist"),
+            vec![template.clone()]
+        );
+
+        let mark = command_api
+            .mark_create_from_template(CreateMarkFromTemplateCommand {
+                timer_id: timer.id.clone(),
+                template_id: template.id.clone(),
+                marked_at_minute: 120,
+            })
+            .data
+            .expect("mark should exist");
+        assert_eq!(mark.description, "release: -");
+
+        let missing = command_api.mark_create_from_template(CreateMarkFromTemplateCommand {
+            timer_id: timer.id,
+            template_id: "template-404".to_string(),
+            marked_at_minute: 130,
+        });
+        assert_eq!(
+            missing.error.expect("error should exist").code,
+            "E_NOT_FOUND"
+        );
     }
 
     #[test]
-    fn tests_maps_validation_error_for_empty_timer_name() {
+    fn tests_undoes_and_redoes_todo_delete() {
         let service = AppService::new(InMemoryStore::default());
         let mut command_api = CommandApi::new(service);
 
-        let response = command_api.timer_create(CreateTimerCommand {
-            name: "  ".to_string(),
-            target_at_minute: 200,
-            now_minute: 100,
+        let empty = command_api.undo();
+        assert_eq!(
+            empty.error.expect("error should exist").code,
+            "E_VALIDATION"
+        );
+
+        let timer = command_api
+            .timer_create(CreateTimerCommand {
+                name: "release".to_string(),
+                target_at_minute: 5_000,
+                now_minute: 100,
+            })
+            .data
+            .expect("timer should exist");
+        let todo = command_api
+            .todo_create(CreateTodoCommand {
+                timer_id: timer.id.clone(),
+                title: "ship".to_string(),
+                now_minute: 110,
+            })
+            .data
+            .expect("todo should exist");
+        command_api.todo_delete(DeleteTodoCommand {
+            todo_id: todo.id.clone(),
         });
 
-        assert!(!response.ok);
-        let error = response.error.expect("error should exist");
-        assert_eq!(error.code, "E_VALIDATION");
+        let step = command_api.undo().data.expect("undo should succeed");
+        assert_eq!(step.label, "delete todo");
+        assert_eq!(step.status.redo_depth, 1);
+        assert_eq!(
+            command_api
+                .todo_list_by_timer(&timer.id)
+                .data
+                .expect("todos should exist"),
+            vec![todo]
+        );
+
+        command_api.redo().data.expect("redo should succeed");
+        assert!(command_api
+            .todo_list_by_timer(&timer.id)
+            .data
+            .expect("todos should exist")
+            .is_empty());
+        assert_eq!(
+            command_api
+                .history_status()
+                .data
+                .expect("status should exist")
+                .undo_label
+                .as_deref(),
+            Some("delete todo")
+        );
     }
 
     #[test]
-    fn tests_drives_todo_and_archive_flow() {
+    fn tests_maps_validation_error_for_invalid_stats_range() {
         let service = AppService::new(InMemoryStore::default());
-        let mut command_api = CommandApi::new(service);
+        let command_api = CommandApi::new(service);
+
+        let response = command_api.stats_daily(DailyStatsCommand {
+            from_date: "2026-10-01".to_string(),
+            to_date: "2026-09-30".to_string(),
+            utc_offset_minutes: 0,
+        });
+        assert_eq!(
+            response.error.expect("error should exist").code,
+            "E_VALIDATION"
+        );
+
+        let response = command_api.stats_daily(DailyStatsCommand {
+            from_date: "2026-10-01".to_string(),
+            to_date: "2026-10-07".to_string(),
+            utc_offset_minutes: 480,
+        });
+        assert_eq!(response.data.expect("stats should exist").days.len(), 7);
+    }
 
+    #[test]
+    fn tests_lists_entity_history_and_rejects_unknown_type() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
         let timer = command_api
             .timer_create(CreateTimerCommand {
-                name: "flow".to_string(),
-                target_at_minute: 500,
+                name: "release".to_string(),
+                target_at_minute: 5_000,
                 now_minute: 100,
             })
             .data
-            .expect("timer should exist");
+            .expect("timer should be created");
 
-        let todo_response = command_api.todo_create(CreateTodoCommand {
+        let response = command_api.event_history(EntityHistoryCommand {
+            entity_type: "timer".to_string(),
+            entity_id: timer.id.clone(),
+        });
+        assert_eq!(response.data.expect("history should exist").len(), 1);
+
+        let response = command_api.event_history(EntityHistoryCommand {
+            entity_type: "widget".to_string(),
+            entity_id: timer.id,
+        });
+        assert_eq!(
+            response.error.expect("error should exist").code,
+            "E_VALIDATION"
+        );
+    }
+
+    #[test]
+    fn tests_searches_marks_and_rejects_blank_text() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
+        let timer = command_api
+            .timer_create(CreateTimerCommand {
+                name: "release".to_string(),
+                target_at_minute: 5_000,
+                now_minute: 100,
+            })
+            .data
+            .expect("timer should be created");
+        command_api.mark_create(CreateMarkCommand {
             timer_id: timer.id.clone(),
-            title: "link todo".to_string(),
-            now_minute: 110,
+            marked_at_minute: 110,
+            description: "修复登录问题".to_string(),
+            todo_ids: vec![],
+            tags: vec![],
+            category_id: None,
         });
-        assert!(todo_response.ok);
 
-        let archive_response = command_api.timer_archive(ArchiveTimerCommand {
-            timer_id: timer.id,
+        let search = |text: &str| SearchCommand {
+            text: text.to_string(),
+            timer_id: Some(timer.id.clone()),
+            since_minute: None,
+            until_minute: None,
+            status: None,
+            limit: None,
+        };
+        let hits = command_api
+            .search(search("登录"))
+            .data
+            .expect("hits should exist");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].snippet, "修复登录问题");
+
+        assert_eq!(
+            command_api
+                .search(search(" "))
+                .error
+                .expect("error should exist")
+                .code,
+            "E_VALIDATION"
+        );
+    }
+
+    #[test]
+    fn tests_runs_query_expression_and_maps_parse_errors() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
+        let timer = command_api
+            .timer_create(CreateTimerCommand {
+                name: "Release".to_string(),
+                target_at_minute: 2_000,
+                now_minute: 100,
+            })
+            .data
+            .expect("timer should be created");
+        for title in ["ship #backend", "write notes"] {
+            command_api.todo_create(CreateTodoCommand {
+                timer_id: timer.id.clone(),
+                title: title.to_string(),
+                now_minute: 110,
+            });
+        }
+
+        let results = command_api
+            .query(QueryCommand {
+                expression: r#"timer:"release" status:open due<2d tag:backend"#.to_string(),
+                now_minute: 120,
+                utc_offset_minutes: 0,
+            })
+            .data
+            .expect("results should exist");
+        assert_eq!(results.todos.len(), 1);
+        assert_eq!(results.todos[0].title, "ship #backend");
+
+        let response = command_api.query(QueryCommand {
+            expression: "status:".to_string(),
             now_minute: 120,
+            utc_offset_minutes: 0,
         });
-        assert!(archive_response.ok);
+        assert_eq!(
+            response.error.expect("error should exist").code,
+            "E_VALIDATION"
+        );
     }
 
     #[test]
-    fn tests_deletes_todo_successfully() {
+    fn tests_pages_marks_newest_first_with_cursor() {
         let service = AppService::new(InMemoryStore::default());
         let mut command_api = CommandApi::new(service);
+        let timer = command_api
+            .timer_create(CreateTimerCommand {
+                name: "release".to_string(),
+                target_at_minute: 5_000,
+                now_minute: 100,
+            })
+            .data
+            .expect("timer should be created");
+        for minute in [110, 120, 130, 140, 150] {
+            command_api.mark_create(CreateMarkCommand {
+                timer_id: timer.id.clone(),
+                marked_at_minute: minute,
+                description: format!("at {minute}"),
+                todo_ids: vec![],
+                tags: vec![],
+                category_id: None,
+            });
+        }
+
+        let request = |cursor: Option<String>| PageByTimerCommand {
+            timer_id: timer.id.clone(),
+            since_minute: Some(120),
+            until_minute: None,
+            cursor,
+            offset: 0,
+            limit: Some(3),
+            newest_first: true,
+        };
+        let first = command_api
+            .mark_page_by_timer(request(None))
+            .data
+            .expect("page should exist");
+        let minutes: Vec<i64> = first
+            .items
+            .iter()
+            .map(|mark| mark.marked_at_minute)
+            .collect();
+        assert_eq!(minutes, vec![150, 140, 130]);
+        assert_eq!(first.total, 4);
+
+        let second = command_api
+            .mark_page_by_timer(request(first.next_cursor))
+            .data
+            .expect("page should exist");
+        assert_eq!(second.items.len(), 1);
+        assert_eq!(second.items[0].marked_at_minute, 120);
+        assert_eq!(second.next_cursor, None);
+
+        let response = command_api.todo_page_by_timer(PageByTimerCommand {
+            limit: Some(0),
+            ..request(None)
+        });
+        assert_eq!(
+            response.error.expect("error should exist").code,
+            "E_VALIDATION"
+        );
+    }
 
+    #[test]
+    fn tests_builds_timeline_with_archive_time_from_event_log() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
         let timer = command_api
             .timer_create(CreateTimerCommand {
-                name: "flow".to_string(),
-                target_at_minute: 500,
+                name: "release".to_string(),
+                target_at_minute: 5_000,
                 now_minute: 100,
             })
             .data
-            .expect("timer should exist");
+            .expect("timer should be created");
+        command_api.todo_create(CreateTodoCommand {
+            timer_id: timer.id.clone(),
+            title: "ship".to_string(),
+            now_minute: 110,
+        });
+        command_api.timer_archive(ArchiveTimerCommand {
+            timer_id: timer.id.clone(),
+            now_minute: 120,
+        });
 
-        let todo = command_api
+        let entries = command_api
+            .timeline(TimelineCommand {
+                since_minute: 105,
+                until_minute: 200,
+            })
+            .data
+            .expect("timeline should exist");
+        let kinds: Vec<(i64, TimelineKind)> = entries
+            .iter()
+            .map(|entry| (entry.at_minute, entry.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                (110, TimelineKind::TodoCreated),
+                (120, TimelineKind::TimerArchived)
+            ]
+        );
+
+        let response = command_api.timeline(TimelineCommand {
+            since_minute: 200,
+            until_minute: 100,
+        });
+        assert_eq!(
+            response.error.expect("error should exist").code,
+            "E_VALIDATION"
+        );
+    }
+
+    #[test]
+    fn tests_exports_markdown_for_single_timer() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
+        let timer = command_api
+            .timer_create(CreateTimerCommand {
+                name: "release".to_string(),
+                target_at_minute: 5_000,
+                now_minute: 0,
+            })
+            .data
+            .expect("timer should be created");
+        for (minute, description) in [(60, "start"), (150, "shipped")] {
+            command_api.mark_create(CreateMarkCommand {
+                timer_id: timer.id.clone(),
+                marked_at_minute: minute,
+                description: description.to_string(),
+                todo_ids: vec![],
+                tags: vec![],
+                category_id: None,
+            });
+        }
+
+        let request = |timer_id: &str| ExportMarkdownCommand {
+            timer_id: Some(timer_id.to_string()),
+            from_date: "1970-01-01".to_string(),
+            to_date: "1970-01-01".to_string(),
+            utc_offset_minutes: 0,
+        };
+        let markdown = command_api
+            .export_markdown(request(&timer.id))
+            .data
+            .expect("markdown should exist");
+        assert!(markdown.contains("- 01:00–02:30（1小时30分） **release**：shipped\n"));
+        assert!(markdown.ends_with("**总计：1小时30分**\n"));
+
+        assert_eq!(
+            command_api
+                .export_markdown(request("timer-404"))
+                .error
+                .expect("error should exist")
+                .code,
+            "E_NOT_FOUND"
+        );
+    }
+
+    #[test]
+    fn tests_exports_ics_for_active_timers() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
+        let mut timer_ids = Vec::new();
+        for name in ["release", "retired"] {
+            let timer = command_api
+                .timer_create(CreateTimerCommand {
+                    name: name.to_string(),
+                    target_at_minute: 5_000,
+                    now_minute: 0,
+                })
+                .data
+                .expect("timer should be created");
+            for minute in [60, 150] {
+                command_api.mark_create(CreateMarkCommand {
+                    timer_id: timer.id.clone(),
+                    marked_at_minute: minute,
+                    description: name.to_string(),
+                    todo_ids: vec![],
+                    tags: vec![],
+                    category_id: None,
+                });
+            }
+            timer_ids.push(timer.id);
+        }
+        command_api.timer_archive(ArchiveTimerCommand {
+            timer_id: timer_ids[1].clone(),
+            now_minute: 200,
+        });
+
+        let request = |offsets_minutes| ExportIcsCommand {
+            timer_id: None,
+            include_archived: false,
+            offsets_minutes,
+        };
+        let ics = command_api
+            .export_ics(request(Some(vec![15])))
+            .data
+            .expect("ics should exist");
+        assert!(ics.contains(&format!("UID:{}@countdown-todo\r\n", timer_ids[0])));
+        assert!(!ics.contains(&format!("UID:{}@countdown-todo\r\n", timer_ids[1])));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("DTSTART:19700101T010000Z\r\nDTEND:19700101T023000Z\r\n"));
+        assert!(ics.contains("TRIGGER:-PT15M\r\n"));
+
+        assert_eq!(
+            command_api
+                .export_ics(request(Some(vec![-5])))
+                .error
+                .expect("error should exist")
+                .code,
+            "E_VALIDATION"
+        );
+    }
+
+    #[test]
+    fn tests_imports_and_exports_todotxt() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
+        let timer = command_api
+            .timer_create(CreateTimerCommand {
+                name: "release".to_string(),
+                target_at_minute: 50_000,
+                now_minute: 0,
+            })
+            .data
+            .expect("timer should be created");
+
+        let text = "(A) 1970-01-02 write notes +docs\nx 1970-01-03 1970-01-02 ship it\n";
+        let request = |text: &str| ImportTodoTxtCommand {
+            timer_id: timer.id.clone(),
+            text: text.to_string(),
+            utc_offset_minutes: 0,
+            now_minute: 5_000,
+        };
+        let imported = command_api
+            .todo_import_todotxt(request(text))
+            .data
+            .expect("todos should be imported");
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].tags, vec!["docs", "pri-a"]);
+        assert_eq!(imported[1].status, TodoStatus::Done);
+        assert_eq!(imported[1].done_at_minute, Some(2 * 24 * 60));
+
+        let exported = command_api
+            .todo_export_todotxt(ExportTodoTxtCommand {
+                timer_id: timer.id.clone(),
+                utc_offset_minutes: 0,
+            })
+            .data
+            .expect("todo.txt should export");
+        assert_eq!(exported, text);
+
+        let failed = command_api.todo_import_todotxt(request("fine\nx\n"));
+        assert_eq!(
+            failed.error.expect("error should exist").code,
+            "E_VALIDATION"
+        );
+        assert_eq!(
+            command_api
+                .todo_list_by_timer(&timer.id)
+                .data
+                .expect("todos should list")
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn tests_imports_time_entries_into_existing_and_new_timers() {
+        let service = AppService::new(InMemoryStore::default());
+        let mut command_api = CommandApi::new(service);
+        let timer = command_api
+            .timer_create(CreateTimerCommand {
+                name: "Website".to_string(),
+                target_at_minute: 50_000,
+                now_minute: 0,
+            })
+            .data
+            .expect("timer should be created");
+
+        let report = command_api
+            .import_time_entries(ImportTimeEntriesCommand {
+                text: "Project,Description,Start Date,Start Time,End Date,End Time\n\
+                       Website,layout,01/01/1970,09:00 AM,01/01/1970,10:00 AM\n\
+                       ,email,01/01/1970,01:00 PM,01/01/1970,01:20 PM\n\
+                       Website,broken,01/01/1970,later,01/01/1970,11:00 AM\n"
+                    .to_string(),
+                utc_offset_minutes: 0,
+                now_minute: 2_000,
+            })
+            .data
+            .expect("time entries should import");
+        assert_eq!(report.marks[0].timer_id, timer.id);
+        assert_eq!(report.marks[0].duration_minutes, Some(60));
+        assert_eq!(report.created_timers.len(), 1);
+        assert_eq!(report.created_timers[0].name, "无项目");
+        assert_eq!(report.created_timers[0].target_at_minute, 13 * 60 + 20);
+        assert_eq!(report.skipped.len(), 1);
+        assert_eq!(report.skipped[0].row, 4);
+
+        let missing_columns = command_api.import_time_entries(ImportTimeEntriesCommand {
+            text: "Project,Description\nWebsite,x\n".to_string(),
+            utc_offset_minutes: 0,
+            now_minute: 2_000,
+        });
+        assert_eq!(
+            missing_columns.error.expect("error should exist").code,
+            "E_VALIDATION"
+        );
+    }
+
+    #[test]
+    fn tests_moves_todos_and_segments_through_warrior_formats() {
+        let mut source = CommandApi::new(AppService::new(InMemoryStore::default()));
+        let timer = source
+            .timer_create(CreateTimerCommand {
+                name: "release".to_string(),
+                target_at_minute: 50_000,
+                now_minute: 0,
+            })
+            .data
+            .expect("timer should be created");
+        let todo = source
             .todo_create(CreateTodoCommand {
-                timer_id: timer.id,
-                title: "delete me".to_string(),
-                now_minute: 110,
+                timer_id: timer.id.clone(),
+                title: "ship #ops".to_string(),
+                now_minute: 10,
             })
             .data
-            .expect("todo should exist");
+            .expect("todo should be created");
+        source.todo_update_status(UpdateTodoStatusCommand {
+            todo_id: todo.id.clone(),
+            status: TodoStatus::Done,
+            now_minute: 200,
+        });
+        for (minute, description) in [(60, "start"), (150, "shipped #ops")] {
+            source.mark_create(CreateMarkCommand {
+                timer_id: timer.id.clone(),
+                marked_at_minute: minute,
+                description: description.to_string(),
+                todo_ids: vec![],
+                tags: vec![],
+                category_id: None,
+            });
+        }
+        let export = ExportWarriorCommand { timer_id: None };
+        let tasks = source
+            .export_taskwarrior(export.clone())
+            .data
+            .expect("tasks should export");
+        let intervals = source
+            .export_timewarrior(export)
+            .data
+            .expect("intervals should export");
 
-        let delete_response = command_api.todo_delete(DeleteTodoCommand { todo_id: todo.id });
-        assert!(delete_response.ok);
-        assert!(delete_response.error.is_none());
+        let mut target = CommandApi::new(AppService::new(InMemoryStore::default()));
+        let request = |text: &str| ImportWarriorCommand {
+            timer_id: None,
+            text: text.to_string(),
+            now_minute: 1_000,
+        };
+        let imported_tasks = target
+            .import_taskwarrior(request(&tasks))
+            .data
+            .expect("tasks should import");
+        assert_eq!(imported_tasks.created_timers.len(), 1);
+        assert_eq!(imported_tasks.created_timers[0].name, "release");
+        assert_eq!(imported_tasks.created_timers[0].target_at_minute, 50_000);
+        let imported_todo = &imported_tasks.todos[0];
+        assert_eq!(imported_todo.title, "ship #ops");
+        assert_eq!(imported_todo.status, TodoStatus::Done);
+        assert_eq!(imported_todo.done_at_minute, Some(200));
+        assert_eq!(imported_todo.tags, vec!["ops"]);
+
+        let imported_marks = target
+            .import_timewarrior(request(&intervals))
+            .data
+            .expect("intervals should import");
+        assert!(imported_marks.created_timers.is_empty());
+        assert_eq!(imported_marks.marks.len(), 1);
+        let mark = &imported_marks.marks[0];
+        assert_eq!(mark.timer_id, imported_tasks.created_timers[0].id);
+        assert_eq!(
+            (mark.prev_marked_at_minute, mark.marked_at_minute),
+            (Some(60), 150)
+        );
+        assert_eq!(mark.description, "shipped #ops");
+        assert_eq!(mark.tags, vec!["ops"]);
+
+        let again = source
+            .import_taskwarrior(request(&tasks))
+            .data
+            .expect("re-import should succeed");
+        assert!(again.todos.is_empty());
+        assert_eq!(again.skipped[0].reason, format!("duplicate of {}", todo.id));
+    }
+
+    #[test]
+    fn tests_round_trips_data_export_and_merges_with_remapped_ids() {
+        let mut source = CommandApi::new(AppService::new(InMemoryStore::default()));
+        let timer = source
+            .timer_create(CreateTimerCommand {
+                name: "release".to_string(),
+                target_at_minute: 50_000,
+                now_minute: 0,
+            })
+            .data
+            .expect("timer should be created");
+        let todo = source
+            .todo_create(CreateTodoCommand {
+                timer_id: timer.id.clone(),
+                title: "ship".to_string(),
+                now_minute: 10,
+            })
+            .data
+            .expect("todo should be created");
+        let mark = |api: &mut CommandApi<InMemoryStore>, minute: i64, todo_ids: Vec<String>| {
+            api.mark_create(CreateMarkCommand {
+                timer_id: timer.id.clone(),
+                marked_at_minute: minute,
+                description: format!("at {minute}"),
+                todo_ids,
+                tags: vec![],
+                category_id: None,
+            })
+            .data
+            .expect("mark should be created")
+        };
+        mark(&mut source, 60, vec![]);
+        mark(&mut source, 150, vec![todo.id.clone()]);
+        let export = source
+            .export_data(ExportDataCommand { now_minute: 200 })
+            .data
+            .expect("data should export");
+
+        let mut target = CommandApi::new(AppService::new(InMemoryStore::default()));
+        let replaced = target
+            .import_data(ImportDataCommand {
+                text: export.clone(),
+                mode: ImportMode::Replace,
+            })
+            .data
+            .expect("replace should succeed");
+        assert_eq!(replaced.imported, 4);
+        assert_eq!(
+            target
+                .export_data(ExportDataCommand { now_minute: 200 })
+                .data
+                .as_deref(),
+            Some(export.as_str())
+        );
+
+        let diverged = mark(&mut target, 300, vec![]);
+        mark(&mut source, 250, vec![]);
+        let theirs = target
+            .export_data(ExportDataCommand { now_minute: 400 })
+            .data
+            .expect("data should export");
+        let merged = source
+            .import_data(ImportDataCommand {
+                text: theirs,
+                mode: ImportMode::Merge,
+            })
+            .data
+            .expect("merge should succeed");
+        assert_eq!((merged.imported, merged.unchanged), (1, 4));
+        assert_eq!(merged.remapped.len(), 1);
+        assert_eq!(merged.remapped[0].from, diverged.id);
+
+        let marks = source
+            .mark_list_by_timer(&timer.id)
+            .data
+            .expect("marks should list");
+        let last = marks
+            .iter()
+            .find(|mark| mark.id == merged.remapped[0].to)
+            .expect("remapped mark should exist");
+        assert_eq!(last.prev_marked_at_minute, Some(250));
+        assert_eq!(last.duration_minutes, Some(50));
+
+        let broken = source.import_data(ImportDataCommand {
+            text: "{\"format\":\"countdown-todo\",\"version\":9,\"exported_at_minute\":0}"
+                .to_string(),
+            mode: ImportMode::Merge,
+        });
+        assert_eq!(broken.error.map(|error| error.code), Some("E_VALIDATION"));
     }
 }
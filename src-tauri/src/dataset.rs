@@ -0,0 +1,308 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::model::{Category, EpochMinutes, Mark, MarkTemplate, Project, Timer, Todo};
+use crate::repository::StoreContents;
+
+pub const FORMAT: &str = "countdown-todo";
+pub const VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportMode {
+    Replace,
+    Merge,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RemappedId {
+    pub entity_type: String,
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DataImportReport {
+    pub mode: ImportMode,
+    pub imported: usize,
+    pub unchanged: usize,
+    pub relinked_marks: usize,
+    pub remapped: Vec<RemappedId>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Dataset {
+    format: String,
+    version: u32,
+    exported_at_minute: EpochMinutes,
+    #[serde(default)]
+    projects: Vec<Project>,
+    #[serde(default)]
+    categories: Vec<Category>,
+    #[serde(default)]
+    templates: Vec<MarkTemplate>,
+    #[serde(default)]
+    timers: Vec<Timer>,
+    #[serde(default)]
+    todos: Vec<Todo>,
+    #[serde(default)]
+    marks: Vec<Mark>,
+}
+
+pub fn render(contents: StoreContents, exported_at_minute: EpochMinutes) -> AppResult<String> {
+    let mut dataset = Dataset {
+        format: FORMAT.to_string(),
+        version: VERSION,
+        exported_at_minute,
+        projects: contents.projects,
+        categories: contents.categories,
+        templates: contents.templates,
+        timers: contents.timers,
+        todos: contents.todos,
+        marks: contents.marks,
+    };
+    sort_by_id(&mut dataset.projects, |project| &project.id);
+    sort_by_id(&mut dataset.categories, |category| &category.id);
+    sort_by_id(&mut dataset.templates, |template| &template.id);
+    sort_by_id(&mut dataset.timers, |timer| &timer.id);
+    sort_by_id(&mut dataset.todos, |todo| &todo.id);
+    sort_by_id(&mut dataset.marks, |mark| &mark.id);
+
+    let mut json = serde_json::to_string_pretty(&dataset)
+        .map_err(|error| AppError::Internal(format!("failed to encode export: {error}")))?;
+    json.push('\n');
+    Ok(json)
+}
+
+pub fn parse(text: &str) -> AppResult<StoreContents> {
+    let dataset: Dataset = serde_json::from_str(text.trim_start_matches('\u{feff}'))
+        .map_err(|error| AppError::Validation(format!("invalid export JSON: {error}")))?;
+    if dataset.format != FORMAT {
+        return Err(AppError::Validation(format!(
+            "unsupported export format '{}'",
+            dataset.format
+        )));
+    }
+    if dataset.version != VERSION {
+        return Err(AppError::Validation(format!(
+            "unsupported export version {}, expected {VERSION}",
+            dataset.version
+        )));
+    }
+
+    let contents = StoreContents {
+        timers: dataset.timers,
+        projects: dataset.projects,
+        categories: dataset.categories,
+        templates: dataset.templates,
+        todos: dataset.todos,
+        marks: dataset.marks,
+    };
+    validate(&contents)?;
+    Ok(contents)
+}
+
+pub fn validate(contents: &StoreContents) -> AppResult<()> {
+    let mut ids = HashSet::new();
+    let all_ids = contents
+        .projects
+        .iter()
+        .map(|project| &project.id)
+        .chain(contents.categories.iter().map(|category| &category.id))
+        .chain(contents.templates.iter().map(|template| &template.id))
+        .chain(contents.timers.iter().map(|timer| &timer.id))
+        .chain(contents.todos.iter().map(|todo| &todo.id))
+        .chain(contents.marks.iter().map(|mark| &mark.id));
+    for id in all_ids {
+        if id.trim().is_empty() {
+            return Err(AppError::Validation(
+                "export contains an empty id".to_string(),
+            ));
+        }
+        if !ids.insert(id.as_str()) {
+            return Err(AppError::Validation(format!("duplicate id {id}")));
+        }
+    }
+
+    let project_ids: HashSet<&str> = contents.projects.iter().map(|p| p.id.as_str()).collect();
+    let category_ids: HashSet<&str> = contents.categories.iter().map(|c| c.id.as_str()).collect();
+    let timer_ids: HashSet<&str> = contents.timers.iter().map(|t| t.id.as_str()).collect();
+    let todo_timers: HashMap<&str, &str> = contents
+        .todos
+        .iter()
+        .map(|todo| (todo.id.as_str(), todo.timer_id.as_str()))
+        .collect();
+    let missing = |what: &str, id: &str, owner: &str| {
+        AppError::Validation(format!("{owner} references missing {what} {id}"))
+    };
+
+    for timer in &contents.timers {
+        if let Some(project_id) = &timer.project_id {
+            if !project_ids.contains(project_id.as_str()) {
+                return Err(missing("project", project_id, &timer.id));
+            }
+        }
+    }
+    for todo in &contents.todos {
+        if !timer_ids.contains(todo.timer_id.as_str()) {
+            return Err(missing("timer", &todo.timer_id, &todo.id));
+        }
+    }
+    for mark in &contents.marks {
+        if !timer_ids.contains(mark.timer_id.as_str()) {
+            return Err(missing("timer", &mark.timer_id, &mark.id));
+        }
+        if let Some(category_id) = &mark.category_id {
+            if !category_ids.contains(category_id.as_str()) {
+                return Err(missing("category", category_id, &mark.id));
+            }
+        }
+        if let Some(todo_id) = mark
+            .todo_ids
+            .iter()
+            .find(|todo_id| todo_timers.get(todo_id.as_str()) != Some(&mark.timer_id.as_str()))
+        {
+            return Err(missing("todo", todo_id, &mark.id));
+        }
+        let consistent = match (mark.prev_marked_at_minute, mark.duration_minutes) {
+            (None, None) => true,
+            (Some(prev), Some(duration)) => {
+                prev <= mark.marked_at_minute && duration == mark.marked_at_minute - prev
+            }
+            _ => false,
+        };
+        if !consistent {
+            return Err(AppError::Validation(format!(
+                "{} has an inconsistent previous mark or duration",
+                mark.id
+            )));
+        }
+    }
+    Ok(())
+}
+
+pub fn chained_mark_ids(marks: &[Mark]) -> HashSet<String> {
+    let moments: HashSet<EpochMinutes> = marks.iter().map(|mark| mark.marked_at_minute).collect();
+    marks
+        .iter()
+        .filter(|mark| {
+            mark.prev_marked_at_minute
+                .is_none_or(|prev| prev != mark.marked_at_minute && moments.contains(&prev))
+        })
+        .map(|mark| mark.id.clone())
+        .collect()
+}
+
+pub fn relink_chain(marks: &mut [Mark], chained: &HashSet<String>) {
+    marks.sort_by(chain_order);
+    let mut previous: Option<EpochMinutes> = None;
+    for mark in marks.iter_mut() {
+        if chained.contains(&mark.id) {
+            mark.prev_marked_at_minute = previous;
+            mark.duration_minutes = previous.map(|prev| mark.marked_at_minute - prev);
+        }
+        previous = Some(mark.marked_at_minute);
+    }
+}
+
+fn chain_order(left: &Mark, right: &Mark) -> std::cmp::Ordering {
+    (left.marked_at_minute, id_key(&left.id)).cmp(&(right.marked_at_minute, id_key(&right.id)))
+}
+
+fn sort_by_id<T>(items: &mut [T], id: impl Fn(&T) -> &String) {
+    items.sort_by(|left, right| id_key(id(left)).cmp(&id_key(id(right))));
+}
+
+fn id_key(id: &str) -> (u64, &str) {
+    let sequence = id
+        .rsplit_once('-')
+        .and_then(|(_, suffix)| suffix.parse().ok())
+        .unwrap_or(u64::MAX);
+    (sequence, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{chained_mark_ids, parse, relink_chain, render};
+    use crate::model::{Mark, Timer};
+    use crate::repository::StoreContents;
+
+    fn mark(id: &str, marked: i64, prev: Option<i64>) -> Mark {
+        Mark {
+            id: id.to_string(),
+            timer_id: "timer-1".to_string(),
+            marked_at_minute: marked,
+            prev_marked_at_minute: prev,
+            duration_minutes: prev.map(|prev| marked - prev),
+            description: String::new(),
+            todo_ids: vec![],
+            tags: vec![],
+            category_id: None,
+        }
+    }
+
+    #[test]
+    fn round_trips_contents_and_rejects_broken_references() {
+        let contents = StoreContents {
+            timers: vec![Timer {
+                id: "timer-1".to_string(),
+                name: "release".to_string(),
+                target_at_minute: 100,
+                created_at_minute: 0,
+                updated_at_minute: 0,
+                archived: false,
+                project_id: None,
+                tags: vec![],
+            }],
+            marks: vec![mark("mark-10", 30, Some(10)), mark("mark-2", 10, None)],
+            ..StoreContents::default()
+        };
+        let json = render(contents.clone(), 50).expect("export should encode");
+        assert!(json.contains("\"version\": 1"));
+        let parsed = parse(&json).expect("export should parse");
+        assert_eq!(parsed.timers, contents.timers);
+        let ids: Vec<&str> = parsed.marks.iter().map(|mark| mark.id.as_str()).collect();
+        assert_eq!(ids, vec!["mark-2", "mark-10"]);
+
+        let error = parse(&json.replace("\"timer_id\": \"timer-1\"", "\"timer_id\": \"timer-9\""))
+            .expect_err("dangling timer should fail");
+        assert!(error.to_string().contains("missing timer timer-9"));
+        assert!(parse(&json.replace("\"version\": 1", "\"version\": 2")).is_err());
+        assert!(
+            parse(&json.replace("\"duration_minutes\": 20", "\"duration_minutes\": 5")).is_err()
+        );
+    }
+
+    #[test]
+    fn relinks_chained_marks_and_keeps_segments() {
+        let existing = vec![mark("mark-2", 10, None), mark("mark-3", 30, Some(10))];
+        let incoming = vec![mark("mark-7", 20, None), mark("mark-8", 50, Some(40))];
+        let mut chained = chained_mark_ids(&existing);
+        chained.extend(chained_mark_ids(&incoming));
+        assert!(!chained.contains("mark-8"));
+
+        let mut merged = [existing, incoming].concat();
+        relink_chain(&mut merged, &chained);
+        let links: Vec<(&str, Option<i64>, Option<i64>)> = merged
+            .iter()
+            .map(|mark| {
+                (
+                    mark.id.as_str(),
+                    mark.prev_marked_at_minute,
+                    mark.duration_minutes,
+                )
+            })
+            .collect();
+        assert_eq!(
+            links,
+            vec![
+                ("mark-2", None, None),
+                ("mark-7", Some(10), Some(10)),
+                ("mark-3", Some(20), Some(10)),
+                ("mark-8", Some(40), Some(10)),
+            ]
+        );
+    }
+}
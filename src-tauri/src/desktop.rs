@@ -1,19 +1,51 @@
 use std::path::PathBuf;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 use parking_lot::Mutex;
 use tauri::Manager;
 
+use countdown_todo_core::backup::{BackupInfo, BackupPolicy};
 use countdown_todo_core::command::{
-    ArchiveTimerCommand, CommandApi, CreateMarkCommand, CreateTimerCommand, CreateTodoCommand,
-    DeleteTodoCommand, Envelope, UpdateTimerCommand, UpdateTodoStatusCommand,
+    ArchiveTimerCommand, CategoryReportCommand, CommandApi, CreateCategoryCommand,
+    CreateMarkCommand, CreateMarkFromTemplateCommand, CreateProjectCommand, CreateTemplateCommand,
+    CreateTimerCommand, CreateTodoCommand, DailyStatsCommand, DeleteCategoryCommand,
+    DeleteProjectCommand, DeleteTemplateCommand, DeleteTimerCommand, DeleteTodoCommand,
+    EntityHistoryCommand, Envelope, ExportDataCommand, ExportIcsCommand, ExportMarkdownCommand,
+    ExportTodoTxtCommand, ExportWarriorCommand, ImportDataCommand, ImportTimeEntriesCommand,
+    ImportTodoTxtCommand, ImportWarriorCommand, ListMarksByTagCommand, MergeDataDirCommand,
+    MergeTagsCommand, PageByTimerCommand, PollRemindersCommand, QueryCommand,
+    RenameCategoryCommand, RenameProjectCommand, RenameTagCommand, RestoreBackupCommand,
+    SearchCommand, SetMarkCategoryCommand, SetTimerProjectCommand, SetTimerTagsCommand,
+    SetTodoTagsCommand, TimelineCommand, UnarchiveTimerCommand, UpdateTemplateCommand,
+    UpdateTimerCommand, UpdateTodoStatusCommand,
 };
-use countdown_todo_core::model::{Mark, Timer, Todo, TodoStatus};
+use countdown_todo_core::dataset::{DataImportReport, ImportMode};
+use countdown_todo_core::event::{DomainEvent, EventSubscriber, RebuildSummary};
+use countdown_todo_core::history::{HistoryStatus, HistoryStep};
+use countdown_todo_core::merge::MergeReport;
+use countdown_todo_core::model::{
+    Category, Mark, MarkTemplate, Project, ProjectSummary, TagUsage, Timer, Todo, TodoStatus,
+};
+use countdown_todo_core::page::Page;
+use countdown_todo_core::query::QueryResults;
+use countdown_todo_core::reminder::{ReminderEvent, ReminderKind, ReminderPolicy};
+use countdown_todo_core::report::CategoryReport;
 use countdown_todo_core::repository::CsvStore;
+use countdown_todo_core::search::SearchHit;
 use countdown_todo_core::service::AppService;
+use countdown_todo_core::stats::DailyStats;
+use countdown_todo_core::sync::SyncReport;
+use countdown_todo_core::taskwarrior::TaskImportReport;
+use countdown_todo_core::timeline::TimelineEntry;
+use countdown_todo_core::timesheet::TimesheetReport;
+
+const DOMAIN_EVENT_NAME: &str = "domain-event";
+const REMINDER_POLL_INTERVAL: Duration = Duration::from_secs(30);
 
 struct DesktopState {
     api: Mutex<CommandApi<CsvStore>>,
     data_dir: PathBuf,
+    reminder_policy: ReminderPolicy,
 }
 
 #[tauri::command]
@@ -66,6 +98,103 @@ fn timer_archive(
     })
 }
 
+#[tauri::command]
+fn timer_unarchive(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: String,
+    now_minute: i64,
+) -> Envelope<Timer> {
+    state.api.lock().timer_unarchive(UnarchiveTimerCommand {
+        timer_id,
+        now_minute,
+    })
+}
+
+#[tauri::command]
+fn timer_delete(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: String,
+    cascade: bool,
+) -> Envelope<Timer> {
+    state
+        .api
+        .lock()
+        .timer_delete(DeleteTimerCommand { timer_id, cascade })
+}
+
+#[tauri::command]
+fn timer_set_project(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: String,
+    project_id: Option<String>,
+    now_minute: i64,
+) -> Envelope<Timer> {
+    state.api.lock().timer_set_project(SetTimerProjectCommand {
+        timer_id,
+        project_id,
+        now_minute,
+    })
+}
+
+#[tauri::command]
+fn timer_list_by_project(
+    state: tauri::State<'_, DesktopState>,
+    project_id: Option<String>,
+    include_archived: bool,
+) -> Envelope<Vec<Timer>> {
+    state
+        .api
+        .lock()
+        .timer_list_by_project(project_id.as_deref(), include_archived)
+}
+
+#[tauri::command]
+fn project_create(
+    state: tauri::State<'_, DesktopState>,
+    name: String,
+    now_minute: i64,
+) -> Envelope<Project> {
+    state
+        .api
+        .lock()
+        .project_create(CreateProjectCommand { name, now_minute })
+}
+
+#[tauri::command]
+fn project_rename(
+    state: tauri::State<'_, DesktopState>,
+    project_id: String,
+    name: String,
+    now_minute: i64,
+) -> Envelope<Project> {
+    state.api.lock().project_rename(RenameProjectCommand {
+        project_id,
+        name,
+        now_minute,
+    })
+}
+
+#[tauri::command]
+fn project_delete(state: tauri::State<'_, DesktopState>, project_id: String) -> Envelope<Project> {
+    state
+        .api
+        .lock()
+        .project_delete(DeleteProjectCommand { project_id })
+}
+
+#[tauri::command]
+fn project_list(state: tauri::State<'_, DesktopState>) -> Envelope<Vec<Project>> {
+    state.api.lock().project_list()
+}
+
+#[tauri::command]
+fn project_summary_list(
+    state: tauri::State<'_, DesktopState>,
+    now_minute: i64,
+) -> Envelope<Vec<ProjectSummary>> {
+    state.api.lock().project_summary_list(now_minute)
+}
+
 #[tauri::command]
 fn todo_create(
     state: tauri::State<'_, DesktopState>,
@@ -88,6 +217,29 @@ fn todo_list_by_timer(
     state.api.lock().todo_list_by_timer(&timer_id)
 }
 
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn todo_page_by_timer(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: String,
+    since_minute: Option<i64>,
+    until_minute: Option<i64>,
+    cursor: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    newest_first: Option<bool>,
+) -> Envelope<Page<Todo>> {
+    state.api.lock().todo_page_by_timer(PageByTimerCommand {
+        timer_id,
+        since_minute,
+        until_minute,
+        cursor,
+        offset: offset.unwrap_or_default(),
+        limit,
+        newest_first: newest_first.unwrap_or_default(),
+    })
+}
+
 #[tauri::command]
 fn todo_update_status(
     state: tauri::State<'_, DesktopState>,
@@ -95,7 +247,7 @@ fn todo_update_status(
     status: String,
     now_minute: i64,
 ) -> Envelope<Todo> {
-    let Some(parsed_status) = TodoStatus::from_str(&status) else {
+    let Ok(parsed_status) = status.parse::<TodoStatus>() else {
         return Envelope {
             ok: false,
             data: None,
@@ -129,15 +281,205 @@ fn mark_create(
     marked_at_minute: i64,
     description: String,
     todo_ids: Vec<String>,
+    tags: Option<Vec<String>>,
+    category_id: Option<String>,
 ) -> Envelope<Mark> {
     state.api.lock().mark_create(CreateMarkCommand {
         timer_id,
         marked_at_minute,
         description,
         todo_ids,
+        tags: tags.unwrap_or_default(),
+        category_id,
     })
 }
 
+#[tauri::command]
+fn mark_create_from_template(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: String,
+    template_id: String,
+    marked_at_minute: i64,
+) -> Envelope<Mark> {
+    state
+        .api
+        .lock()
+        .mark_create_from_template(CreateMarkFromTemplateCommand {
+            timer_id,
+            template_id,
+            marked_at_minute,
+        })
+}
+
+#[tauri::command]
+fn template_create(
+    state: tauri::State<'_, DesktopState>,
+    name: String,
+    body: String,
+    now_minute: i64,
+) -> Envelope<MarkTemplate> {
+    state.api.lock().template_create(CreateTemplateCommand {
+        name,
+        body,
+        now_minute,
+    })
+}
+
+#[tauri::command]
+fn template_update(
+    state: tauri::State<'_, DesktopState>,
+    template_id: String,
+    name: String,
+    body: String,
+    now_minute: i64,
+) -> Envelope<MarkTemplate> {
+    state.api.lock().template_update(UpdateTemplateCommand {
+        template_id,
+        name,
+        body,
+        now_minute,
+    })
+}
+
+#[tauri::command]
+fn template_delete(
+    state: tauri::State<'_, DesktopState>,
+    template_id: String,
+) -> Envelope<MarkTemplate> {
+    state
+        .api
+        .lock()
+        .template_delete(DeleteTemplateCommand { template_id })
+}
+
+#[tauri::command]
+fn template_list(state: tauri::State<'_, DesktopState>) -> Envelope<Vec<MarkTemplate>> {
+    state.api.lock().template_list()
+}
+
+#[tauri::command]
+fn mark_set_category(
+    state: tauri::State<'_, DesktopState>,
+    mark_id: String,
+    category_id: Option<String>,
+) -> Envelope<Mark> {
+    state.api.lock().mark_set_category(SetMarkCategoryCommand {
+        mark_id,
+        category_id,
+    })
+}
+
+#[tauri::command]
+fn category_create(
+    state: tauri::State<'_, DesktopState>,
+    name: String,
+    now_minute: i64,
+) -> Envelope<Category> {
+    state
+        .api
+        .lock()
+        .category_create(CreateCategoryCommand { name, now_minute })
+}
+
+#[tauri::command]
+fn category_rename(
+    state: tauri::State<'_, DesktopState>,
+    category_id: String,
+    name: String,
+    now_minute: i64,
+) -> Envelope<Category> {
+    state.api.lock().category_rename(RenameCategoryCommand {
+        category_id,
+        name,
+        now_minute,
+    })
+}
+
+#[tauri::command]
+fn category_delete(
+    state: tauri::State<'_, DesktopState>,
+    category_id: String,
+) -> Envelope<Category> {
+    state
+        .api
+        .lock()
+        .category_delete(DeleteCategoryCommand { category_id })
+}
+
+#[tauri::command]
+fn category_list(state: tauri::State<'_, DesktopState>) -> Envelope<Vec<Category>> {
+    state.api.lock().category_list()
+}
+
+#[tauri::command]
+fn report_by_category(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: Option<String>,
+    from_minute: i64,
+    to_minute: i64,
+    utc_offset_minutes: i64,
+) -> Envelope<CategoryReport> {
+    state.api.lock().report_by_category(CategoryReportCommand {
+        timer_id,
+        from_minute,
+        to_minute,
+        utc_offset_minutes,
+    })
+}
+
+#[tauri::command]
+fn event_history(
+    state: tauri::State<'_, DesktopState>,
+    entity_type: String,
+    entity_id: String,
+) -> Envelope<Vec<DomainEvent>> {
+    state.api.lock().event_history(EntityHistoryCommand {
+        entity_type,
+        entity_id,
+    })
+}
+
+#[tauri::command]
+fn event_rebuild(state: tauri::State<'_, DesktopState>) -> Envelope<RebuildSummary> {
+    state.api.lock().event_rebuild()
+}
+
+#[tauri::command]
+fn undo(state: tauri::State<'_, DesktopState>) -> Envelope<HistoryStep> {
+    state.api.lock().undo()
+}
+
+#[tauri::command]
+fn redo(state: tauri::State<'_, DesktopState>) -> Envelope<HistoryStep> {
+    state.api.lock().redo()
+}
+
+#[tauri::command]
+fn history_status(state: tauri::State<'_, DesktopState>) -> Envelope<HistoryStatus> {
+    state.api.lock().history_status()
+}
+
+#[tauri::command]
+fn backup_list(state: tauri::State<'_, DesktopState>) -> Envelope<Vec<BackupInfo>> {
+    state.api.lock().backup_list()
+}
+
+#[tauri::command]
+fn backup_create(state: tauri::State<'_, DesktopState>) -> Envelope<BackupInfo> {
+    state.api.lock().backup_create()
+}
+
+#[tauri::command]
+fn backup_restore(
+    state: tauri::State<'_, DesktopState>,
+    backup_id: String,
+) -> Envelope<BackupInfo> {
+    state
+        .api
+        .lock()
+        .backup_restore(RestoreBackupCommand { backup_id })
+}
+
 #[tauri::command]
 fn mark_list_by_timer(
     state: tauri::State<'_, DesktopState>,
@@ -146,6 +488,351 @@ fn mark_list_by_timer(
     state.api.lock().mark_list_by_timer(&timer_id)
 }
 
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+fn mark_page_by_timer(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: String,
+    since_minute: Option<i64>,
+    until_minute: Option<i64>,
+    cursor: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+    newest_first: Option<bool>,
+) -> Envelope<Page<Mark>> {
+    state.api.lock().mark_page_by_timer(PageByTimerCommand {
+        timer_id,
+        since_minute,
+        until_minute,
+        cursor,
+        offset: offset.unwrap_or_default(),
+        limit,
+        newest_first: newest_first.unwrap_or_default(),
+    })
+}
+
+#[tauri::command]
+fn stats_daily(
+    state: tauri::State<'_, DesktopState>,
+    from_date: String,
+    to_date: String,
+    utc_offset_minutes: i64,
+) -> Envelope<DailyStats> {
+    state.api.lock().stats_daily(DailyStatsCommand {
+        from_date,
+        to_date,
+        utc_offset_minutes,
+    })
+}
+
+#[tauri::command]
+fn timer_set_tags(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: String,
+    tags: Vec<String>,
+    now_minute: i64,
+) -> Envelope<Timer> {
+    state.api.lock().timer_set_tags(SetTimerTagsCommand {
+        timer_id,
+        tags,
+        now_minute,
+    })
+}
+
+#[tauri::command]
+fn todo_set_tags(
+    state: tauri::State<'_, DesktopState>,
+    todo_id: String,
+    tags: Vec<String>,
+    now_minute: i64,
+) -> Envelope<Todo> {
+    state.api.lock().todo_set_tags(SetTodoTagsCommand {
+        todo_id,
+        tags,
+        now_minute,
+    })
+}
+
+#[tauri::command]
+fn timer_list_by_tag(state: tauri::State<'_, DesktopState>, tag: String) -> Envelope<Vec<Timer>> {
+    state.api.lock().timer_list_by_tag(&tag)
+}
+
+#[tauri::command]
+fn todo_list_by_tag(state: tauri::State<'_, DesktopState>, tag: String) -> Envelope<Vec<Todo>> {
+    state.api.lock().todo_list_by_tag(&tag)
+}
+
+#[tauri::command]
+fn mark_list_by_tag(
+    state: tauri::State<'_, DesktopState>,
+    tag: String,
+    since_minute: Option<i64>,
+    until_minute: Option<i64>,
+) -> Envelope<Vec<Mark>> {
+    state.api.lock().mark_list_by_tag(ListMarksByTagCommand {
+        tag,
+        since_minute,
+        until_minute,
+    })
+}
+
+#[tauri::command]
+fn search(
+    state: tauri::State<'_, DesktopState>,
+    text: String,
+    timer_id: Option<String>,
+    since_minute: Option<i64>,
+    until_minute: Option<i64>,
+    status: Option<String>,
+    limit: Option<usize>,
+) -> Envelope<Vec<SearchHit>> {
+    let parsed_status = match status.as_deref().map(str::parse::<TodoStatus>) {
+        None => None,
+        Some(Ok(parsed_status)) => Some(parsed_status),
+        Some(Err(_)) => {
+            return Envelope {
+                ok: false,
+                data: None,
+                error: Some(countdown_todo_core::command::CommandError {
+                    code: "E_VALIDATION",
+                    message: format!("unsupported todo status: {}", status.unwrap_or_default()),
+                    detail: None,
+                }),
+            };
+        }
+    };
+
+    state.api.lock().search(SearchCommand {
+        text,
+        timer_id,
+        since_minute,
+        until_minute,
+        status: parsed_status,
+        limit,
+    })
+}
+
+#[tauri::command]
+fn query(
+    state: tauri::State<'_, DesktopState>,
+    expression: String,
+    now_minute: i64,
+    utc_offset_minutes: i64,
+) -> Envelope<QueryResults> {
+    state.api.lock().query(QueryCommand {
+        expression,
+        now_minute,
+        utc_offset_minutes,
+    })
+}
+
+#[tauri::command]
+fn todo_import_todotxt(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: String,
+    text: String,
+    utc_offset_minutes: i64,
+    now_minute: i64,
+) -> Envelope<Vec<Todo>> {
+    state.api.lock().todo_import_todotxt(ImportTodoTxtCommand {
+        timer_id,
+        text,
+        utc_offset_minutes,
+        now_minute,
+    })
+}
+
+#[tauri::command]
+fn todo_export_todotxt(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: String,
+    utc_offset_minutes: i64,
+) -> Envelope<String> {
+    state.api.lock().todo_export_todotxt(ExportTodoTxtCommand {
+        timer_id,
+        utc_offset_minutes,
+    })
+}
+
+#[tauri::command]
+fn import_time_entries(
+    state: tauri::State<'_, DesktopState>,
+    text: String,
+    utc_offset_minutes: i64,
+    now_minute: i64,
+) -> Envelope<TimesheetReport> {
+    state
+        .api
+        .lock()
+        .import_time_entries(ImportTimeEntriesCommand {
+            text,
+            utc_offset_minutes,
+            now_minute,
+        })
+}
+
+#[tauri::command]
+fn export_data(state: tauri::State<'_, DesktopState>, now_minute: i64) -> Envelope<String> {
+    state
+        .api
+        .lock()
+        .export_data(ExportDataCommand { now_minute })
+}
+
+#[tauri::command]
+fn import_data(
+    state: tauri::State<'_, DesktopState>,
+    text: String,
+    mode: ImportMode,
+) -> Envelope<DataImportReport> {
+    state
+        .api
+        .lock()
+        .import_data(ImportDataCommand { text, mode })
+}
+
+#[tauri::command]
+fn merge_data_dir(
+    state: tauri::State<'_, DesktopState>,
+    other_dir: String,
+) -> Envelope<MergeReport> {
+    state
+        .api
+        .lock()
+        .merge_data_dir(MergeDataDirCommand { other_dir })
+}
+
+#[tauri::command]
+fn sync_data(state: tauri::State<'_, DesktopState>) -> Envelope<SyncReport> {
+    state.api.lock().sync_data()
+}
+
+#[tauri::command]
+fn export_taskwarrior(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: Option<String>,
+) -> Envelope<String> {
+    state
+        .api
+        .lock()
+        .export_taskwarrior(ExportWarriorCommand { timer_id })
+}
+
+#[tauri::command]
+fn import_taskwarrior(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: Option<String>,
+    text: String,
+    now_minute: i64,
+) -> Envelope<TaskImportReport> {
+    state.api.lock().import_taskwarrior(ImportWarriorCommand {
+        timer_id,
+        text,
+        now_minute,
+    })
+}
+
+#[tauri::command]
+fn export_timewarrior(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: Option<String>,
+) -> Envelope<String> {
+    state
+        .api
+        .lock()
+        .export_timewarrior(ExportWarriorCommand { timer_id })
+}
+
+#[tauri::command]
+fn import_timewarrior(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: Option<String>,
+    text: String,
+    now_minute: i64,
+) -> Envelope<TimesheetReport> {
+    state.api.lock().import_timewarrior(ImportWarriorCommand {
+        timer_id,
+        text,
+        now_minute,
+    })
+}
+
+#[tauri::command]
+fn export_ics(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: Option<String>,
+    include_archived: bool,
+    offsets_minutes: Option<Vec<i64>>,
+) -> Envelope<String> {
+    state.api.lock().export_ics(ExportIcsCommand {
+        timer_id,
+        include_archived,
+        offsets_minutes,
+    })
+}
+
+#[tauri::command]
+fn export_markdown(
+    state: tauri::State<'_, DesktopState>,
+    timer_id: Option<String>,
+    from_date: String,
+    to_date: String,
+    utc_offset_minutes: i64,
+) -> Envelope<String> {
+    state.api.lock().export_markdown(ExportMarkdownCommand {
+        timer_id,
+        from_date,
+        to_date,
+        utc_offset_minutes,
+    })
+}
+
+#[tauri::command]
+fn timeline(
+    state: tauri::State<'_, DesktopState>,
+    since_minute: i64,
+    until_minute: i64,
+) -> Envelope<Vec<TimelineEntry>> {
+    state.api.lock().timeline(TimelineCommand {
+        since_minute,
+        until_minute,
+    })
+}
+
+#[tauri::command]
+fn tag_list(state: tauri::State<'_, DesktopState>) -> Envelope<Vec<TagUsage>> {
+    state.api.lock().tag_list()
+}
+
+#[tauri::command]
+fn tag_rename(
+    state: tauri::State<'_, DesktopState>,
+    from: String,
+    to: String,
+    now_minute: i64,
+) -> Envelope<TagUsage> {
+    state.api.lock().tag_rename(RenameTagCommand {
+        from,
+        to,
+        now_minute,
+    })
+}
+
+#[tauri::command]
+fn tag_merge(
+    state: tauri::State<'_, DesktopState>,
+    sources: Vec<String>,
+    target: String,
+    now_minute: i64,
+) -> Envelope<TagUsage> {
+    state.api.lock().tag_merge(MergeTagsCommand {
+        sources,
+        target,
+        now_minute,
+    })
+}
+
 #[tauri::command]
 fn open_data_dir(
     state: tauri::State<'_, DesktopState>,
@@ -184,19 +871,124 @@ fn resolve_data_dir(app_handle: &tauri::AppHandle) -> PathBuf {
         .join("data")
 }
 
+fn resolve_device() -> Option<String> {
+    std::env::var("COUNTDOWN_TODO_DEVICE")
+        .ok()
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+fn resolve_watch_interval() -> Option<Duration> {
+    std::env::var("COUNTDOWN_TODO_WATCH_SECS")
+        .ok()
+        .and_then(|value| value.trim().parse().ok())
+        .filter(|seconds| *seconds > 0)
+        .map(Duration::from_secs)
+}
+
+fn resolve_reminder_policy() -> ReminderPolicy {
+    std::env::var("COUNTDOWN_TODO_REMINDER_OFFSETS")
+        .ok()
+        .and_then(|value| ReminderPolicy::parse(&value).ok())
+        .unwrap_or_default()
+}
+
+fn current_minute() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| (elapsed.as_secs() / 60) as i64)
+        .unwrap_or_default()
+}
+
+fn reminder_body(event: &ReminderEvent) -> String {
+    match event.kind {
+        ReminderKind::Upcoming => format!("距离截止还剩 {} 分钟", event.remaining_minutes),
+        ReminderKind::Overdue => format!("已超时 {} 分钟", -event.remaining_minutes),
+    }
+}
+
+struct WebviewForwarder {
+    app_handle: tauri::AppHandle,
+}
+
+impl EventSubscriber for WebviewForwarder {
+    fn on_event(&mut self, event: &DomainEvent) {
+        let _ = self.app_handle.emit_all(DOMAIN_EVENT_NAME, event);
+    }
+}
+
+fn spawn_reminder_loop(app_handle: tauri::AppHandle) {
+    std::thread::spawn(move || loop {
+        let state = app_handle.state::<DesktopState>();
+        let synced = state.api.lock().sync_data();
+        if synced.data.is_some_and(|report| report.changed) {
+            let _ = app_handle.emit_all("data-synced", ());
+        }
+        let response = state.api.lock().reminder_poll(PollRemindersCommand {
+            now_minute: current_minute(),
+            offsets_minutes: Some(state.reminder_policy.offsets_minutes.clone()),
+        });
+
+        for event in response.data.unwrap_or_default() {
+            let _ = tauri::api::notification::Notification::new(
+                &app_handle.config().tauri.bundle.identifier,
+            )
+            .title(&event.timer_name)
+            .body(reminder_body(&event))
+            .show();
+            let _ = app_handle.emit_all("reminder-due", &event);
+        }
+
+        std::thread::sleep(REMINDER_POLL_INTERVAL);
+    });
+}
+
+fn spawn_watch_loop(app_handle: tauri::AppHandle, interval: Duration) {
+    std::thread::spawn(move || loop {
+        std::thread::sleep(interval);
+        let state = app_handle.state::<DesktopState>();
+        let synced = state.api.lock().sync_data();
+        if synced.data.is_some_and(|report| report.changed) {
+            let _ = app_handle.emit_all("data-synced", ());
+        }
+    });
+}
+
 pub fn run() {
     tauri::Builder::default()
         .setup(|app| {
             let data_dir = resolve_data_dir(&app.handle());
-            let store = CsvStore::new(&data_dir)
-                .map_err(|error: countdown_todo_core::AppError| error.to_string())?;
-            let service = AppService::new(store);
-            let api = CommandApi::new(service);
+            let opened = match resolve_device() {
+                Some(device) => CsvStore::with_device(&data_dir, &device),
+                None => CsvStore::with_backup_policy(&data_dir, BackupPolicy::from_env()),
+            };
+            let store = match opened {
+                Err(countdown_todo_core::AppError::Conflict(message)) => {
+                    tauri::api::dialog::message(
+                        app.get_window("main").as_ref(),
+                        "Countdown Todo",
+                        format!("{message}\n\n已以只读方式打开，修改不会被保存。"),
+                    );
+                    CsvStore::open_read_only(&data_dir)
+                }
+                opened => opened,
+            }
+            .map_err(|error| error.to_string())?;
+            let service = AppService::open(store).map_err(|error| error.to_string())?;
+            let mut api = CommandApi::new(service);
+            api.subscribe(Box::new(WebviewForwarder {
+                app_handle: app.handle(),
+            }));
 
             app.manage(DesktopState {
                 api: Mutex::new(api),
                 data_dir,
+                reminder_policy: resolve_reminder_policy(),
             });
+            spawn_reminder_loop(app.handle());
+            if let Some(interval) = resolve_watch_interval() {
+                spawn_watch_loop(app.handle(), interval);
+            }
 
             Ok(())
         })
@@ -205,12 +997,67 @@ pub fn run() {
             timer_list,
             timer_update,
             timer_archive,
+            timer_unarchive,
+            timer_delete,
+            timer_set_project,
+            timer_list_by_project,
+            project_create,
+            project_rename,
+            project_delete,
+            project_list,
+            project_summary_list,
             todo_create,
             todo_list_by_timer,
+            todo_page_by_timer,
             todo_update_status,
             todo_delete,
             mark_create,
             mark_list_by_timer,
+            mark_page_by_timer,
+            mark_create_from_template,
+            template_create,
+            template_update,
+            template_delete,
+            template_list,
+            mark_set_category,
+            category_create,
+            category_rename,
+            category_delete,
+            category_list,
+            report_by_category,
+            stats_daily,
+            event_history,
+            event_rebuild,
+            undo,
+            redo,
+            history_status,
+            backup_list,
+            backup_create,
+            backup_restore,
+            timer_set_tags,
+            todo_set_tags,
+            timer_list_by_tag,
+            todo_list_by_tag,
+            mark_list_by_tag,
+            search,
+            query,
+            timeline,
+            export_markdown,
+            export_ics,
+            todo_import_todotxt,
+            todo_export_todotxt,
+            import_time_entries,
+            export_taskwarrior,
+            import_taskwarrior,
+            export_timewarrior,
+            import_timewarrior,
+            export_data,
+            import_data,
+            merge_data_dir,
+            sync_data,
+            tag_list,
+            tag_rename,
+            tag_merge,
             open_data_dir
         ])
         .run(tauri::generate_context!())
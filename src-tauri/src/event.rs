@@ -0,0 +1,344 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+use crate::history::Entity;
+use crate::model::{Category, EpochMinutes, Mark, MarkTemplate, Project, Timer, Todo};
+
+pub const ENTITY_TYPES: [&str; 6] = ["timer", "project", "category", "template", "todo", "mark"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum EventKind {
+    TimerCreated,
+    TimerUpdated,
+    TimerArchived,
+    TimerUnarchived,
+    TimerProjectChanged,
+    TimerTagsChanged,
+    TimerDeleted,
+    ProjectCreated,
+    ProjectRenamed,
+    ProjectDeleted,
+    CategoryCreated,
+    CategoryRenamed,
+    CategoryDeleted,
+    TemplateCreated,
+    TemplateUpdated,
+    TemplateDeleted,
+    TodoCreated,
+    TodoStatusChanged,
+    TodoTagsChanged,
+    TodoDeleted,
+    MarkAppended,
+    MarkCategoryChanged,
+    MarkTagsChanged,
+    MarkDeleted,
+    Imported,
+    BackupRestored,
+    Undone,
+    Redone,
+}
+
+impl EventKind {
+    pub const ALL: [EventKind; 28] = [
+        Self::TimerCreated,
+        Self::TimerUpdated,
+        Self::TimerArchived,
+        Self::TimerUnarchived,
+        Self::TimerProjectChanged,
+        Self::TimerTagsChanged,
+        Self::TimerDeleted,
+        Self::ProjectCreated,
+        Self::ProjectRenamed,
+        Self::ProjectDeleted,
+        Self::CategoryCreated,
+        Self::CategoryRenamed,
+        Self::CategoryDeleted,
+        Self::TemplateCreated,
+        Self::TemplateUpdated,
+        Self::TemplateDeleted,
+        Self::TodoCreated,
+        Self::TodoStatusChanged,
+        Self::TodoTagsChanged,
+        Self::TodoDeleted,
+        Self::MarkAppended,
+        Self::MarkCategoryChanged,
+        Self::MarkTagsChanged,
+        Self::MarkDeleted,
+        Self::Imported,
+        Self::BackupRestored,
+        Self::Undone,
+        Self::Redone,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::TimerCreated => "TimerCreated",
+            Self::TimerUpdated => "TimerUpdated",
+            Self::TimerArchived => "TimerArchived",
+            Self::TimerUnarchived => "TimerUnarchived",
+            Self::TimerProjectChanged => "TimerProjectChanged",
+            Self::TimerTagsChanged => "TimerTagsChanged",
+            Self::TimerDeleted => "TimerDeleted",
+            Self::ProjectCreated => "ProjectCreated",
+            Self::ProjectRenamed => "ProjectRenamed",
+            Self::ProjectDeleted => "ProjectDeleted",
+            Self::CategoryCreated => "CategoryCreated",
+            Self::CategoryRenamed => "CategoryRenamed",
+            Self::CategoryDeleted => "CategoryDeleted",
+            Self::TemplateCreated => "TemplateCreated",
+            Self::TemplateUpdated => "TemplateUpdated",
+            Self::TemplateDeleted => "TemplateDeleted",
+            Self::TodoCreated => "TodoCreated",
+            Self::TodoStatusChanged => "TodoStatusChanged",
+            Self::TodoTagsChanged => "TodoTagsChanged",
+            Self::TodoDeleted => "TodoDeleted",
+            Self::MarkAppended => "MarkAppended",
+            Self::MarkCategoryChanged => "MarkCategoryChanged",
+            Self::MarkTagsChanged => "MarkTagsChanged",
+            Self::MarkDeleted => "MarkDeleted",
+            Self::Imported => "Imported",
+            Self::BackupRestored => "BackupRestored",
+            Self::Undone => "Undone",
+            Self::Redone => "Redone",
+        }
+    }
+
+    pub fn parse(value: &str) -> AppResult<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|kind| kind.as_str() == value)
+            .ok_or_else(|| AppError::Internal(format!("unknown event kind '{value}'")))
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::TimerCreated => "create timer",
+            Self::TimerUpdated => "update timer",
+            Self::TimerArchived => "archive timer",
+            Self::TimerUnarchived => "unarchive timer",
+            Self::TimerProjectChanged => "set timer project",
+            Self::TimerTagsChanged => "set timer tags",
+            Self::TimerDeleted => "delete timer",
+            Self::ProjectCreated => "create project",
+            Self::ProjectRenamed => "rename project",
+            Self::ProjectDeleted => "delete project",
+            Self::CategoryCreated => "create category",
+            Self::CategoryRenamed => "rename category",
+            Self::CategoryDeleted => "delete category",
+            Self::TemplateCreated => "create template",
+            Self::TemplateUpdated => "update template",
+            Self::TemplateDeleted => "delete template",
+            Self::TodoCreated => "create todo",
+            Self::TodoStatusChanged => "set todo status",
+            Self::TodoTagsChanged => "set todo tags",
+            Self::TodoDeleted => "delete todo",
+            Self::MarkAppended => "create mark",
+            Self::MarkCategoryChanged => "set mark category",
+            Self::MarkTagsChanged => "set mark tags",
+            Self::MarkDeleted => "delete mark",
+            Self::Imported => "import",
+            Self::BackupRestored => "restore backup",
+            Self::Undone => "undo",
+            Self::Redone => "redo",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DomainEvent {
+    pub sequence: u64,
+    pub kind: EventKind,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub at_minute: Option<EpochMinutes>,
+    pub state: Option<Entity>,
+}
+
+impl DomainEvent {
+    pub fn saved(kind: EventKind, entity: Entity, at_minute: Option<EpochMinutes>) -> Self {
+        Self {
+            sequence: 0,
+            kind,
+            entity_type: entity.entity_type().to_string(),
+            entity_id: entity.id().to_string(),
+            at_minute,
+            state: Some(entity),
+        }
+    }
+
+    pub fn deleted(kind: EventKind, entity: &Entity, at_minute: Option<EpochMinutes>) -> Self {
+        Self {
+            sequence: 0,
+            kind,
+            entity_type: entity.entity_type().to_string(),
+            entity_id: entity.id().to_string(),
+            at_minute,
+            state: None,
+        }
+    }
+}
+
+pub trait EventSubscriber: Send {
+    fn on_event(&mut self, event: &DomainEvent);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RebuildSummary {
+    pub event_count: usize,
+    pub timer_count: usize,
+    pub project_count: usize,
+    pub category_count: usize,
+    pub template_count: usize,
+    pub todo_count: usize,
+    pub mark_count: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReplayState {
+    pub timers: BTreeMap<String, Timer>,
+    pub projects: BTreeMap<String, Project>,
+    pub categories: BTreeMap<String, Category>,
+    pub templates: BTreeMap<String, MarkTemplate>,
+    pub todos: BTreeMap<String, Todo>,
+    pub marks: BTreeMap<String, Mark>,
+}
+
+impl ReplayState {
+    pub fn entities(&self) -> Vec<Entity> {
+        let mut entities = Vec::new();
+        entities.extend(self.timers.values().cloned().map(Entity::Timer));
+        entities.extend(self.projects.values().cloned().map(Entity::Project));
+        entities.extend(self.categories.values().cloned().map(Entity::Category));
+        entities.extend(self.templates.values().cloned().map(Entity::Template));
+        entities.extend(self.todos.values().cloned().map(Entity::Todo));
+        entities.extend(self.marks.values().cloned().map(Entity::Mark));
+        entities
+    }
+
+    fn apply(&mut self, event: &DomainEvent) {
+        match &event.state {
+            Some(Entity::Timer(timer)) => {
+                self.timers.insert(timer.id.clone(), timer.clone());
+            }
+            Some(Entity::Project(project)) => {
+                self.projects.insert(project.id.clone(), project.clone());
+            }
+            Some(Entity::Category(category)) => {
+                self.categories
+                    .insert(category.id.clone(), category.clone());
+            }
+            Some(Entity::Template(template)) => {
+                self.templates.insert(template.id.clone(), template.clone());
+            }
+            Some(Entity::Todo(todo)) => {
+                self.todos.insert(todo.id.clone(), todo.clone());
+            }
+            Some(Entity::Mark(mark)) => {
+                self.marks.insert(mark.id.clone(), mark.clone());
+            }
+            None => self.remove(&event.entity_type, &event.entity_id),
+        }
+    }
+
+    fn remove(&mut self, entity_type: &str, entity_id: &str) {
+        match entity_type {
+            "timer" => {
+                self.timers.remove(entity_id);
+                self.todos.retain(|_, todo| todo.timer_id != entity_id);
+                self.marks.retain(|_, mark| mark.timer_id != entity_id);
+            }
+            "project" => {
+                self.projects.remove(entity_id);
+            }
+            "category" => {
+                self.categories.remove(entity_id);
+            }
+            "template" => {
+                self.templates.remove(entity_id);
+            }
+            "todo" => {
+                self.todos.remove(entity_id);
+            }
+            "mark" => {
+                self.marks.remove(entity_id);
+            }
+            _ => {}
+        }
+    }
+}
+
+pub fn replay(events: &[DomainEvent]) -> ReplayState {
+    let mut state = ReplayState::default();
+    for event in events {
+        state.apply(event);
+    }
+    state
+}
+
+pub fn validate_entity_type(entity_type: &str) -> AppResult<()> {
+    if ENTITY_TYPES.contains(&entity_type) {
+        return Ok(());
+    }
+    Err(AppError::Validation(format!(
+        "unknown entity type '{entity_type}', expected one of {}",
+        ENTITY_TYPES.join(", ")
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{replay, DomainEvent, EventKind};
+    use crate::history::Entity;
+    use crate::model::{Timer, Todo, TodoStatus};
+
+    #[test]
+    fn replays_saves_and_cascading_timer_delete() {
+        let timer = Timer {
+            id: "timer-1".to_string(),
+            name: "release".to_string(),
+            target_at_minute: 500,
+            created_at_minute: 0,
+            updated_at_minute: 0,
+            archived: false,
+            project_id: None,
+            tags: vec![],
+        };
+        let todo = Todo {
+            id: "todo-2".to_string(),
+            timer_id: timer.id.clone(),
+            title: "ship".to_string(),
+            status: TodoStatus::Open,
+            created_at_minute: 0,
+            updated_at_minute: 0,
+            done_at_minute: None,
+            tags: vec![],
+        };
+
+        let mut events = vec![
+            DomainEvent::saved(
+                EventKind::TimerCreated,
+                Entity::Timer(timer.clone()),
+                Some(0),
+            ),
+            DomainEvent::saved(EventKind::TodoCreated, Entity::Todo(todo.clone()), Some(1)),
+        ];
+        let state = replay(&events);
+        assert_eq!(state.todos.get("todo-2"), Some(&todo));
+
+        events.push(DomainEvent::deleted(
+            EventKind::TimerDeleted,
+            &Entity::Timer(timer),
+            None,
+        ));
+        let state = replay(&events);
+        assert!(state.timers.is_empty());
+        assert!(state.todos.is_empty());
+        assert_eq!(
+            EventKind::parse("TodoStatusChanged"),
+            Ok(EventKind::TodoStatusChanged)
+        );
+    }
+}
@@ -0,0 +1,234 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde::Serialize;
+
+use crate::model::{Category, Mark, MarkTemplate, Project, Timer, Todo};
+
+pub const DEFAULT_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "type", content = "data", rename_all = "lowercase")]
+pub enum Entity {
+    Timer(Timer),
+    Project(Project),
+    Category(Category),
+    Template(MarkTemplate),
+    Todo(Todo),
+    Mark(Mark),
+}
+
+impl Entity {
+    pub fn entity_type(&self) -> &'static str {
+        match self {
+            Self::Timer(_) => "timer",
+            Self::Project(_) => "project",
+            Self::Category(_) => "category",
+            Self::Template(_) => "template",
+            Self::Todo(_) => "todo",
+            Self::Mark(_) => "mark",
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        match self {
+            Self::Timer(timer) => &timer.id,
+            Self::Project(project) => &project.id,
+            Self::Category(category) => &category.id,
+            Self::Template(template) => &template.id,
+            Self::Todo(todo) => &todo.id,
+            Self::Mark(mark) => &mark.id,
+        }
+    }
+
+    pub fn set_id(&mut self, id: &str) {
+        let target = match self {
+            Self::Timer(timer) => &mut timer.id,
+            Self::Project(project) => &mut project.id,
+            Self::Category(category) => &mut category.id,
+            Self::Template(template) => &mut template.id,
+            Self::Todo(todo) => &mut todo.id,
+            Self::Mark(mark) => &mut mark.id,
+        };
+        *target = id.to_string();
+    }
+
+    pub fn remap_references(&mut self, remap: &HashMap<String, String>) {
+        let apply = |id: &mut String| {
+            if let Some(to) = remap.get(id.as_str()) {
+                *id = to.clone();
+            }
+        };
+        match self {
+            Self::Timer(timer) => timer.project_id.iter_mut().for_each(apply),
+            Self::Todo(todo) => apply(&mut todo.timer_id),
+            Self::Mark(mark) => {
+                apply(&mut mark.timer_id);
+                mark.category_id.iter_mut().for_each(apply);
+                mark.todo_ids.iter_mut().for_each(apply);
+            }
+            Self::Project(_) | Self::Category(_) | Self::Template(_) => {}
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub before: Option<Entity>,
+    pub after: Option<Entity>,
+}
+
+impl Change {
+    pub fn deleted(before: Entity) -> Self {
+        Self {
+            before: Some(before),
+            after: None,
+        }
+    }
+
+    pub fn updated(before: Entity, after: Entity) -> Self {
+        Self {
+            before: Some(before),
+            after: Some(after),
+        }
+    }
+
+    pub fn reversed(&self) -> Self {
+        Self {
+            before: self.after.clone(),
+            after: self.before.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Operation {
+    pub label: String,
+    pub changes: Vec<Change>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HistoryStatus {
+    pub undo_label: Option<String>,
+    pub redo_label: Option<String>,
+    pub undo_depth: usize,
+    pub redo_depth: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HistoryStep {
+    pub label: String,
+    pub change_count: usize,
+    pub status: HistoryStatus,
+}
+
+#[derive(Debug)]
+pub struct History {
+    undo: VecDeque<Operation>,
+    redo: Vec<Operation>,
+    limit: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self::new(DEFAULT_LIMIT)
+    }
+}
+
+impl History {
+    pub fn new(limit: usize) -> Self {
+        Self {
+            undo: VecDeque::new(),
+            redo: Vec::new(),
+            limit: limit.max(1),
+        }
+    }
+
+    pub fn record(&mut self, label: &str, changes: Vec<Change>) {
+        if changes.is_empty() {
+            return;
+        }
+        self.redo.clear();
+        self.undo.push_back(Operation {
+            label: label.to_string(),
+            changes,
+        });
+        while self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+    }
+
+    pub fn take_undo(&mut self) -> Option<Operation> {
+        self.undo.pop_back()
+    }
+
+    pub fn take_redo(&mut self) -> Option<Operation> {
+        self.redo.pop()
+    }
+
+    pub fn push_undone(&mut self, operation: Operation) {
+        self.redo.push(operation);
+    }
+
+    pub fn push_redone(&mut self, operation: Operation) {
+        self.undo.push_back(operation);
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    pub fn status(&self) -> HistoryStatus {
+        HistoryStatus {
+            undo_label: self.undo.back().map(|operation| operation.label.clone()),
+            redo_label: self.redo.last().map(|operation| operation.label.clone()),
+            undo_depth: self.undo.len(),
+            redo_depth: self.redo.len(),
+        }
+    }
+
+    pub fn step(&self, operation: &Operation) -> HistoryStep {
+        HistoryStep {
+            label: operation.label.clone(),
+            change_count: operation.changes.len(),
+            status: self.status(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Change, Entity, History};
+    use crate::model::Project;
+
+    fn project_change(id: &str) -> Change {
+        Change {
+            before: None,
+            after: Some(Entity::Project(Project {
+                id: id.to_string(),
+                name: id.to_string(),
+                created_at_minute: 0,
+                updated_at_minute: 0,
+            })),
+        }
+    }
+
+    #[test]
+    fn bounds_undo_stack_and_clears_redo_on_new_operation() {
+        let mut history = History::new(2);
+        history.record("one", vec![project_change("project-1")]);
+        history.record("two", vec![project_change("project-2")]);
+        history.record("three", vec![project_change("project-3")]);
+        history.record("empty", vec![]);
+        assert_eq!(history.status().undo_depth, 2);
+
+        let undone = history.take_undo().expect("operation should exist");
+        assert_eq!(undone.label, "three");
+        history.push_undone(undone);
+        assert_eq!(history.status().redo_label.as_deref(), Some("three"));
+
+        history.record("four", vec![project_change("project-4")]);
+        assert_eq!(history.status().redo_depth, 0);
+        assert_eq!(history.status().undo_label.as_deref(), Some("four"));
+    }
+}
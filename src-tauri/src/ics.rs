@@ -0,0 +1,261 @@
+use std::collections::HashMap;
+
+use crate::calendar;
+use crate::model::{EpochMinutes, Mark, Timer, Todo, TodoStatus};
+use crate::reminder::ReminderPolicy;
+use crate::stats::TimerActivity;
+
+const PRODID: &str = "-//countdown-todo//ICS Export//ZH";
+const UID_DOMAIN: &str = "countdown-todo";
+const MAX_LINE_OCTETS: usize = 75;
+
+pub fn render_calendar(activities: &[TimerActivity<'_>], policy: &ReminderPolicy) -> String {
+    let mut calendar = Calendar::default();
+    calendar.line("BEGIN:VCALENDAR");
+    calendar.line("VERSION:2.0");
+    calendar.line(&format!("PRODID:{PRODID}"));
+    calendar.line("CALSCALE:GREGORIAN");
+    calendar.line("METHOD:PUBLISH");
+    calendar.line("X-WR-CALNAME:Countdown Todo");
+
+    for activity in activities {
+        push_deadline(&mut calendar, activity.timer, policy);
+        let todos: HashMap<&str, &Todo> = activity
+            .todos
+            .iter()
+            .map(|todo| (todo.id.as_str(), todo))
+            .collect();
+        for mark in activity.marks {
+            push_segment(&mut calendar, activity.timer, mark, &todos);
+        }
+    }
+
+    calendar.line("END:VCALENDAR");
+    calendar.output
+}
+
+fn uid(entity_id: &str) -> String {
+    format!("{entity_id}@{UID_DOMAIN}")
+}
+
+fn push_deadline(calendar: &mut Calendar, timer: &Timer, policy: &ReminderPolicy) {
+    calendar.line("BEGIN:VEVENT");
+    calendar.line(&format!("UID:{}", uid(&timer.id)));
+    calendar.line(&format!(
+        "DTSTAMP:{}",
+        calendar::format_utc_stamp(timer.updated_at_minute)
+    ));
+    calendar.line(&format!(
+        "LAST-MODIFIED:{}",
+        calendar::format_utc_stamp(timer.updated_at_minute)
+    ));
+    calendar.line(&format!(
+        "DTSTART:{}",
+        calendar::format_utc_stamp(timer.target_at_minute)
+    ));
+    calendar.line(&format!(
+        "SUMMARY:{}",
+        escape_text(&format!("截止：{}", timer.name))
+    ));
+    calendar.line("TRANSP:TRANSPARENT");
+    if timer.archived {
+        calendar.line("STATUS:CANCELLED");
+    }
+    push_categories(calendar, &timer.tags);
+    for offset in &policy.offsets_minutes {
+        calendar.line("BEGIN:VALARM");
+        calendar.line("ACTION:DISPLAY");
+        calendar.line(&format!("DESCRIPTION:{}", escape_text(&timer.name)));
+        calendar.line(&format!("TRIGGER:{}", format_trigger(*offset)));
+        calendar.line("END:VALARM");
+    }
+    calendar.line("END:VEVENT");
+}
+
+fn push_segment(calendar: &mut Calendar, timer: &Timer, mark: &Mark, todos: &HashMap<&str, &Todo>) {
+    let Some(start) = mark.prev_marked_at_minute else {
+        return;
+    };
+    let description = mark.description.trim();
+    let summary = match description.lines().next() {
+        Some(first_line) => format!("{}：{first_line}", timer.name),
+        None => timer.name.clone(),
+    };
+    let mut body = description.to_string();
+    for todo in mark
+        .todo_ids
+        .iter()
+        .filter_map(|todo_id| todos.get(todo_id.as_str()))
+    {
+        let checkbox = if todo.status == TodoStatus::Done {
+            "x"
+        } else {
+            " "
+        };
+        body.push_str(&format!("\n- [{checkbox}] {}", todo.title));
+    }
+
+    calendar.line("BEGIN:VEVENT");
+    calendar.line(&format!("UID:{}", uid(&mark.id)));
+    calendar.line(&format!(
+        "DTSTAMP:{}",
+        calendar::format_utc_stamp(mark.marked_at_minute)
+    ));
+    calendar.line(&format!("DTSTART:{}", calendar::format_utc_stamp(start)));
+    if mark.marked_at_minute > start {
+        calendar.line(&format!(
+            "DTEND:{}",
+            calendar::format_utc_stamp(mark.marked_at_minute)
+        ));
+    }
+    calendar.line(&format!("SUMMARY:{}", escape_text(&summary)));
+    let body = body.trim();
+    if !body.is_empty() {
+        calendar.line(&format!("DESCRIPTION:{}", escape_text(body)));
+    }
+    calendar.line(&format!("RELATED-TO:{}", uid(&timer.id)));
+    push_categories(calendar, &mark.tags);
+    calendar.line("END:VEVENT");
+}
+
+fn push_categories(calendar: &mut Calendar, tags: &[String]) {
+    if tags.is_empty() {
+        return;
+    }
+    let categories: Vec<String> = tags.iter().map(|tag| escape_text(tag)).collect();
+    calendar.line(&format!("CATEGORIES:{}", categories.join(",")));
+}
+
+fn format_trigger(offset_minutes: EpochMinutes) -> String {
+    if offset_minutes == 0 {
+        "PT0M".to_string()
+    } else {
+        format!("-PT{offset_minutes}M")
+    }
+}
+
+fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            other => escaped.push(other),
+        }
+    }
+    escaped
+}
+
+#[derive(Default)]
+struct Calendar {
+    output: String,
+}
+
+impl Calendar {
+    fn line(&mut self, content: &str) {
+        let mut octets = 0;
+        for character in content.chars() {
+            let width = character.len_utf8();
+            if octets + width > MAX_LINE_OCTETS {
+                self.output.push_str("\r\n ");
+                octets = 1;
+            }
+            self.output.push(character);
+            octets += width;
+        }
+        self.output.push_str("\r\n");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_calendar;
+    use crate::calendar;
+    use crate::model::{Mark, Timer, Todo, TodoStatus};
+    use crate::reminder::ReminderPolicy;
+    use crate::stats::TimerActivity;
+
+    #[test]
+    fn renders_deadlines_and_segments_with_stable_uids() {
+        let day = calendar::parse_day("2026-10-12").expect("date should parse") * 24 * 60;
+        let timer = Timer {
+            id: "timer-1".to_string(),
+            name: "release, v2".to_string(),
+            target_at_minute: day + 18 * 60,
+            created_at_minute: day,
+            updated_at_minute: day + 5,
+            archived: false,
+            project_id: None,
+            tags: vec!["work".to_string()],
+        };
+        let todos = vec![Todo {
+            id: "todo-2".to_string(),
+            timer_id: "timer-1".to_string(),
+            title: "fix login".to_string(),
+            status: TodoStatus::Done,
+            created_at_minute: day,
+            updated_at_minute: day + 90,
+            done_at_minute: Some(day + 90),
+            tags: vec![],
+        }];
+        let mark = |id: &str, prev: Option<i64>, marked: i64, description: &str| Mark {
+            id: id.to_string(),
+            timer_id: "timer-1".to_string(),
+            marked_at_minute: marked,
+            prev_marked_at_minute: prev,
+            duration_minutes: prev.map(|prev| marked - prev),
+            description: description.to_string(),
+            todo_ids: vec!["todo-2".to_string()],
+            tags: vec![],
+            category_id: None,
+        };
+        let long_description = "长".repeat(40);
+        let marks = vec![
+            mark("mark-3", None, day + 60, "start"),
+            mark(
+                "mark-4",
+                Some(day + 60),
+                day + 90,
+                "fixed; login\nadded tests",
+            ),
+            mark("mark-5", Some(day + 90), day + 120, &long_description),
+        ];
+        let activities = [TimerActivity {
+            timer: &timer,
+            marks: &marks,
+            todos: &todos,
+        }];
+        let policy = ReminderPolicy::new(vec![0, 60]).expect("policy should build");
+
+        let ics = render_calendar(&activities, &policy);
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\nVERSION:2.0\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert!(ics.contains(
+            "UID:timer-1@countdown-todo\r\n\
+             DTSTAMP:20261012T000500Z\r\n\
+             LAST-MODIFIED:20261012T000500Z\r\n\
+             DTSTART:20261012T180000Z\r\n\
+             SUMMARY:截止：release\\, v2\r\n"
+        ));
+        assert!(ics.contains("TRIGGER:-PT60M\r\nEND:VALARM\r\nBEGIN:VALARM"));
+        assert!(ics.contains("TRIGGER:PT0M\r\n"));
+        assert!(!ics.contains("mark-3@"));
+        assert!(ics.contains(
+            "UID:mark-4@countdown-todo\r\n\
+             DTSTAMP:20261012T013000Z\r\n\
+             DTSTART:20261012T010000Z\r\n\
+             DTEND:20261012T013000Z\r\n\
+             SUMMARY:release\\, v2：fixed\\; login\r\n\
+             DESCRIPTION:fixed\\; login\\nadded tests\\n- [x] fix login\r\n\
+             RELATED-TO:timer-1@countdown-todo\r\n"
+        ));
+        for line in ics.split("\r\n") {
+            assert!(line.len() <= 75, "line too long: {line}");
+        }
+        assert!(ics.contains("\r\n 长"));
+        assert_eq!(ics.matches('\n').count(), ics.matches("\r\n").count());
+    }
+}
@@ -1,11 +1,34 @@
+pub mod backup;
+pub mod calendar;
 pub mod command;
+pub mod dataset;
 pub mod error;
+pub mod event;
+pub mod history;
+pub mod ics;
+pub mod lock;
+pub mod merge;
 pub mod model;
+pub mod page;
+pub mod query;
+pub mod reminder;
+pub mod report;
 pub mod repository;
+pub mod search;
 pub mod service;
+pub mod stats;
+pub mod sync;
+pub mod tag;
+pub mod taskwarrior;
+pub mod template;
+pub mod timeline;
+pub mod timesheet;
+pub mod timewarrior;
+pub mod todotxt;
+pub mod worklog;
 
 pub use command::{CommandApi, CommandError, Envelope};
 pub use error::{AppError, AppResult};
-pub use model::{EpochMinutes, Mark, Timer, Todo, TodoStatus};
+pub use model::{EpochMinutes, Mark, Project, ProjectSummary, TagUsage, Timer, Todo, TodoStatus};
 pub use repository::{CsvStore, InMemoryStore, Store};
 pub use service::AppService;
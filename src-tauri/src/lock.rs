@@ -0,0 +1,256 @@
+use std::fs::{self, File, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::{AppError, AppResult};
+
+pub const LOCK_FILE: &str = ".lock";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockOwner {
+    pub pid: u32,
+    pub host: String,
+    pub acquired_at_secs: u64,
+}
+
+impl LockOwner {
+    fn current() -> Self {
+        Self {
+            pid: std::process::id(),
+            host: host_name(),
+            acquired_at_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default(),
+        }
+    }
+
+    fn render(&self) -> String {
+        format!(
+            "pid={}\nhost={}\nacquired_at={}\n",
+            self.pid, self.host, self.acquired_at_secs
+        )
+    }
+
+    fn parse(text: &str) -> Option<Self> {
+        let mut pid = None;
+        let mut host = None;
+        let mut acquired_at_secs = None;
+        for line in text.lines() {
+            match line.split_once('=')? {
+                ("pid", value) => pid = value.parse().ok(),
+                ("host", value) => host = Some(value.to_string()),
+                ("acquired_at", value) => acquired_at_secs = value.parse().ok(),
+                _ => {}
+            }
+        }
+        Some(Self {
+            pid: pid?,
+            host: host?,
+            acquired_at_secs: acquired_at_secs?,
+        })
+    }
+
+    /// A lock is only provably stale when its process ran on this host and has exited.
+    fn is_stale(&self) -> bool {
+        self.host == host_name() && process_alive(self.pid) == Some(false)
+    }
+}
+
+/// Advisory lock held for as long as a writer keeps a data directory open.
+#[derive(Debug)]
+pub struct DataDirLock {
+    path: PathBuf,
+    _file: File,
+}
+
+impl DataDirLock {
+    pub fn acquire(path: impl Into<PathBuf>) -> AppResult<Self> {
+        let path = path.into();
+        let owner = LockOwner::current();
+        let mut recovered = false;
+        loop {
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(mut file) => {
+                    file.write_all(owner.render().as_bytes())
+                        .and_then(|()| file.sync_all())
+                        .map_err(|error| {
+                            let _ = fs::remove_file(&path);
+                            AppError::Internal(format!("failed to write {path:?}: {error}"))
+                        })?;
+                    return Ok(Self { path, _file: file });
+                }
+                Err(error) if error.kind() == ErrorKind::AlreadyExists => {
+                    let holder = read_owner(&path);
+                    let reclaimed = !recovered
+                        && holder
+                            .as_ref()
+                            .is_some_and(|owner| owner.is_stale() && reclaim_stale(&path, owner));
+                    if !reclaimed {
+                        return Err(held_error(&path, holder.as_ref()));
+                    }
+                    recovered = true;
+                }
+                Err(error) => {
+                    return Err(AppError::Internal(format!(
+                        "failed to create {path:?}: {error}"
+                    )))
+                }
+            }
+        }
+    }
+
+    pub fn owner(path: &Path) -> Option<LockOwner> {
+        read_owner(path)
+    }
+}
+
+impl Drop for DataDirLock {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Moves the lock aside before deleting it, so a fresh lock another process
+/// created after `stale` was read is put back rather than removed.
+fn reclaim_stale(path: &Path, stale: &LockOwner) -> bool {
+    let mut claimed = path.as_os_str().to_owned();
+    claimed.push(format!(".stale-{}", std::process::id()));
+    let claimed = PathBuf::from(claimed);
+    if fs::rename(path, &claimed).is_err() {
+        return false;
+    }
+    if read_owner(&claimed).as_ref() == Some(stale) {
+        return fs::remove_file(&claimed).is_ok();
+    }
+    let _ = fs::hard_link(&claimed, path);
+    let _ = fs::remove_file(&claimed);
+    false
+}
+
+fn read_owner(path: &Path) -> Option<LockOwner> {
+    LockOwner::parse(&fs::read_to_string(path).ok()?)
+}
+
+fn held_error(path: &Path, holder: Option<&LockOwner>) -> AppError {
+    let holder = holder.map_or_else(
+        || "another process".to_string(),
+        |owner| format!("pid {} on host {}", owner.pid, owner.host),
+    );
+    AppError::Conflict(format!(
+        "data directory in use by {holder} (lock file {path:?}); open it read-only or remove the lock file if that process is gone"
+    ))
+}
+
+fn host_name() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "localhost".to_string())
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> Option<bool> {
+    if Path::new("/proc/self").exists() {
+        return Some(Path::new("/proc").join(pid.to_string()).exists());
+    }
+    std::process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .ok()
+        .map(|status| status.success())
+}
+
+/// Windows refuses to move the lock file while its holder keeps it open,
+/// so recovery simply attempts the move.
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> Option<bool> {
+    Some(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use super::{host_name, reclaim_stale, DataDirLock, LockOwner};
+    use crate::error::AppError;
+
+    #[test]
+    fn rejects_a_live_holder_and_recovers_a_stale_lock() {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system clock should be after unix epoch")
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("countdown-todo-lock-{timestamp}"));
+        std::fs::create_dir_all(&dir).expect("temporary dir should be created");
+        let path = dir.join(".lock");
+
+        let lock = DataDirLock::acquire(&path).expect("first writer should lock");
+        assert_eq!(
+            DataDirLock::owner(&path).map(|owner| owner.pid),
+            Some(std::process::id())
+        );
+        assert!(matches!(
+            DataDirLock::acquire(&path),
+            Err(AppError::Conflict(_))
+        ));
+        drop(lock);
+        assert!(!path.exists());
+
+        let dead = LockOwner {
+            pid: u32::MAX - 1,
+            host: host_name(),
+            acquired_at_secs: 0,
+        };
+        std::fs::write(&path, dead.render()).expect("stale lock should be written");
+        let recovered = DataDirLock::acquire(&path).expect("stale lock should be recovered");
+        drop(recovered);
+
+        let foreign = LockOwner {
+            host: "elsewhere".to_string(),
+            ..dead
+        };
+        std::fs::write(&path, foreign.render()).expect("foreign lock should be written");
+        assert!(matches!(
+            DataDirLock::acquire(&path),
+            Err(AppError::Conflict(_))
+        ));
+    }
+
+    #[test]
+    fn keeps_a_fresh_lock_that_replaced_the_stale_one() {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system clock should be after unix epoch")
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("countdown-todo-lock-race-{timestamp}"));
+        std::fs::create_dir_all(&dir).expect("temporary dir should be created");
+        let path = dir.join(".lock");
+
+        let dead = LockOwner {
+            pid: u32::MAX - 1,
+            host: host_name(),
+            acquired_at_secs: 0,
+        };
+        let fresh = LockOwner {
+            pid: u32::MAX - 2,
+            host: host_name(),
+            acquired_at_secs: 1,
+        };
+        std::fs::write(&path, fresh.render()).expect("fresh lock should be written");
+
+        assert!(!reclaim_stale(&path, &dead));
+        assert_eq!(DataDirLock::owner(&path), Some(fresh));
+        assert_eq!(
+            std::fs::read_dir(&dir)
+                .expect("lock dir should list")
+                .count(),
+            1
+        );
+    }
+}
@@ -0,0 +1,394 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::dataset::{self, RemappedId};
+use crate::error::{AppError, AppResult};
+use crate::history::Entity;
+use crate::model::{EpochMinutes, Mark};
+use crate::repository::{CsvStore, StoreContents};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MergeSide {
+    Left,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MergeConflict {
+    pub entity_type: String,
+    pub id: String,
+    pub kept: MergeSide,
+    pub left_updated_at_minute: Option<EpochMinutes>,
+    pub right_updated_at_minute: Option<EpochMinutes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct MergeReport {
+    pub added: usize,
+    pub deduplicated: usize,
+    pub relinked_marks: usize,
+    pub conflicts: Vec<MergeConflict>,
+    pub remapped: Vec<RemappedId>,
+}
+
+pub fn load_data_dir(path: &Path) -> AppResult<StoreContents> {
+    if !path.is_dir() {
+        return Err(AppError::NotFound(format!("data directory {path:?}")));
+    }
+    Ok(StoreContents::load(&CsvStore::open_read_only(path)?))
+}
+
+pub fn merge(left: StoreContents, right: StoreContents) -> (StoreContents, MergeReport) {
+    let mut ids = IdAllocator::after(&left, &right);
+    let mut report = MergeReport::default();
+    let mut remap: HashMap<String, String> = HashMap::new();
+
+    let mut left_entities = left.into_entities();
+    let left_marks = split_marks(&mut left_entities);
+    let mut right_entities = right.into_entities();
+    let right_marks = split_marks(&mut right_entities);
+
+    let mut index: HashMap<String, usize> = left_entities
+        .iter()
+        .enumerate()
+        .map(|(position, entity)| (entity.id().to_string(), position))
+        .collect();
+    let mut merged = left_entities;
+    for mut entity in right_entities {
+        entity.remap_references(&remap);
+        let Some(&position) = index.get(entity.id()) else {
+            index.insert(entity.id().to_string(), merged.len());
+            merged.push(entity);
+            report.added += 1;
+            continue;
+        };
+        let existing = &merged[position];
+        if *existing == entity {
+            report.deduplicated += 1;
+            continue;
+        }
+        let (left_created, left_updated) = stamps(existing);
+        let (right_created, right_updated) = stamps(&entity);
+        if left_created != right_created {
+            ids.remap(&mut entity, &mut remap, &mut report);
+            index.insert(entity.id().to_string(), merged.len());
+            merged.push(entity);
+            report.added += 1;
+            continue;
+        }
+        let kept = if right_updated > left_updated {
+            merged[position] = entity.clone();
+            MergeSide::Right
+        } else {
+            MergeSide::Left
+        };
+        report.conflicts.push(MergeConflict {
+            entity_type: entity.entity_type().to_string(),
+            id: entity.id().to_string(),
+            kept,
+            left_updated_at_minute: Some(left_updated),
+            right_updated_at_minute: Some(right_updated),
+        });
+    }
+
+    let right_marks: Vec<Mark> = right_marks
+        .into_iter()
+        .map(|mut mark| {
+            for id in std::iter::once(&mut mark.timer_id)
+                .chain(mark.category_id.iter_mut())
+                .chain(mark.todo_ids.iter_mut())
+            {
+                if let Some(to) = remap.get(id.as_str()) {
+                    *id = to.clone();
+                }
+            }
+            mark
+        })
+        .collect();
+    let marks = merge_marks(left_marks, right_marks, &mut ids, &mut report);
+
+    let mut contents = StoreContents::default();
+    for entity in merged {
+        match entity {
+            Entity::Timer(timer) => contents.timers.push(timer),
+            Entity::Project(project) => contents.projects.push(project),
+            Entity::Category(category) => contents.categories.push(category),
+            Entity::Template(template) => contents.templates.push(template),
+            Entity::Todo(todo) => contents.todos.push(todo),
+            Entity::Mark(mark) => contents.marks.push(mark),
+        }
+    }
+    contents.marks.extend(marks);
+    (contents, report)
+}
+
+fn merge_marks(
+    left: Vec<Mark>,
+    right: Vec<Mark>,
+    ids: &mut IdAllocator,
+    report: &mut MergeReport,
+) -> Vec<Mark> {
+    let mut by_timer: BTreeMap<String, (Vec<Mark>, Vec<Mark>)> = BTreeMap::new();
+    for mark in left {
+        by_timer
+            .entry(mark.timer_id.clone())
+            .or_default()
+            .0
+            .push(mark);
+    }
+    for mark in right {
+        by_timer
+            .entry(mark.timer_id.clone())
+            .or_default()
+            .1
+            .push(mark);
+    }
+    let mut known: HashMap<String, Mark> = by_timer
+        .values()
+        .flat_map(|(left, _)| left.iter())
+        .map(|mark| (mark.id.clone(), mark.clone()))
+        .collect();
+
+    let mut marks = Vec::new();
+    for (left, right) in by_timer.into_values() {
+        let mut chained = dataset::chained_mark_ids(&left);
+        let right_chained = dataset::chained_mark_ids(&right);
+        let original: HashMap<String, Mark> = right
+            .iter()
+            .chain(&left)
+            .map(|mark| (mark.id.clone(), mark.clone()))
+            .collect();
+
+        let mut merged = left;
+        for mut mark in right {
+            let was_chained = right_chained.contains(&mark.id);
+            if merged.iter().any(|existing| {
+                same_moment(existing, &mark) && unlinked(existing) == unlinked(&mark)
+            }) {
+                report.deduplicated += 1;
+                continue;
+            }
+            if let Some(existing) = known.get(&mark.id) {
+                if same_moment(existing, &mark) {
+                    report.conflicts.push(MergeConflict {
+                        entity_type: "mark".to_string(),
+                        id: mark.id.clone(),
+                        kept: MergeSide::Left,
+                        left_updated_at_minute: None,
+                        right_updated_at_minute: None,
+                    });
+                    continue;
+                }
+                let to = ids.next("mark");
+                report.remapped.push(RemappedId {
+                    entity_type: "mark".to_string(),
+                    from: std::mem::replace(&mut mark.id, to.clone()),
+                    to,
+                });
+            }
+            if was_chained {
+                chained.insert(mark.id.clone());
+            }
+            known.insert(mark.id.clone(), mark.clone());
+            merged.push(mark);
+            report.added += 1;
+        }
+
+        dataset::relink_chain(&mut merged, &chained);
+        report.relinked_marks += merged
+            .iter()
+            .filter(|mark| {
+                original.get(&mark.id).is_some_and(|source| {
+                    source.id == mark.id
+                        && (source.prev_marked_at_minute, source.duration_minutes)
+                            != (mark.prev_marked_at_minute, mark.duration_minutes)
+                })
+            })
+            .count();
+        marks.extend(merged);
+    }
+    marks
+}
+
+fn split_marks(entities: &mut Vec<Entity>) -> Vec<Mark> {
+    let mut marks = Vec::new();
+    entities.retain(|entity| match entity {
+        Entity::Mark(mark) => {
+            marks.push(mark.clone());
+            false
+        }
+        _ => true,
+    });
+    marks
+}
+
+fn stamps(entity: &Entity) -> (EpochMinutes, EpochMinutes) {
+    match entity {
+        Entity::Timer(timer) => (timer.created_at_minute, timer.updated_at_minute),
+        Entity::Project(project) => (project.created_at_minute, project.updated_at_minute),
+        Entity::Category(category) => (category.created_at_minute, category.updated_at_minute),
+        Entity::Template(template) => (template.created_at_minute, template.updated_at_minute),
+        Entity::Todo(todo) => (todo.created_at_minute, todo.updated_at_minute),
+        Entity::Mark(mark) => (mark.marked_at_minute, mark.marked_at_minute),
+    }
+}
+
+fn same_moment(left: &Mark, right: &Mark) -> bool {
+    left.timer_id == right.timer_id && left.marked_at_minute == right.marked_at_minute
+}
+
+fn unlinked(mark: &Mark) -> Mark {
+    Mark {
+        id: String::new(),
+        prev_marked_at_minute: None,
+        duration_minutes: None,
+        ..mark.clone()
+    }
+}
+
+struct IdAllocator {
+    next_sequence: u64,
+}
+
+impl IdAllocator {
+    fn after(left: &StoreContents, right: &StoreContents) -> Self {
+        let highest = [left, right]
+            .into_iter()
+            .flat_map(StoreContents::ids)
+            .filter_map(|id| id.rsplit_once('-')?.1.parse::<u64>().ok())
+            .max()
+            .unwrap_or(0);
+        Self {
+            next_sequence: highest + 1,
+        }
+    }
+
+    fn next(&mut self, prefix: &str) -> String {
+        let id = format!("{prefix}-{}", self.next_sequence);
+        self.next_sequence += 1;
+        id
+    }
+
+    fn remap(
+        &mut self,
+        entity: &mut Entity,
+        remap: &mut HashMap<String, String>,
+        report: &mut MergeReport,
+    ) {
+        let from = entity.id().to_string();
+        let to = self.next(entity.entity_type());
+        entity.set_id(&to);
+        remap.insert(from.clone(), to.clone());
+        report.remapped.push(RemappedId {
+            entity_type: entity.entity_type().to_string(),
+            from,
+            to,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge, MergeSide};
+    use crate::model::{Mark, Timer, Todo, TodoStatus};
+    use crate::repository::StoreContents;
+
+    fn timer(id: &str, name: &str, created: i64, updated: i64) -> Timer {
+        Timer {
+            id: id.to_string(),
+            name: name.to_string(),
+            target_at_minute: 1_000,
+            created_at_minute: created,
+            updated_at_minute: updated,
+            archived: false,
+            project_id: None,
+            tags: vec![],
+        }
+    }
+
+    fn mark(id: &str, timer_id: &str, marked: i64, prev: Option<i64>) -> Mark {
+        Mark {
+            id: id.to_string(),
+            timer_id: timer_id.to_string(),
+            marked_at_minute: marked,
+            prev_marked_at_minute: prev,
+            duration_minutes: prev.map(|prev| marked - prev),
+            description: format!("at {marked}"),
+            todo_ids: vec![],
+            tags: vec![],
+            category_id: None,
+        }
+    }
+
+    #[test]
+    fn merges_divergent_contents_with_conflicts_and_rechained_marks() {
+        let todo = |id: &str, timer_id: &str, title: &str, created: i64| Todo {
+            id: id.to_string(),
+            timer_id: timer_id.to_string(),
+            title: title.to_string(),
+            status: TodoStatus::Open,
+            created_at_minute: created,
+            updated_at_minute: created,
+            done_at_minute: None,
+            tags: vec![],
+        };
+        let left = StoreContents {
+            timers: vec![timer("timer-1", "release", 0, 10)],
+            todos: vec![todo("todo-4", "timer-1", "docs", 1)],
+            marks: vec![
+                mark("mark-2", "timer-1", 10, None),
+                mark("mark-3", "timer-1", 30, Some(10)),
+            ],
+            ..StoreContents::default()
+        };
+        let right = StoreContents {
+            timers: vec![
+                timer("timer-1", "release v2", 0, 20),
+                timer("timer-4", "chores", 5, 5),
+            ],
+            todos: vec![todo("todo-4", "timer-4", "sweep", 5)],
+            marks: vec![
+                mark("mark-2", "timer-1", 10, None),
+                mark("mark-3", "timer-1", 20, Some(10)),
+            ],
+            ..StoreContents::default()
+        };
+
+        let (contents, report) = merge(left, right);
+        assert_eq!(contents.timers[0].name, "release v2");
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].kept, MergeSide::Right);
+        assert_eq!(report.deduplicated, 1);
+
+        let remapped: Vec<(&str, &str)> = report
+            .remapped
+            .iter()
+            .map(|remapped| (remapped.from.as_str(), remapped.to.as_str()))
+            .collect();
+        assert_eq!(remapped, vec![("todo-4", "todo-5"), ("mark-3", "mark-6")]);
+
+        let chain: Vec<(&str, i64, Option<i64>)> = contents
+            .marks
+            .iter()
+            .map(|mark| {
+                (
+                    mark.id.as_str(),
+                    mark.marked_at_minute,
+                    mark.duration_minutes,
+                )
+            })
+            .collect();
+        assert_eq!(
+            chain,
+            vec![
+                ("mark-2", 10, None),
+                ("mark-6", 20, Some(10)),
+                ("mark-3", 30, Some(10)),
+            ]
+        );
+        assert_eq!(report.relinked_marks, 1);
+    }
+}
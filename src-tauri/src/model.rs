@@ -1,5 +1,9 @@
+use std::str::FromStr;
+
 use serde::{Deserialize, Serialize};
 
+use crate::error::{AppError, AppResult};
+
 pub type EpochMinutes = i64;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -10,6 +14,8 @@ pub struct Timer {
     pub created_at_minute: EpochMinutes,
     pub updated_at_minute: EpochMinutes,
     pub archived: bool,
+    pub project_id: Option<String>,
+    pub tags: Vec<String>,
 }
 
 impl Timer {
@@ -18,6 +24,39 @@ impl Timer {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Project {
+    pub id: String,
+    pub name: String,
+    pub created_at_minute: EpochMinutes,
+    pub updated_at_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProjectSummary {
+    pub project: Project,
+    pub timer_count: usize,
+    pub open_todo_count: usize,
+    pub marks_this_week: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Category {
+    pub id: String,
+    pub name: String,
+    pub created_at_minute: EpochMinutes,
+    pub updated_at_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarkTemplate {
+    pub id: String,
+    pub name: String,
+    pub body: String,
+    pub created_at_minute: EpochMinutes,
+    pub updated_at_minute: EpochMinutes,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Mark {
     pub id: String,
@@ -27,6 +66,8 @@ pub struct Mark {
     pub duration_minutes: Option<EpochMinutes>,
     pub description: String,
     pub todo_ids: Vec<String>,
+    pub tags: Vec<String>,
+    pub category_id: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -36,15 +77,21 @@ pub enum TodoStatus {
     Done,
 }
 
-impl TodoStatus {
-    pub fn from_str(value: &str) -> Option<Self> {
+impl FromStr for TodoStatus {
+    type Err = AppError;
+
+    fn from_str(value: &str) -> AppResult<Self> {
         match value {
-            "open" => Some(Self::Open),
-            "done" => Some(Self::Done),
-            _ => None,
+            "open" => Ok(Self::Open),
+            "done" => Ok(Self::Done),
+            _ => Err(AppError::Validation(format!(
+                "unsupported todo status: {value}"
+            ))),
         }
     }
+}
 
+impl TodoStatus {
     pub fn as_str(&self) -> &'static str {
         match self {
             Self::Open => "open",
@@ -62,4 +109,13 @@ pub struct Todo {
     pub created_at_minute: EpochMinutes,
     pub updated_at_minute: EpochMinutes,
     pub done_at_minute: Option<EpochMinutes>,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagUsage {
+    pub tag: String,
+    pub timer_count: usize,
+    pub todo_count: usize,
+    pub mark_count: usize,
 }
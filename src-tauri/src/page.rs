@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::model::EpochMinutes;
+
+pub const MAX_LIMIT: usize = 1_000;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PageRequest {
+    pub since_minute: Option<EpochMinutes>,
+    pub until_minute: Option<EpochMinutes>,
+    pub cursor: Option<String>,
+    pub offset: usize,
+    pub limit: Option<usize>,
+    pub newest_first: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub total: usize,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> Page<T> {
+    pub fn map<U>(self, convert: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(convert).collect(),
+            total: self.total,
+            next_cursor: self.next_cursor,
+        }
+    }
+}
+
+pub fn paginate<T: Clone>(
+    sorted: &[T],
+    key: impl Fn(&T) -> (EpochMinutes, &str),
+    request: &PageRequest,
+) -> AppResult<Page<T>> {
+    let limit = validate(request)?;
+    let start = request.since_minute.map_or(0, |since| {
+        sorted.partition_point(|item| key(item).0 < since)
+    });
+    let end = request.until_minute.map_or(sorted.len(), |until| {
+        sorted.partition_point(|item| key(item).0 < until)
+    });
+    let window = &sorted[start..end.max(start)];
+
+    let remaining = match request.cursor.as_deref().map(parse_cursor).transpose()? {
+        None => window,
+        Some((minute, id)) if request.newest_first => {
+            &window[..window.partition_point(|item| key(item) < (minute, id.as_str()))]
+        }
+        Some((minute, id)) => {
+            &window[window.partition_point(|item| key(item) <= (minute, id.as_str()))..]
+        }
+    };
+
+    let ordered: Box<dyn Iterator<Item = &T>> = if request.newest_first {
+        Box::new(remaining.iter().rev())
+    } else {
+        Box::new(remaining.iter())
+    };
+    let items: Vec<T> = ordered.skip(request.offset).take(limit).cloned().collect();
+    let next_cursor = if request.offset + items.len() < remaining.len() {
+        items.last().map(|item| {
+            let (minute, id) = key(item);
+            format!("{minute}:{id}")
+        })
+    } else {
+        None
+    };
+
+    Ok(Page {
+        items,
+        total: window.len(),
+        next_cursor,
+    })
+}
+
+fn validate(request: &PageRequest) -> AppResult<usize> {
+    if let (Some(since), Some(until)) = (request.since_minute, request.until_minute) {
+        if since > until {
+            return Err(AppError::Validation(format!(
+                "page range start {since} is after end {until}"
+            )));
+        }
+    }
+    match request.limit {
+        Some(0) => Err(AppError::Validation(
+            "page limit must be at least 1".to_string(),
+        )),
+        Some(limit) if limit > MAX_LIMIT => Err(AppError::Validation(format!(
+            "page limit must be at most {MAX_LIMIT}"
+        ))),
+        Some(limit) => Ok(limit),
+        None => Ok(MAX_LIMIT),
+    }
+}
+
+fn parse_cursor(cursor: &str) -> AppResult<(EpochMinutes, String)> {
+    cursor
+        .split_once(':')
+        .and_then(|(minute, id)| Some((minute.parse().ok()?, id.to_string())))
+        .filter(|(_, id)| !id.is_empty())
+        .ok_or_else(|| AppError::Validation(format!("invalid page cursor '{cursor}'")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{paginate, PageRequest};
+
+    fn items() -> Vec<(i64, String)> {
+        (0..6)
+            .map(|index| (10 * (index / 2), format!("mark-{index}")))
+            .collect()
+    }
+
+    fn key(item: &(i64, String)) -> (i64, &str) {
+        (item.0, &item.1)
+    }
+
+    #[test]
+    fn walks_range_with_cursor_in_both_directions() {
+        let items = items();
+
+        let mut request = PageRequest {
+            since_minute: Some(5),
+            limit: Some(2),
+            newest_first: true,
+            ..PageRequest::default()
+        };
+        let first = paginate(&items, key, &request).expect("page should load");
+        assert_eq!(first.total, 4);
+        assert_eq!(first.items[0].1, "mark-5");
+        assert_eq!(first.next_cursor.as_deref(), Some("20:mark-4"));
+
+        request.cursor = first.next_cursor;
+        let second = paginate(&items, key, &request).expect("page should load");
+        let ids: Vec<&str> = second.items.iter().map(|item| item.1.as_str()).collect();
+        assert_eq!(ids, vec!["mark-3", "mark-2"]);
+        assert_eq!(second.next_cursor, None);
+
+        let oldest = paginate(
+            &items,
+            key,
+            &PageRequest {
+                until_minute: Some(20),
+                offset: 1,
+                limit: Some(2),
+                ..PageRequest::default()
+            },
+        )
+        .expect("page should load");
+        let ids: Vec<&str> = oldest.items.iter().map(|item| item.1.as_str()).collect();
+        assert_eq!(ids, vec!["mark-1", "mark-2"]);
+        assert_eq!(oldest.next_cursor.as_deref(), Some("10:mark-2"));
+
+        request.cursor = Some("bogus".to_string());
+        assert!(paginate(&items, key, &request).is_err());
+    }
+}
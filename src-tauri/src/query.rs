@@ -0,0 +1,639 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::calendar::{self, MINUTES_PER_DAY};
+use crate::error::{AppError, AppResult};
+use crate::model::{Category, EpochMinutes, Mark, Project, Timer, Todo, TodoStatus};
+use crate::search;
+use crate::tag;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QueryTarget {
+    All,
+    Marks,
+    Todos,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    Relative(EpochMinutes),
+    Day(i64),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Condition {
+    Text(String),
+    Timer(String),
+    Project(String),
+    Category(String),
+    Tag(String),
+    Status(TodoStatus),
+    Archived(bool),
+    Due(Comparison, Bound),
+    After(Bound),
+    Before(Bound),
+    Duration(Comparison, EpochMinutes),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Filter {
+    pub negated: bool,
+    pub condition: Condition,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Query {
+    pub target: QueryTarget,
+    pub filters: Vec<Filter>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct QueryResults {
+    pub target: QueryTarget,
+    pub marks: Vec<Mark>,
+    pub todos: Vec<Todo>,
+}
+
+pub struct QueryContext<'a> {
+    pub now_minute: EpochMinutes,
+    pub utc_offset_minutes: EpochMinutes,
+    pub timers: &'a [Timer],
+    pub projects: &'a [Project],
+    pub categories: &'a [Category],
+}
+
+#[derive(Debug)]
+struct Clause {
+    position: usize,
+    negated: bool,
+    key: Option<String>,
+    operator: Option<Comparison>,
+    value: String,
+}
+
+pub fn parse(input: &str) -> AppResult<Query> {
+    let mut target = None;
+    let mut filters = Vec::new();
+    let mut limit = None;
+
+    for (index, clause) in lex(input)?.into_iter().enumerate() {
+        let Some(key) = clause.key.as_deref() else {
+            let explicit = match clause.value.as_str() {
+                "marks" | "mark" if index == 0 && !clause.negated => Some(QueryTarget::Marks),
+                "todos" | "todo" if index == 0 && !clause.negated => Some(QueryTarget::Todos),
+                _ => None,
+            };
+            match explicit {
+                Some(explicit) => target = Some(explicit),
+                None => filters.push(Filter {
+                    negated: clause.negated,
+                    condition: Condition::Text(folded(&clause.value)),
+                }),
+            }
+            continue;
+        };
+
+        let operator = clause.operator.unwrap_or(Comparison::Eq);
+        let condition = match key {
+            "timer" => Condition::Timer(equality_value(&clause, operator)?),
+            "project" => Condition::Project(equality_value(&clause, operator)?),
+            "category" => Condition::Category(equality_value(&clause, operator)?),
+            "tag" => Condition::Tag(tag::normalize_tag(&equality_value(&clause, operator)?)?),
+            "status" => {
+                let value = equality_value(&clause, operator)?;
+                Condition::Status(
+                    value
+                        .to_lowercase()
+                        .parse::<TodoStatus>()
+                        .map_err(|_| clause_error(&clause, "status must be open or done"))?,
+                )
+            }
+            "archived" => Condition::Archived(
+                match equality_value(&clause, operator)?.to_lowercase().as_str() {
+                    "true" | "yes" => true,
+                    "false" | "no" => false,
+                    _ => return Err(clause_error(&clause, "archived must be true or false")),
+                },
+            ),
+            "due" => Condition::Due(operator, parse_bound(&clause)?),
+            "after" => Condition::After(parse_bound_with(&clause, operator)?),
+            "before" => Condition::Before(parse_bound_with(&clause, operator)?),
+            "duration" => Condition::Duration(
+                operator,
+                parse_span(&clause.value)
+                    .ok_or_else(|| clause_error(&clause, "expected minutes like 90, 45m or 2h"))?,
+            ),
+            "limit" => {
+                equality_value(&clause, operator)?;
+                limit = Some(
+                    clause
+                        .value
+                        .parse::<usize>()
+                        .ok()
+                        .filter(|limit| *limit > 0)
+                        .ok_or_else(|| clause_error(&clause, "limit must be a positive integer"))?,
+                );
+                continue;
+            }
+            _ => return Err(clause_error(&clause, &format!("unknown field '{key}'"))),
+        };
+        filters.push(Filter {
+            negated: clause.negated,
+            condition,
+        });
+    }
+
+    let target = resolve_target(target, &filters)?;
+    Ok(Query {
+        target,
+        filters,
+        limit,
+    })
+}
+
+pub fn evaluate(
+    query: &Query,
+    context: &QueryContext<'_>,
+    todos: Vec<Todo>,
+    marks: Vec<Mark>,
+) -> QueryResults {
+    let timers: HashMap<&str, &Timer> = context
+        .timers
+        .iter()
+        .map(|timer| (timer.id.as_str(), timer))
+        .collect();
+    let limit = query.limit.unwrap_or(usize::MAX);
+
+    let mut matched_marks = Vec::new();
+    if query.target != QueryTarget::Todos {
+        matched_marks = marks
+            .into_iter()
+            .filter(|mark| {
+                let item = Item {
+                    timer: timers.get(mark.timer_id.as_str()).copied(),
+                    text: &mark.description,
+                    tags: &mark.tags,
+                    at_minute: mark.marked_at_minute,
+                    status: None,
+                    duration_minutes: mark.duration_minutes,
+                    category_id: mark.category_id.as_deref(),
+                };
+                matches_all(query, context, &item)
+            })
+            .collect();
+        matched_marks.sort_by(|left, right| {
+            left.marked_at_minute
+                .cmp(&right.marked_at_minute)
+                .then(left.id.cmp(&right.id))
+        });
+        matched_marks.truncate(limit);
+    }
+
+    let mut matched_todos = Vec::new();
+    if query.target != QueryTarget::Marks {
+        matched_todos = todos
+            .into_iter()
+            .filter(|todo| {
+                let item = Item {
+                    timer: timers.get(todo.timer_id.as_str()).copied(),
+                    text: &todo.title,
+                    tags: &todo.tags,
+                    at_minute: todo.created_at_minute,
+                    status: Some(&todo.status),
+                    duration_minutes: None,
+                    category_id: None,
+                };
+                matches_all(query, context, &item)
+            })
+            .collect();
+        matched_todos.sort_by(|left, right| {
+            left.created_at_minute
+                .cmp(&right.created_at_minute)
+                .then(left.id.cmp(&right.id))
+        });
+        matched_todos.truncate(limit);
+    }
+
+    QueryResults {
+        target: query.target,
+        marks: matched_marks,
+        todos: matched_todos,
+    }
+}
+
+struct Item<'a> {
+    timer: Option<&'a Timer>,
+    text: &'a str,
+    tags: &'a [String],
+    at_minute: EpochMinutes,
+    status: Option<&'a TodoStatus>,
+    duration_minutes: Option<EpochMinutes>,
+    category_id: Option<&'a str>,
+}
+
+fn matches_all(query: &Query, context: &QueryContext<'_>, item: &Item<'_>) -> bool {
+    query
+        .filters
+        .iter()
+        .all(|filter| matches(&filter.condition, context, item) != filter.negated)
+}
+
+fn matches(condition: &Condition, context: &QueryContext<'_>, item: &Item<'_>) -> bool {
+    match condition {
+        Condition::Text(text) => folded(item.text).contains(text.as_str()),
+        Condition::Timer(value) => item
+            .timer
+            .is_some_and(|timer| names_match(&timer.id, &timer.name, value)),
+        Condition::Project(value) => item
+            .timer
+            .and_then(|timer| timer.project_id.as_deref())
+            .and_then(|project_id| {
+                context
+                    .projects
+                    .iter()
+                    .find(|project| project.id == project_id)
+            })
+            .is_some_and(|project| names_match(&project.id, &project.name, value)),
+        Condition::Category(value) => item
+            .category_id
+            .and_then(|category_id| {
+                context
+                    .categories
+                    .iter()
+                    .find(|category| category.id == category_id)
+            })
+            .is_some_and(|category| names_match(&category.id, &category.name, value)),
+        Condition::Tag(tag) => {
+            item.tags.contains(tag) || item.timer.is_some_and(|timer| timer.tags.contains(tag))
+        }
+        Condition::Status(status) => item.status == Some(status),
+        Condition::Archived(archived) => {
+            item.timer.is_some_and(|timer| timer.archived == *archived)
+        }
+        Condition::Due(comparison, bound) => item.timer.is_some_and(|timer| {
+            let (start, end) = interval(*bound, context, 1);
+            compare(timer.target_at_minute, *comparison, start, end)
+        }),
+        Condition::After(bound) => {
+            let (start, _) = interval(*bound, context, -1);
+            item.at_minute >= start
+        }
+        Condition::Before(bound) => {
+            let (start, _) = interval(*bound, context, -1);
+            item.at_minute < start
+        }
+        Condition::Duration(comparison, minutes) => item
+            .duration_minutes
+            .is_some_and(|duration| compare(duration, *comparison, *minutes, minutes + 1)),
+    }
+}
+
+fn interval(
+    bound: Bound,
+    context: &QueryContext<'_>,
+    direction: EpochMinutes,
+) -> (EpochMinutes, EpochMinutes) {
+    match bound {
+        Bound::Relative(minutes) => {
+            let minute = context.now_minute + direction * minutes;
+            (minute, minute + 1)
+        }
+        Bound::Day(day) => (
+            calendar::day_start_minute(day, context.utc_offset_minutes),
+            calendar::day_start_minute(day + 1, context.utc_offset_minutes),
+        ),
+    }
+}
+
+fn compare(
+    value: EpochMinutes,
+    comparison: Comparison,
+    start: EpochMinutes,
+    end: EpochMinutes,
+) -> bool {
+    match comparison {
+        Comparison::Eq => start <= value && value < end,
+        Comparison::Lt => value < start,
+        Comparison::Le => value < end,
+        Comparison::Gt => value >= end,
+        Comparison::Ge => value >= start,
+    }
+}
+
+fn names_match(id: &str, name: &str, value: &str) -> bool {
+    id == value || folded(name) == folded(value)
+}
+
+fn folded(text: &str) -> String {
+    search::fold(text).into_iter().collect()
+}
+
+fn resolve_target(explicit: Option<QueryTarget>, filters: &[Filter]) -> AppResult<QueryTarget> {
+    let mut target = explicit.unwrap_or(QueryTarget::All);
+    for filter in filters {
+        let (required, field) = match filter.condition {
+            Condition::Status(_) => (QueryTarget::Todos, "status"),
+            Condition::Duration(..) => (QueryTarget::Marks, "duration"),
+            Condition::Category(_) => (QueryTarget::Marks, "category"),
+            _ => continue,
+        };
+        if target == QueryTarget::All {
+            target = required;
+        } else if target != required {
+            let kind = match required {
+                QueryTarget::Todos => "todos",
+                _ => "marks",
+            };
+            return Err(AppError::Validation(format!(
+                "field '{field}' only applies to {kind}"
+            )));
+        }
+    }
+    Ok(target)
+}
+
+fn equality_value(clause: &Clause, operator: Comparison) -> AppResult<String> {
+    if operator != Comparison::Eq {
+        return Err(clause_error(clause, "only ':' is supported for this field"));
+    }
+    Ok(clause.value.clone())
+}
+
+fn parse_bound_with(clause: &Clause, operator: Comparison) -> AppResult<Bound> {
+    equality_value(clause, operator)?;
+    parse_bound(clause)
+}
+
+fn parse_bound(clause: &Clause) -> AppResult<Bound> {
+    if clause.value.contains('-') {
+        return calendar::parse_day(&clause.value)
+            .map(Bound::Day)
+            .map_err(|_| clause_error(clause, "expected a date like 2026-10-01"));
+    }
+    parse_span(&clause.value)
+        .map(Bound::Relative)
+        .ok_or_else(|| clause_error(clause, "expected a date like 2026-10-01 or a span like 2d"))
+}
+
+fn parse_span(value: &str) -> Option<EpochMinutes> {
+    let value = value.trim().to_lowercase();
+    let (digits, unit) = match value.find(|char: char| !char.is_ascii_digit()) {
+        Some(split) => value.split_at(split),
+        None => (value.as_str(), "m"),
+    };
+    let amount: EpochMinutes = digits.parse().ok()?;
+    let scale = match unit {
+        "m" | "min" => 1,
+        "h" => 60,
+        "d" => MINUTES_PER_DAY,
+        "w" => 7 * MINUTES_PER_DAY,
+        _ => return None,
+    };
+    amount.checked_mul(scale)
+}
+
+fn clause_error(clause: &Clause, message: &str) -> AppError {
+    let key = clause.key.as_deref().unwrap_or("text");
+    AppError::Validation(format!(
+        "invalid query at position {}: {key}: {message}",
+        clause.position + 1
+    ))
+}
+
+fn lex(input: &str) -> AppResult<Vec<Clause>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut clauses = Vec::new();
+    let mut cursor = 0;
+
+    while cursor < chars.len() {
+        if chars[cursor].is_whitespace() {
+            cursor += 1;
+            continue;
+        }
+
+        let position = cursor;
+        let negated = chars[cursor] == '-'
+            && chars
+                .get(cursor + 1)
+                .is_some_and(|next| !next.is_whitespace());
+        if negated {
+            cursor += 1;
+        }
+
+        let key_end = chars[cursor..]
+            .iter()
+            .position(|char| !(char.is_ascii_alphabetic() || *char == '_'))
+            .map_or(chars.len(), |offset| cursor + offset);
+        let operator_char = chars.get(key_end).copied();
+        let (key, operator) =
+            if key_end > cursor && matches!(operator_char, Some(':' | '<' | '>' | '=')) {
+                let key: String = chars[cursor..key_end]
+                    .iter()
+                    .collect::<String>()
+                    .to_lowercase();
+                let or_equal = chars.get(key_end + 1) == Some(&'=');
+                let (operator, width) = match operator_char {
+                    Some(':') | Some('=') => (Comparison::Eq, 1),
+                    Some('<') if or_equal => (Comparison::Le, 2),
+                    Some('<') => (Comparison::Lt, 1),
+                    Some('>') if or_equal => (Comparison::Ge, 2),
+                    _ => (Comparison::Gt, 1),
+                };
+                cursor = key_end + width;
+                (Some(key), Some(operator))
+            } else {
+                (None, None)
+            };
+
+        let (value, next) = read_value(&chars, cursor).ok_or_else(|| {
+            AppError::Validation(format!(
+                "invalid query at position {}: unterminated quote",
+                position + 1
+            ))
+        })?;
+        cursor = next;
+        let clause = Clause {
+            position,
+            negated,
+            key,
+            operator,
+            value,
+        };
+        if clause.value.trim().is_empty() {
+            return Err(clause_error(&clause, "missing value"));
+        }
+        clauses.push(clause);
+    }
+
+    Ok(clauses)
+}
+
+fn read_value(chars: &[char], start: usize) -> Option<(String, usize)> {
+    if chars.get(start) != Some(&'"') {
+        let end = chars[start..]
+            .iter()
+            .position(|char| char.is_whitespace())
+            .map_or(chars.len(), |offset| start + offset);
+        return Some((chars[start..end].iter().collect(), end));
+    }
+
+    let mut value = String::new();
+    let mut cursor = start + 1;
+    while cursor < chars.len() {
+        match chars[cursor] {
+            '"' => return Some((value, cursor + 1)),
+            '\\' if cursor + 1 < chars.len() => {
+                value.push(chars[cursor + 1]);
+                cursor += 2;
+            }
+            char => {
+                value.push(char);
+                cursor += 1;
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, parse, Bound, Comparison, Condition, QueryContext, QueryTarget};
+    use crate::model::{Mark, Timer, Todo, TodoStatus};
+
+    fn timer(id: &str, name: &str, target_at_minute: i64, tags: &[&str]) -> Timer {
+        Timer {
+            id: id.to_string(),
+            name: name.to_string(),
+            target_at_minute,
+            created_at_minute: 0,
+            updated_at_minute: 0,
+            archived: false,
+            project_id: None,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        }
+    }
+
+    fn todo(id: &str, timer_id: &str, title: &str, status: TodoStatus) -> Todo {
+        Todo {
+            id: id.to_string(),
+            timer_id: timer_id.to_string(),
+            title: title.to_string(),
+            status,
+            created_at_minute: 10,
+            updated_at_minute: 10,
+            done_at_minute: None,
+            tags: vec![],
+        }
+    }
+
+    fn mark(id: &str, marked_at_minute: i64, duration_minutes: Option<i64>) -> Mark {
+        Mark {
+            id: id.to_string(),
+            timer_id: "timer-2".to_string(),
+            marked_at_minute,
+            prev_marked_at_minute: None,
+            duration_minutes,
+            description: "deploy".to_string(),
+            todo_ids: vec![],
+            tags: vec![],
+            category_id: None,
+        }
+    }
+
+    #[test]
+    fn parses_fields_operators_and_quoted_values() {
+        let query = parse(r#"timer:"release \"v2\"" status:open due<2d -tag:Backend login"#)
+            .expect("query should parse");
+        assert_eq!(query.target, QueryTarget::Todos);
+        let conditions: Vec<(bool, Condition)> = query
+            .filters
+            .into_iter()
+            .map(|filter| (filter.negated, filter.condition))
+            .collect();
+        assert_eq!(
+            conditions,
+            vec![
+                (false, Condition::Timer("release \"v2\"".to_string())),
+                (false, Condition::Status(TodoStatus::Open)),
+                (
+                    false,
+                    Condition::Due(Comparison::Lt, Bound::Relative(2 * 24 * 60))
+                ),
+                (true, Condition::Tag("backend".to_string())),
+                (false, Condition::Text("login".to_string())),
+            ]
+        );
+
+        let query = parse("marks after:2026-10-01 duration>=1h").expect("query should parse");
+        assert_eq!(query.target, QueryTarget::Marks);
+        assert_eq!(
+            query.filters[1].condition,
+            Condition::Duration(Comparison::Ge, 60)
+        );
+
+        for invalid in [
+            "status:blocked",
+            "owner:me",
+            "timer:\"release",
+            "after>2026-10-01",
+            "marks status:open",
+            "status:open duration>5",
+            "limit:0",
+        ] {
+            assert!(parse(invalid).is_err(), "{invalid} should be rejected");
+        }
+    }
+
+    #[test]
+    fn evaluates_filters_against_items() {
+        let timers = vec![
+            timer("timer-1", "Release", 3_000, &["backend"]),
+            timer("timer-2", "Side", 100_000, &[]),
+        ];
+        let context = QueryContext {
+            now_minute: 1_000,
+            utc_offset_minutes: 0,
+            timers: &timers,
+            projects: &[],
+            categories: &[],
+        };
+        let todos = vec![
+            todo("todo-3", "timer-1", "ship", TodoStatus::Open),
+            todo("todo-4", "timer-1", "docs", TodoStatus::Done),
+            todo("todo-5", "timer-2", "relax", TodoStatus::Open),
+        ];
+        let marks = vec![
+            mark("mark-6", 2 * 24 * 60, Some(30)),
+            mark("mark-7", 3 * 24 * 60, Some(90)),
+        ];
+
+        let query =
+            parse("timer:release status:open due<2d tag:backend").expect("query should parse");
+        let results = evaluate(&query, &context, todos.clone(), marks.clone());
+        assert_eq!(results.todos.len(), 1);
+        assert_eq!(results.todos[0].id, "todo-3");
+        assert!(results.marks.is_empty());
+
+        let query = parse("marks after:1970-01-03 duration>=60").expect("query should parse");
+        let results = evaluate(&query, &context, todos.clone(), marks.clone());
+        let ids: Vec<&str> = results.marks.iter().map(|mark| mark.id.as_str()).collect();
+        assert_eq!(ids, vec!["mark-7"]);
+
+        let query = parse("-tag:backend").expect("query should parse");
+        let results = evaluate(&query, &context, todos, marks);
+        assert_eq!(results.todos.len(), 1);
+        assert_eq!(results.marks.len(), 2);
+    }
+}
@@ -0,0 +1,201 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::model::{EpochMinutes, Timer};
+
+pub const DEFAULT_OFFSETS_MINUTES: [EpochMinutes; 4] = [24 * 60, 60, 10, 0];
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReminderPolicy {
+    pub offsets_minutes: Vec<EpochMinutes>,
+}
+
+impl Default for ReminderPolicy {
+    fn default() -> Self {
+        Self {
+            offsets_minutes: DEFAULT_OFFSETS_MINUTES.to_vec(),
+        }
+    }
+}
+
+impl ReminderPolicy {
+    pub fn new(offsets_minutes: Vec<EpochMinutes>) -> AppResult<Self> {
+        if let Some(offset) = offsets_minutes.iter().find(|offset| **offset < 0) {
+            return Err(AppError::Validation(format!(
+                "reminder offset must not be negative, got {offset}"
+            )));
+        }
+
+        let mut offsets_minutes = offsets_minutes;
+        offsets_minutes.sort_unstable_by(|left, right| right.cmp(left));
+        offsets_minutes.dedup();
+        Ok(Self { offsets_minutes })
+    }
+
+    pub fn parse(value: &str) -> AppResult<Self> {
+        let offsets = value
+            .split(',')
+            .map(str::trim)
+            .filter(|part| !part.is_empty())
+            .map(|part| {
+                part.parse::<EpochMinutes>().map_err(|error| {
+                    AppError::Validation(format!("invalid reminder offset '{part}': {error}"))
+                })
+            })
+            .collect::<AppResult<Vec<EpochMinutes>>>()?;
+        Self::new(offsets)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FiredReminder {
+    pub timer_id: String,
+    pub target_at_minute: EpochMinutes,
+    pub offset_minutes: EpochMinutes,
+    pub fired_at_minute: EpochMinutes,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReminderKind {
+    Upcoming,
+    Overdue,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReminderEvent {
+    pub timer_id: String,
+    pub timer_name: String,
+    pub target_at_minute: EpochMinutes,
+    pub offset_minutes: EpochMinutes,
+    pub remaining_minutes: EpochMinutes,
+    pub kind: ReminderKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ReminderPlan {
+    pub events: Vec<ReminderEvent>,
+    pub fired: Vec<FiredReminder>,
+}
+
+pub fn plan_reminders(
+    timers: &[Timer],
+    fired: &[FiredReminder],
+    policy: &ReminderPolicy,
+    now_minute: EpochMinutes,
+) -> ReminderPlan {
+    let mut plan = ReminderPlan::default();
+
+    for timer in timers.iter().filter(|timer| !timer.archived) {
+        let pending: Vec<EpochMinutes> = policy
+            .offsets_minutes
+            .iter()
+            .copied()
+            .filter(|offset| now_minute >= timer.target_at_minute - offset)
+            .filter(|offset| {
+                !fired.iter().any(|record| {
+                    record.timer_id == timer.id
+                        && record.target_at_minute == timer.target_at_minute
+                        && record.offset_minutes == *offset
+                })
+            })
+            .collect();
+
+        let Some(closest_offset) = pending.iter().copied().min() else {
+            continue;
+        };
+
+        let remaining_minutes = timer.remaining_minutes(now_minute);
+        plan.events.push(ReminderEvent {
+            timer_id: timer.id.clone(),
+            timer_name: timer.name.clone(),
+            target_at_minute: timer.target_at_minute,
+            offset_minutes: closest_offset,
+            remaining_minutes,
+            kind: if remaining_minutes <= 0 {
+                ReminderKind::Overdue
+            } else {
+                ReminderKind::Upcoming
+            },
+        });
+
+        for offset in pending {
+            plan.fired.push(FiredReminder {
+                timer_id: timer.id.clone(),
+                target_at_minute: timer.target_at_minute,
+                offset_minutes: offset,
+                fired_at_minute: now_minute,
+            });
+        }
+    }
+
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{plan_reminders, FiredReminder, ReminderKind, ReminderPolicy};
+    use crate::model::Timer;
+
+    fn timer(id: &str, target_at_minute: i64) -> Timer {
+        Timer {
+            id: id.to_string(),
+            name: id.to_string(),
+            target_at_minute,
+            created_at_minute: 0,
+            updated_at_minute: 0,
+            archived: false,
+            project_id: None,
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn fires_only_the_closest_passed_threshold() {
+        let timers = vec![timer("timer-1", 2_000)];
+        let policy = ReminderPolicy::default();
+
+        assert!(plan_reminders(&timers, &[], &policy, 500).events.is_empty());
+
+        let plan = plan_reminders(&timers, &[], &policy, 1_995);
+        assert_eq!(plan.events.len(), 1);
+        assert_eq!(plan.events[0].offset_minutes, 10);
+        assert_eq!(plan.events[0].kind, ReminderKind::Upcoming);
+        assert_eq!(plan.fired.len(), 3);
+
+        let later = plan_reminders(&timers, &plan.fired, &policy, 1_998);
+        assert!(later.events.is_empty());
+
+        let overdue = plan_reminders(&timers, &plan.fired, &policy, 2_001);
+        assert_eq!(overdue.events.len(), 1);
+        assert_eq!(overdue.events[0].kind, ReminderKind::Overdue);
+    }
+
+    #[test]
+    fn rearms_when_target_moves_and_skips_archived() {
+        let mut moved = timer("timer-1", 3_000);
+        let fired = vec![FiredReminder {
+            timer_id: "timer-1".to_string(),
+            target_at_minute: 2_000,
+            offset_minutes: 60,
+            fired_at_minute: 1_940,
+        }];
+        let policy = ReminderPolicy::new(vec![60]).expect("policy should be valid");
+
+        let plan = plan_reminders(&[moved.clone()], &fired, &policy, 2_950);
+        assert_eq!(plan.events.len(), 1);
+
+        moved.archived = true;
+        assert!(plan_reminders(&[moved], &[], &policy, 2_950)
+            .events
+            .is_empty());
+    }
+
+    #[test]
+    fn parses_policy_offsets() {
+        let policy = ReminderPolicy::parse("10, 1440,60,10").expect("policy should parse");
+        assert_eq!(policy.offsets_minutes, vec![1440, 60, 10]);
+        assert!(ReminderPolicy::parse("-5").is_err());
+        assert!(ReminderPolicy::parse("soon").is_err());
+    }
+}
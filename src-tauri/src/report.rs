@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::calendar;
+use crate::model::{Category, EpochMinutes, Mark, Timer};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CategoryMinutes {
+    pub category_id: Option<String>,
+    pub category_name: Option<String>,
+    pub minutes: EpochMinutes,
+    pub mark_count: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TimerCategoryBreakdown {
+    pub timer_id: String,
+    pub timer_name: String,
+    pub categories: Vec<CategoryMinutes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DayCategoryBreakdown {
+    pub date: String,
+    pub categories: Vec<CategoryMinutes>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CategoryReport {
+    pub from_minute: EpochMinutes,
+    pub to_minute: EpochMinutes,
+    pub utc_offset_minutes: EpochMinutes,
+    pub by_timer: Vec<TimerCategoryBreakdown>,
+    pub by_day: Vec<DayCategoryBreakdown>,
+    pub totals: Vec<CategoryMinutes>,
+}
+
+type CategoryTotals = BTreeMap<Option<String>, (EpochMinutes, usize)>;
+
+pub fn category_report(
+    timers: &[Timer],
+    marks: &[Mark],
+    categories: &[Category],
+    from_minute: EpochMinutes,
+    to_minute: EpochMinutes,
+    utc_offset_minutes: EpochMinutes,
+) -> CategoryReport {
+    let mut by_timer: BTreeMap<String, CategoryTotals> = BTreeMap::new();
+    let mut by_day: BTreeMap<i64, CategoryTotals> = BTreeMap::new();
+    let mut totals = CategoryTotals::new();
+
+    for mark in marks {
+        let Some(start_minute) = mark.prev_marked_at_minute else {
+            continue;
+        };
+        let start_minute = start_minute.max(from_minute);
+        let end_minute = mark.marked_at_minute.min(to_minute);
+        if end_minute <= start_minute {
+            continue;
+        }
+
+        let category_id = mark.category_id.clone();
+        let minutes = end_minute - start_minute;
+        add_minutes(
+            by_timer.entry(mark.timer_id.clone()).or_default(),
+            &category_id,
+            minutes,
+        );
+        add_minutes(&mut totals, &category_id, minutes);
+        for (day, day_minutes) in
+            calendar::split_by_day(start_minute, end_minute, utc_offset_minutes)
+        {
+            add_minutes(by_day.entry(day).or_default(), &category_id, day_minutes);
+        }
+    }
+
+    CategoryReport {
+        from_minute,
+        to_minute,
+        utc_offset_minutes,
+        by_timer: by_timer
+            .into_iter()
+            .map(|(timer_id, totals)| TimerCategoryBreakdown {
+                timer_name: timers
+                    .iter()
+                    .find(|timer| timer.id == timer_id)
+                    .map(|timer| timer.name.clone())
+                    .unwrap_or_default(),
+                timer_id,
+                categories: category_rows(totals, categories),
+            })
+            .collect(),
+        by_day: by_day
+            .into_iter()
+            .map(|(day, totals)| DayCategoryBreakdown {
+                date: calendar::format_day(day),
+                categories: category_rows(totals, categories),
+            })
+            .collect(),
+        totals: category_rows(totals, categories),
+    }
+}
+
+fn add_minutes(totals: &mut CategoryTotals, category_id: &Option<String>, minutes: EpochMinutes) {
+    let entry = totals.entry(category_id.clone()).or_insert((0, 0));
+    entry.0 += minutes;
+    entry.1 += 1;
+}
+
+fn category_rows(totals: CategoryTotals, categories: &[Category]) -> Vec<CategoryMinutes> {
+    let mut rows: Vec<CategoryMinutes> = totals
+        .into_iter()
+        .map(|(category_id, (minutes, mark_count))| CategoryMinutes {
+            category_name: category_id.as_ref().and_then(|id| {
+                categories
+                    .iter()
+                    .find(|category| &category.id == id)
+                    .map(|category| category.name.clone())
+            }),
+            category_id,
+            minutes,
+            mark_count,
+        })
+        .collect();
+    rows.sort_by_key(|row| std::cmp::Reverse(row.minutes));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::category_report;
+    use crate::calendar::MINUTES_PER_DAY;
+    use crate::model::{Category, Mark, Timer};
+
+    fn mark(id: &str, prev: Option<i64>, at: i64, category_id: Option<&str>) -> Mark {
+        Mark {
+            id: id.to_string(),
+            timer_id: "timer-1".to_string(),
+            marked_at_minute: at,
+            prev_marked_at_minute: prev,
+            duration_minutes: prev.map(|prev| at - prev),
+            description: String::new(),
+            todo_ids: vec![],
+            tags: vec![],
+            category_id: category_id.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn aggregates_minutes_by_category_timer_and_day() {
+        let timers = vec![Timer {
+            id: "timer-1".to_string(),
+            name: "sprint".to_string(),
+            target_at_minute: 10 * MINUTES_PER_DAY,
+            created_at_minute: 0,
+            updated_at_minute: 0,
+            archived: false,
+            project_id: None,
+            tags: vec![],
+        }];
+        let categories = vec![Category {
+            id: "category-1".to_string(),
+            name: "meetings".to_string(),
+            created_at_minute: 0,
+            updated_at_minute: 0,
+        }];
+        let day_one_end = 2 * MINUTES_PER_DAY;
+        let marks = vec![
+            mark("mark-1", None, day_one_end - 300, None),
+            mark(
+                "mark-2",
+                Some(day_one_end - 300),
+                day_one_end - 240,
+                Some("category-1"),
+            ),
+            mark("mark-3", Some(day_one_end - 240), day_one_end + 60, None),
+        ];
+
+        let report = category_report(&timers, &marks, &categories, 0, 10 * MINUTES_PER_DAY, 0);
+
+        assert_eq!(report.totals.len(), 2);
+        assert_eq!(report.totals[0].category_id, None);
+        assert_eq!(report.totals[0].minutes, 300);
+        assert_eq!(report.totals[1].category_name.as_deref(), Some("meetings"));
+        assert_eq!(report.totals[1].minutes, 60);
+
+        assert_eq!(report.by_timer.len(), 1);
+        assert_eq!(report.by_timer[0].timer_name, "sprint");
+
+        assert_eq!(report.by_day.len(), 2);
+        assert_eq!(report.by_day[0].date, "1970-01-02");
+        assert_eq!(report.by_day[0].categories[0].minutes, 240);
+        assert_eq!(report.by_day[1].categories[0].minutes, 60);
+    }
+}
@@ -1,21 +1,117 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::fs::File;
-use std::io::{Read, Write};
+use std::fs::{File, OpenOptions};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{ErrorKind, Read, Write};
 use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
+use crate::backup::{self, BackupInfo, BackupPolicy};
+use crate::dataset;
 use crate::error::{AppError, AppResult};
-use crate::model::{Mark, Timer, Todo, TodoStatus};
-
-const TIMERS_HEADER: &str = "id,name,target_at,created_at,updated_at,archived";
+use crate::event::{DomainEvent, EventKind};
+use crate::history::{Change, Entity};
+use crate::lock::{self, DataDirLock};
+use crate::model::{Category, Mark, MarkTemplate, Project, Timer, Todo, TodoStatus};
+use crate::page::{self, Page, PageRequest};
+use crate::reminder::FiredReminder;
+use crate::sync::{self, EntityKey, LogEntry, SyncConflict, SyncReport, Version};
+
+const TIMERS_HEADER: &str = "id,name,target_at,created_at,updated_at,archived,project_id,tags";
 const MARKS_HEADER: &str =
-    "id,timer_id,marked_at,prev_marked_at,duration_minutes,description,todo_ids";
-const TODOS_HEADER: &str = "id,timer_id,title,status,created_at,updated_at,done_at";
+    "id,timer_id,marked_at,prev_marked_at,duration_minutes,description,todo_ids,tags,category_id";
+const TODOS_HEADER: &str = "id,timer_id,title,status,created_at,updated_at,done_at,tags";
+const PROJECTS_HEADER: &str = "id,name,created_at,updated_at";
+const CATEGORIES_HEADER: &str = "id,name,created_at,updated_at";
+const TEMPLATES_HEADER: &str = "id,name,body,created_at,updated_at";
+const REMINDERS_HEADER: &str = "timer_id,target_at,offset_minutes,fired_at";
+const EVENTS_HEADER: &str = "sequence,kind,entity_type,entity_id,at,state";
+const LOG_HEADER: &str = "clock,device,sequence,parent,entity_type,entity_id,state";
+const BATCH_JOURNAL: &str = "batch.journal";
+const DATA_FILES: [(&str, &str); 7] = [
+    ("timers.csv", TIMERS_HEADER),
+    ("marks.csv", MARKS_HEADER),
+    ("todos.csv", TODOS_HEADER),
+    ("projects.csv", PROJECTS_HEADER),
+    ("categories.csv", CATEGORIES_HEADER),
+    ("templates.csv", TEMPLATES_HEADER),
+    ("reminders.csv", REMINDERS_HEADER),
+];
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StoreContents {
+    pub timers: Vec<Timer>,
+    pub projects: Vec<Project>,
+    pub categories: Vec<Category>,
+    pub templates: Vec<MarkTemplate>,
+    pub todos: Vec<Todo>,
+    pub marks: Vec<Mark>,
+}
+
+impl StoreContents {
+    pub fn load(store: &impl Store) -> Self {
+        let timers = store.list_timers(true);
+        let todos = timers
+            .iter()
+            .flat_map(|timer| store.list_todos_by_timer(&timer.id))
+            .collect();
+        let marks = timers
+            .iter()
+            .flat_map(|timer| store.list_marks_by_timer(&timer.id))
+            .collect();
+        Self {
+            timers,
+            projects: store.list_projects(),
+            categories: store.list_categories(),
+            templates: store.list_templates(),
+            todos,
+            marks,
+        }
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = &str> {
+        self.projects
+            .iter()
+            .map(|project| project.id.as_str())
+            .chain(self.categories.iter().map(|category| category.id.as_str()))
+            .chain(self.templates.iter().map(|template| template.id.as_str()))
+            .chain(self.timers.iter().map(|timer| timer.id.as_str()))
+            .chain(self.todos.iter().map(|todo| todo.id.as_str()))
+            .chain(self.marks.iter().map(|mark| mark.id.as_str()))
+    }
+
+    pub fn into_entities(self) -> Vec<Entity> {
+        let mut entities = Vec::new();
+        entities.extend(self.projects.into_iter().map(Entity::Project));
+        entities.extend(self.categories.into_iter().map(Entity::Category));
+        entities.extend(self.templates.into_iter().map(Entity::Template));
+        entities.extend(self.timers.into_iter().map(Entity::Timer));
+        entities.extend(self.todos.into_iter().map(Entity::Todo));
+        entities.extend(self.marks.into_iter().map(Entity::Mark));
+        entities
+    }
+}
 
 pub trait Store {
     fn save_timer(&mut self, timer: Timer) -> AppResult<()>;
     fn get_timer(&self, timer_id: &str) -> Option<Timer>;
     fn list_timers(&self, include_archived: bool) -> Vec<Timer>;
+    fn delete_timer(&mut self, timer_id: &str) -> AppResult<()>;
+
+    fn save_project(&mut self, project: Project) -> AppResult<()>;
+    fn get_project(&self, project_id: &str) -> Option<Project>;
+    fn delete_project(&mut self, project_id: &str) -> AppResult<()>;
+    fn list_projects(&self) -> Vec<Project>;
+
+    fn save_category(&mut self, category: Category) -> AppResult<()>;
+    fn get_category(&self, category_id: &str) -> Option<Category>;
+    fn delete_category(&mut self, category_id: &str) -> AppResult<()>;
+    fn list_categories(&self) -> Vec<Category>;
+
+    fn save_template(&mut self, template: MarkTemplate) -> AppResult<()>;
+    fn get_template(&self, template_id: &str) -> Option<MarkTemplate>;
+    fn delete_template(&mut self, template_id: &str) -> AppResult<()>;
+    fn list_templates(&self) -> Vec<MarkTemplate>;
 
     fn save_todo(&mut self, todo: Todo) -> AppResult<()>;
     fn get_todo(&self, todo_id: &str) -> Option<Todo>;
@@ -23,14 +119,40 @@ pub trait Store {
     fn list_todos_by_timer(&self, timer_id: &str) -> Vec<Todo>;
 
     fn append_mark(&mut self, mark: Mark) -> AppResult<()>;
+    fn update_mark(&mut self, mark: Mark) -> AppResult<()>;
+    fn delete_mark(&mut self, mark_id: &str) -> AppResult<()>;
+    fn get_mark(&self, mark_id: &str) -> Option<Mark>;
+
+    fn list_fired_reminders(&self) -> Vec<FiredReminder>;
+    fn record_fired_reminders(&mut self, reminders: Vec<FiredReminder>) -> AppResult<()>;
     fn list_marks_by_timer(&self, timer_id: &str) -> Vec<Mark>;
+    fn latest_mark_by_timer(&self, timer_id: &str) -> Option<Mark>;
+    fn page_marks_by_timer(&self, timer_id: &str, request: &PageRequest) -> AppResult<Page<Mark>>;
+    fn page_todos_by_timer(&self, timer_id: &str, request: &PageRequest) -> AppResult<Page<Todo>>;
+
+    fn append_events(&mut self, events: Vec<DomainEvent>) -> AppResult<()>;
+    fn list_events(&self) -> Vec<DomainEvent>;
+    fn replace_contents(&mut self, contents: StoreContents) -> AppResult<()>;
+    fn apply_changes(&mut self, changes: Vec<Change>) -> AppResult<()>;
+    fn refresh(&mut self) -> AppResult<SyncReport>;
+    fn id_scope(&self) -> Option<String>;
+    fn is_read_only(&self) -> bool;
+
+    fn list_backups(&self) -> AppResult<Vec<BackupInfo>>;
+    fn create_backup(&mut self) -> AppResult<BackupInfo>;
+    fn restore_backup(&mut self, backup_id: &str) -> AppResult<BackupInfo>;
 }
 
 #[derive(Debug, Default)]
 pub struct InMemoryStore {
     timers: HashMap<String, Timer>,
+    projects: HashMap<String, Project>,
+    categories: HashMap<String, Category>,
+    templates: HashMap<String, MarkTemplate>,
     todos: HashMap<String, Todo>,
     marks_by_timer: HashMap<String, Vec<Mark>>,
+    fired_reminders: Vec<FiredReminder>,
+    events: Vec<DomainEvent>,
 }
 
 impl Store for InMemoryStore {
@@ -54,6 +176,81 @@ impl Store for InMemoryStore {
         timers
     }
 
+    fn delete_timer(&mut self, timer_id: &str) -> AppResult<()> {
+        self.timers
+            .remove(timer_id)
+            .ok_or_else(|| AppError::NotFound(format!("timer {timer_id}")))?;
+        self.todos.retain(|_, todo| todo.timer_id != timer_id);
+        self.marks_by_timer.remove(timer_id);
+        Ok(())
+    }
+
+    fn save_project(&mut self, project: Project) -> AppResult<()> {
+        self.projects.insert(project.id.clone(), project);
+        Ok(())
+    }
+
+    fn get_project(&self, project_id: &str) -> Option<Project> {
+        self.projects.get(project_id).cloned()
+    }
+
+    fn delete_project(&mut self, project_id: &str) -> AppResult<()> {
+        self.projects
+            .remove(project_id)
+            .map(|_| ())
+            .ok_or_else(|| AppError::NotFound(format!("project {project_id}")))
+    }
+
+    fn list_projects(&self) -> Vec<Project> {
+        let mut projects: Vec<Project> = self.projects.values().cloned().collect();
+        projects.sort_by(|left, right| left.name.cmp(&right.name));
+        projects
+    }
+
+    fn save_category(&mut self, category: Category) -> AppResult<()> {
+        self.categories.insert(category.id.clone(), category);
+        Ok(())
+    }
+
+    fn get_category(&self, category_id: &str) -> Option<Category> {
+        self.categories.get(category_id).cloned()
+    }
+
+    fn delete_category(&mut self, category_id: &str) -> AppResult<()> {
+        self.categories
+            .remove(category_id)
+            .map(|_| ())
+            .ok_or_else(|| AppError::NotFound(format!("category {category_id}")))
+    }
+
+    fn list_categories(&self) -> Vec<Category> {
+        let mut categories: Vec<Category> = self.categories.values().cloned().collect();
+        categories.sort_by(|left, right| left.name.cmp(&right.name));
+        categories
+    }
+
+    fn save_template(&mut self, template: MarkTemplate) -> AppResult<()> {
+        self.templates.insert(template.id.clone(), template);
+        Ok(())
+    }
+
+    fn get_template(&self, template_id: &str) -> Option<MarkTemplate> {
+        self.templates.get(template_id).cloned()
+    }
+
+    fn delete_template(&mut self, template_id: &str) -> AppResult<()> {
+        self.templates
+            .remove(template_id)
+            .map(|_| ())
+            .ok_or_else(|| AppError::NotFound(format!("template {template_id}")))
+    }
+
+    fn list_templates(&self) -> Vec<MarkTemplate> {
+        let mut templates: Vec<MarkTemplate> = self.templates.values().cloned().collect();
+        templates.sort_by(|left, right| left.name.cmp(&right.name));
+        templates
+    }
+
     fn save_todo(&mut self, todo: Todo) -> AppResult<()> {
         self.todos.insert(todo.id.clone(), todo);
         Ok(())
@@ -77,26 +274,157 @@ impl Store for InMemoryStore {
             .filter(|todo| todo.timer_id == timer_id)
             .cloned()
             .collect();
-        todos.sort_by_key(|todo| todo.created_at_minute);
+        todos.sort_by(|left, right| {
+            (left.created_at_minute, &left.id).cmp(&(right.created_at_minute, &right.id))
+        });
         todos
     }
 
     fn append_mark(&mut self, mark: Mark) -> AppResult<()> {
+        insert_sorted_mark(
+            self.marks_by_timer
+                .entry(mark.timer_id.clone())
+                .or_default(),
+            mark,
+        );
+        Ok(())
+    }
+
+    fn update_mark(&mut self, mark: Mark) -> AppResult<()> {
+        let marks = self
+            .marks_by_timer
+            .get_mut(&mark.timer_id)
+            .ok_or_else(|| AppError::NotFound(format!("mark {}", mark.id)))?;
+        let position = marks
+            .iter()
+            .position(|existing| existing.id == mark.id)
+            .ok_or_else(|| AppError::NotFound(format!("mark {}", mark.id)))?;
+        marks.remove(position);
+        insert_sorted_mark(marks, mark);
+        Ok(())
+    }
+
+    fn delete_mark(&mut self, mark_id: &str) -> AppResult<()> {
+        let marks = self
+            .marks_by_timer
+            .values_mut()
+            .find(|marks| marks.iter().any(|mark| mark.id == mark_id))
+            .ok_or_else(|| AppError::NotFound(format!("mark {mark_id}")))?;
+        marks.retain(|mark| mark.id != mark_id);
+        Ok(())
+    }
+
+    fn get_mark(&self, mark_id: &str) -> Option<Mark> {
         self.marks_by_timer
-            .entry(mark.timer_id.clone())
-            .or_default()
-            .push(mark);
+            .values()
+            .flat_map(|marks| marks.iter())
+            .find(|mark| mark.id == mark_id)
+            .cloned()
+    }
+
+    fn list_fired_reminders(&self) -> Vec<FiredReminder> {
+        self.fired_reminders.clone()
+    }
+
+    fn record_fired_reminders(&mut self, reminders: Vec<FiredReminder>) -> AppResult<()> {
+        self.fired_reminders.extend(reminders);
         Ok(())
     }
 
     fn list_marks_by_timer(&self, timer_id: &str) -> Vec<Mark> {
-        let mut marks = self
-            .marks_by_timer
+        self.marks_by_timer
+            .get(timer_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn latest_mark_by_timer(&self, timer_id: &str) -> Option<Mark> {
+        self.marks_by_timer
             .get(timer_id)
+            .and_then(|marks| marks.last())
             .cloned()
+    }
+
+    fn page_marks_by_timer(&self, timer_id: &str, request: &PageRequest) -> AppResult<Page<Mark>> {
+        let marks = self
+            .marks_by_timer
+            .get(timer_id)
+            .map(Vec::as_slice)
             .unwrap_or_default();
-        marks.sort_by_key(|mark| mark.marked_at_minute);
-        marks
+        page::paginate(marks, mark_key, request)
+    }
+
+    fn page_todos_by_timer(&self, timer_id: &str, request: &PageRequest) -> AppResult<Page<Todo>> {
+        page_todos(self.todos.values(), timer_id, request)
+    }
+
+    fn append_events(&mut self, events: Vec<DomainEvent>) -> AppResult<()> {
+        self.events.extend(events);
+        Ok(())
+    }
+
+    fn list_events(&self) -> Vec<DomainEvent> {
+        self.events.clone()
+    }
+
+    fn replace_contents(&mut self, contents: StoreContents) -> AppResult<()> {
+        self.timers = index_by_id(contents.timers, |timer| &timer.id);
+        self.projects = index_by_id(contents.projects, |project| &project.id);
+        self.categories = index_by_id(contents.categories, |category| &category.id);
+        self.templates = index_by_id(contents.templates, |template| &template.id);
+        self.todos = index_by_id(contents.todos, |todo| &todo.id);
+        self.marks_by_timer = group_marks_by_timer(contents.marks);
+        Ok(())
+    }
+
+    fn apply_changes(&mut self, changes: Vec<Change>) -> AppResult<()> {
+        for change in changes {
+            match (change.before, change.after) {
+                (_, Some(Entity::Timer(timer))) => self.save_timer(timer)?,
+                (_, Some(Entity::Project(project))) => self.save_project(project)?,
+                (_, Some(Entity::Category(category))) => self.save_category(category)?,
+                (_, Some(Entity::Template(template))) => self.save_template(template)?,
+                (_, Some(Entity::Todo(todo))) => self.save_todo(todo)?,
+                (Some(_), Some(Entity::Mark(mark))) => self.update_mark(mark)?,
+                (None, Some(Entity::Mark(mark))) => self.append_mark(mark)?,
+                (Some(Entity::Timer(timer)), None) => {
+                    self.timers.remove(&timer.id);
+                }
+                (Some(Entity::Project(project)), None) => self.delete_project(&project.id)?,
+                (Some(Entity::Category(category)), None) => self.delete_category(&category.id)?,
+                (Some(Entity::Template(template)), None) => self.delete_template(&template.id)?,
+                (Some(Entity::Todo(todo)), None) => self.delete_todo(&todo.id)?,
+                (Some(Entity::Mark(mark)), None) => self.delete_mark(&mark.id)?,
+                (None, None) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn refresh(&mut self) -> AppResult<SyncReport> {
+        Ok(SyncReport::default())
+    }
+
+    fn id_scope(&self) -> Option<String> {
+        None
+    }
+
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
+    fn list_backups(&self) -> AppResult<Vec<BackupInfo>> {
+        Ok(Vec::new())
+    }
+
+    fn create_backup(&mut self) -> AppResult<BackupInfo> {
+        Err(AppError::Validation(
+            "in-memory store does not keep backups".to_string(),
+        ))
+    }
+
+    fn restore_backup(&mut self, backup_id: &str) -> AppResult<BackupInfo> {
+        Err(AppError::NotFound(format!("backup {backup_id}")))
     }
 }
 
@@ -104,34 +432,149 @@ impl Store for InMemoryStore {
 pub struct CsvStore {
     root: PathBuf,
     timers: HashMap<String, Timer>,
+    projects: HashMap<String, Project>,
+    categories: HashMap<String, Category>,
+    templates: HashMap<String, MarkTemplate>,
     todos: HashMap<String, Todo>,
     marks_by_timer: HashMap<String, Vec<Mark>>,
+    fired_reminders: Vec<FiredReminder>,
+    events: Vec<DomainEvent>,
+    backup_policy: BackupPolicy,
+    writes_since_backup: usize,
+    sync: Option<SyncState>,
+    lock: Option<DataDirLock>,
+    file_signatures: HashMap<PathBuf, FileSignature>,
+    reloaded_on_conflict: bool,
+}
+
+#[derive(Debug)]
+struct SyncState {
+    device: String,
+    clock: u64,
+    sequence: u64,
+    versions: HashMap<EntityKey, Version>,
+    logged: BTreeMap<EntityKey, Entity>,
+    signatures: BTreeMap<PathBuf, FileSignature>,
+    conflicts: Vec<SyncConflict>,
 }
 
 impl CsvStore {
     pub fn new(root: impl Into<PathBuf>) -> AppResult<Self> {
+        Self::with_backup_policy(root, BackupPolicy::default())
+    }
+
+    pub fn with_backup_policy(
+        root: impl Into<PathBuf>,
+        backup_policy: BackupPolicy,
+    ) -> AppResult<Self> {
+        Self::open(root.into(), backup_policy, None)
+    }
+
+    pub fn with_device(root: impl Into<PathBuf>, device: &str) -> AppResult<Self> {
+        sync::validate_device(device)?;
+        Self::open(root.into(), BackupPolicy::default(), Some(device))
+    }
+
+    pub fn open_read_only(root: impl Into<PathBuf>) -> AppResult<Self> {
         let root = root.into();
-        fs::create_dir_all(&root).map_err(|error| {
-            AppError::Internal(format!("failed to create data directory {root:?}: {error}"))
+        if !root.is_dir() {
+            return Err(AppError::NotFound(format!("data directory {root:?}")));
+        }
+        let mut store = Self::load(root, BackupPolicy::default())?;
+        if store.root.join(sync::SYNC_DIR).is_dir() {
+            store.apply_logs()?;
+        }
+        store.events = load_events(&store.events_path())?;
+        Ok(store)
+    }
+
+    fn open(root: PathBuf, backup_policy: BackupPolicy, device: Option<&str>) -> AppResult<Self> {
+        let lock_dir = match device {
+            Some(_) => root.join(sync::SYNC_DIR),
+            None => root.clone(),
+        };
+        fs::create_dir_all(&lock_dir).map_err(|error| {
+            AppError::Internal(format!(
+                "failed to create data directory {lock_dir:?}: {error}"
+            ))
+        })?;
+        let lock = DataDirLock::acquire(match device {
+            Some(device) => lock_dir.join(format!("{device}{}", sync::LOCK_SUFFIX)),
+            None => lock_dir.join(lock::LOCK_FILE),
         })?;
 
-        let timers_path = root.join("timers.csv");
-        let marks_path = root.join("marks.csv");
-        let todos_path = root.join("todos.csv");
+        if device.is_none() {
+            recover_batch(&root.join(BATCH_JOURNAL))?;
+        }
 
-        ensure_csv_file(&timers_path, TIMERS_HEADER)?;
-        ensure_csv_file(&marks_path, MARKS_HEADER)?;
-        ensure_csv_file(&todos_path, TODOS_HEADER)?;
+        for (file_name, header) in DATA_FILES {
+            ensure_csv_file(&root.join(file_name), header)?;
+        }
+        ensure_csv_file(&root.join("events.csv"), EVENTS_HEADER)?;
+
+        let mut store = Self::load(root, backup_policy)?;
+        store.lock = Some(lock);
+        if let Some(device) = device {
+            store.sync = Some(SyncState {
+                device: device.to_string(),
+                clock: 0,
+                sequence: 0,
+                versions: HashMap::new(),
+                logged: BTreeMap::new(),
+                signatures: BTreeMap::new(),
+                conflicts: Vec::new(),
+            });
+            ensure_csv_file(&store.log_path(), LOG_HEADER)?;
+            ensure_csv_file(&store.events_path(), EVENTS_HEADER)?;
+            ensure_csv_file(&store.reminders_path(), REMINDERS_HEADER)?;
+            store.fired_reminders = load_reminders(&store.reminders_path())?;
+            store.apply_logs()?;
+        }
+        // Undo history does not survive a restart, so deleted timers' rows can go.
+        store
+            .fired_reminders
+            .retain(|reminder| store.timers.contains_key(&reminder.timer_id));
+        store.events = load_events(&store.events_path())?;
+        let has_data =
+            !store.timers.is_empty() || !store.projects.is_empty() || !store.categories.is_empty();
+        if has_data && store.sync.is_none() {
+            store.create_backup()?;
+        }
+        Ok(store)
+    }
 
-        let timers = load_timers(&timers_path)?;
-        let marks_by_timer = load_marks(&marks_path)?;
-        let todos = load_todos(&todos_path)?;
+    fn load(root: PathBuf, backup_policy: BackupPolicy) -> AppResult<Self> {
+        let timers = load_timers(&root.join("timers.csv"))?;
+        let marks_by_timer = load_marks(&root.join("marks.csv"))?;
+        let todos = load_todos(&root.join("todos.csv"))?;
+        let projects = load_projects(&root.join("projects.csv"))?;
+        let categories = load_categories(&root.join("categories.csv"))?;
+        let fired_reminders = load_reminders(&root.join("reminders.csv"))?;
+        let templates = load_templates(&root.join("templates.csv"))?;
+        let mut file_signatures = HashMap::new();
+        for (file_name, _) in DATA_FILES {
+            let path = root.join(file_name);
+            if let Some(signature) = FileSignature::read(&path)? {
+                file_signatures.insert(path, signature);
+            }
+        }
 
         Ok(Self {
             root,
             timers,
+            projects,
+            categories,
+            templates,
             todos,
             marks_by_timer,
+            fired_reminders,
+            events: Vec::new(),
+            backup_policy,
+            writes_since_backup: 0,
+            sync: None,
+            lock: None,
+            file_signatures,
+            reloaded_on_conflict: false,
         })
     }
 
@@ -147,174 +590,918 @@ impl CsvStore {
         self.root.join("todos.csv")
     }
 
-    fn persist_timers(&self) -> AppResult<()> {
-        let mut timers: Vec<&Timer> = self.timers.values().collect();
-        timers.sort_by(|left, right| left.id.cmp(&right.id));
+    fn projects_path(&self) -> PathBuf {
+        self.root.join("projects.csv")
+    }
 
-        let mut rows = Vec::with_capacity(timers.len() + 1);
-        rows.push(TIMERS_HEADER.to_string());
-        for timer in timers {
-            rows.push(csv_row(&[
-                &timer.id,
-                &timer.name,
-                &timer.target_at_minute.to_string(),
-                &timer.created_at_minute.to_string(),
-                &timer.updated_at_minute.to_string(),
-                &timer.archived.to_string(),
-            ]));
-        }
+    fn categories_path(&self) -> PathBuf {
+        self.root.join("categories.csv")
+    }
 
-        write_atomic(&self.timers_path(), &rows.join("\n"))
+    fn reminders_path(&self) -> PathBuf {
+        self.device_path(sync::REMINDERS_SUFFIX)
+            .unwrap_or_else(|| self.root.join("reminders.csv"))
     }
 
-    fn persist_marks(&self) -> AppResult<()> {
-        let mut marks: Vec<&Mark> = self
-            .marks_by_timer
-            .values()
-            .flat_map(|timer_marks| timer_marks.iter())
-            .collect();
-        marks.sort_by_key(|mark| {
-            (
-                mark.timer_id.clone(),
-                mark.marked_at_minute,
-                mark.id.clone(),
-            )
-        });
+    fn templates_path(&self) -> PathBuf {
+        self.root.join("templates.csv")
+    }
 
-        let mut rows = Vec::with_capacity(marks.len() + 1);
-        rows.push(MARKS_HEADER.to_string());
-        for mark in marks {
-            rows.push(csv_row(&[
-                &mark.id,
-                &mark.timer_id,
-                &mark.marked_at_minute.to_string(),
-                &optional_i64_to_csv(mark.prev_marked_at_minute),
-                &optional_i64_to_csv(mark.duration_minutes),
-                &mark.description,
-                &mark.todo_ids.join(";"),
-            ]));
-        }
+    fn events_path(&self) -> PathBuf {
+        self.device_path(sync::EVENTS_SUFFIX)
+            .unwrap_or_else(|| self.root.join("events.csv"))
+    }
 
-        write_atomic(&self.marks_path(), &rows.join("\n"))
+    fn log_path(&self) -> PathBuf {
+        self.device_path(sync::LOG_SUFFIX)
+            .unwrap_or_else(|| self.root.join(sync::SYNC_DIR))
     }
 
-    fn persist_todos(&self) -> AppResult<()> {
-        let mut todos: Vec<&Todo> = self.todos.values().collect();
-        todos.sort_by(|left, right| left.id.cmp(&right.id));
+    fn device_path(&self, suffix: &str) -> Option<PathBuf> {
+        self.sync.as_ref().map(|state| {
+            self.root
+                .join(sync::SYNC_DIR)
+                .join(format!("{}{suffix}", state.device))
+        })
+    }
 
-        let mut rows = Vec::with_capacity(todos.len() + 1);
-        rows.push(TODOS_HEADER.to_string());
-        for todo in todos {
-            rows.push(csv_row(&[
-                &todo.id,
-                &todo.timer_id,
-                &todo.title,
-                todo.status.as_str(),
-                &todo.created_at_minute.to_string(),
-                &todo.updated_at_minute.to_string(),
-                &optional_i64_to_csv(todo.done_at_minute),
-            ]));
+    fn record_write(&mut self) -> AppResult<()> {
+        self.writes_since_backup += 1;
+        if self.backup_policy.every_writes > 0
+            && self.writes_since_backup >= self.backup_policy.every_writes
+        {
+            self.create_backup()?;
         }
+        Ok(())
+    }
 
-        write_atomic(&self.todos_path(), &rows.join("\n"))
+    // Timer deletes do not cascade here: a batch lists its todos and marks itself.
+    fn apply_change(&mut self, change: Change) {
+        match (change.before, change.after) {
+            (_, Some(Entity::Timer(timer))) => {
+                self.timers.insert(timer.id.clone(), timer);
+            }
+            (_, Some(Entity::Project(project))) => {
+                self.projects.insert(project.id.clone(), project);
+            }
+            (_, Some(Entity::Category(category))) => {
+                self.categories.insert(category.id.clone(), category);
+            }
+            (_, Some(Entity::Template(template))) => {
+                self.templates.insert(template.id.clone(), template);
+            }
+            (_, Some(Entity::Todo(todo))) => {
+                self.todos.insert(todo.id.clone(), todo);
+            }
+            (_, Some(Entity::Mark(mark))) => {
+                remove_mark(&mut self.marks_by_timer, &mark.id);
+                insert_sorted_mark(
+                    self.marks_by_timer
+                        .entry(mark.timer_id.clone())
+                        .or_default(),
+                    mark,
+                );
+            }
+            (Some(Entity::Timer(timer)), None) => {
+                self.timers.remove(&timer.id);
+            }
+            (Some(Entity::Project(project)), None) => {
+                self.projects.remove(&project.id);
+            }
+            (Some(Entity::Category(category)), None) => {
+                self.categories.remove(&category.id);
+            }
+            (Some(Entity::Template(template)), None) => {
+                self.templates.remove(&template.id);
+            }
+            (Some(Entity::Todo(todo)), None) => {
+                self.todos.remove(&todo.id);
+            }
+            (Some(Entity::Mark(mark)), None) => remove_mark(&mut self.marks_by_timer, &mark.id),
+            (None, None) => {}
+        }
     }
-}
 
-impl Store for CsvStore {
-    fn save_timer(&mut self, timer: Timer) -> AppResult<()> {
-        self.timers.insert(timer.id.clone(), timer);
-        self.persist_timers()
+    fn persist_all(&mut self) -> AppResult<()> {
+        self.write_data(vec![
+            (self.timers_path(), self.render_timers()),
+            (self.marks_path(), self.render_marks()),
+            (self.todos_path(), self.render_todos()),
+            (self.projects_path(), self.render_projects()),
+            (self.categories_path(), self.render_categories()),
+            (self.templates_path(), self.render_templates()),
+            (self.reminders_path(), self.render_reminders()),
+        ])
     }
 
-    fn get_timer(&self, timer_id: &str) -> Option<Timer> {
-        self.timers.get(timer_id).cloned()
+    fn write_data(&mut self, files: Vec<(PathBuf, String)>) -> AppResult<()> {
+        if let Err(error) = self.write_files(files) {
+            // Drop the rejected in-memory change so readers keep seeing what is on disk.
+            self.discard_unsaved()?;
+            return Err(error);
+        }
+        Ok(())
     }
 
-    fn list_timers(&self, include_archived: bool) -> Vec<Timer> {
-        let mut timers: Vec<Timer> = self
-            .timers
-            .values()
-            .filter(|timer| include_archived || !timer.archived)
-            .cloned()
+    fn write_files(&mut self, files: Vec<(PathBuf, String)>) -> AppResult<()> {
+        self.ensure_writable()?;
+        if self.sync.is_none() {
+            if let Some((path, _)) = files.iter().find(|(path, _)| self.is_modified(path)) {
+                return Err(AppError::Conflict(format!(
+                    "{} was changed outside the app; reload the data directory before saving",
+                    path.file_name().unwrap_or_default().to_string_lossy()
+                )));
+            }
+            write_atomic_batch(&self.root.join(BATCH_JOURNAL), &files)?;
+            for (path, _) in &files {
+                match FileSignature::read(path)? {
+                    Some(signature) => self.file_signatures.insert(path.clone(), signature),
+                    None => self.file_signatures.remove(path),
+                };
+            }
+            return self.record_write();
+        }
+        let reminders_path = self.reminders_path();
+        let local: Vec<(PathBuf, String)> = files
+            .into_iter()
+            .filter(|(path, _)| *path == reminders_path)
             .collect();
-        timers.sort_by_key(|timer| timer.target_at_minute);
-        timers
+        write_atomic_batch(&self.root.join(BATCH_JOURNAL), &local)?;
+        self.log_changes()
     }
 
-    fn save_todo(&mut self, todo: Todo) -> AppResult<()> {
-        self.todos.insert(todo.id.clone(), todo);
-        self.persist_todos()
+    fn ensure_writable(&self) -> AppResult<()> {
+        if !self.is_read_only() {
+            return Ok(());
+        }
+        Err(AppError::Conflict(format!(
+            "data directory {:?} is open read-only",
+            self.root
+        )))
     }
 
-    fn get_todo(&self, todo_id: &str) -> Option<Todo> {
-        self.todos.get(todo_id).cloned()
+    fn discard_unsaved(&mut self) -> AppResult<()> {
+        if self.sync.is_some() {
+            return self.apply_logs();
+        }
+        self.reloaded_on_conflict |= self.has_external_edits();
+        self.reload_files()
     }
 
-    fn delete_todo(&mut self, todo_id: &str) -> AppResult<()> {
-        self.todos
-            .remove(todo_id)
-            .map(|_| ())
-            .ok_or_else(|| AppError::NotFound(format!("todo {todo_id}")))?;
-        self.persist_todos()
+    fn has_external_edits(&self) -> bool {
+        DATA_FILES
+            .iter()
+            .any(|(file_name, _)| self.is_modified(&self.root.join(file_name)))
     }
 
-    fn list_todos_by_timer(&self, timer_id: &str) -> Vec<Todo> {
-        let mut todos: Vec<Todo> = self
-            .todos
-            .values()
-            .filter(|todo| todo.timer_id == timer_id)
-            .cloned()
-            .collect();
-        todos.sort_by_key(|todo| todo.created_at_minute);
-        todos
+    fn is_modified(&self, path: &Path) -> bool {
+        match self.file_signatures.get(path) {
+            Some(signature) => !signature.is_current(path).unwrap_or(false),
+            None => path.exists(),
+        }
     }
 
-    fn append_mark(&mut self, mark: Mark) -> AppResult<()> {
-        self.marks_by_timer
-            .entry(mark.timer_id.clone())
-            .or_default()
-            .push(mark);
-        self.persist_marks()
+    fn reload_files(&mut self) -> AppResult<()> {
+        let loaded = Self::load(self.root.clone(), self.backup_policy)?;
+        self.timers = loaded.timers;
+        self.projects = loaded.projects;
+        self.categories = loaded.categories;
+        self.templates = loaded.templates;
+        self.todos = loaded.todos;
+        self.marks_by_timer = loaded.marks_by_timer;
+        self.fired_reminders = loaded.fired_reminders;
+        self.file_signatures = loaded.file_signatures;
+        Ok(())
     }
 
-    fn list_marks_by_timer(&self, timer_id: &str) -> Vec<Mark> {
-        let mut marks = self
-            .marks_by_timer
-            .get(timer_id)
-            .cloned()
-            .unwrap_or_default();
-        marks.sort_by_key(|mark| mark.marked_at_minute);
-        marks
+    fn reload_read_only(&mut self) -> AppResult<SyncReport> {
+        let reloaded = Self::open_read_only(self.root.clone())?;
+        let changed = std::mem::take(&mut self.reloaded_on_conflict)
+            || reloaded.entity_map() != self.entity_map()
+            || reloaded.events != self.events;
+        *self = reloaded;
+        Ok(SyncReport {
+            changed,
+            ..SyncReport::default()
+        })
     }
-}
 
-fn ensure_csv_file(path: &Path, header: &str) -> AppResult<()> {
-    if path.exists() {
-        return Ok(());
+    fn reload_external_edits(&mut self) -> AppResult<SyncReport> {
+        let reloaded = std::mem::take(&mut self.reloaded_on_conflict);
+        let edited = self.has_external_edits();
+        if !edited && !reloaded {
+            return Ok(SyncReport::default());
+        }
+        let before = self.entity_map();
+        if edited {
+            self.reload_files()?;
+        }
+        Ok(SyncReport {
+            changed: reloaded || before != self.entity_map(),
+            ..SyncReport::default()
+        })
     }
-    write_atomic(path, header)
-}
 
-fn load_timers(path: &Path) -> AppResult<HashMap<String, Timer>> {
-    let rows = load_csv_rows(path, TIMERS_HEADER)?;
-    let mut timers = HashMap::new();
+    fn log_changes(&mut self) -> AppResult<()> {
+        let current = self.entity_map();
+        let log_path = self.log_path();
+        let Some(state) = self.sync.as_mut() else {
+            return Ok(());
+        };
+        let mut changes: Vec<(EntityKey, Option<Entity>)> = current
+            .iter()
+            .filter(|(key, entity)| state.logged.get(*key) != Some(*entity))
+            .map(|(key, entity)| (key.clone(), Some(entity.clone())))
+            .collect();
+        changes.extend(
+            state
+                .logged
+                .keys()
+                .filter(|key| !current.contains_key(*key))
+                .map(|key| (key.clone(), None)),
+        );
+        if changes.is_empty() {
+            return Ok(());
+        }
 
-    for fields in rows {
-        if fields.len() != 6 {
-            return Err(AppError::Internal(format!(
-                "timers.csv expected 6 columns, got {}",
-                fields.len()
-            )));
+        let mut rows = Vec::with_capacity(changes.len());
+        for ((entity_type, entity_id), entity) in changes {
+            state.clock += 1;
+            state.sequence += 1;
+            let version = Version {
+                clock: state.clock,
+                device: state.device.clone(),
+            };
+            let key = (entity_type.clone(), entity_id.clone());
+            let entry = LogEntry {
+                parent: state.versions.insert(key, version.clone()),
+                version,
+                sequence: state.sequence,
+                entity_type,
+                entity_id,
+                state: entity,
+            };
+            rows.push(log_row(&entry));
+        }
+        append_rows(&log_path, &rows)?;
+        if let Some(signature) = FileSignature::read(&log_path)? {
+            state.signatures.insert(log_path, signature);
+        }
+        state.logged = current;
+        Ok(())
+    }
+
+    fn apply_logs(&mut self) -> AppResult<()> {
+        let baseline = Self::load(self.root.clone(), self.backup_policy)?;
+        let signatures = read_log_signatures(&self.root)?;
+        let mut entries = Vec::new();
+        for path in signatures.keys() {
+            for fields in load_csv_rows(path, LOG_HEADER)? {
+                entries.push(parse_log_entry(&fields)?);
+            }
+        }
+        let resolved = sync::resolve(entries);
+
+        self.timers = baseline.timers;
+        self.projects = baseline.projects;
+        self.categories = baseline.categories;
+        self.templates = baseline.templates;
+        self.todos = baseline.todos;
+        let mut marks: HashMap<String, Mark> = baseline
+            .marks_by_timer
+            .into_values()
+            .flatten()
+            .map(|mark| (mark.id.clone(), mark))
+            .collect();
+        let mut touched_timers = Vec::new();
+        for ((entity_type, entity_id), entity) in &resolved.states {
+            match entity.clone() {
+                Some(Entity::Timer(timer)) => {
+                    self.timers.insert(timer.id.clone(), timer);
+                }
+                Some(Entity::Project(project)) => {
+                    self.projects.insert(project.id.clone(), project);
+                }
+                Some(Entity::Category(category)) => {
+                    self.categories.insert(category.id.clone(), category);
+                }
+                Some(Entity::Template(template)) => {
+                    self.templates.insert(template.id.clone(), template);
+                }
+                Some(Entity::Todo(todo)) => {
+                    self.todos.insert(todo.id.clone(), todo);
+                }
+                Some(Entity::Mark(mark)) => {
+                    touched_timers.push(mark.timer_id.clone());
+                    if let Some(previous) = marks.insert(mark.id.clone(), mark) {
+                        touched_timers.push(previous.timer_id);
+                    }
+                }
+                None => match entity_type.as_str() {
+                    "timer" => {
+                        self.timers.remove(entity_id);
+                    }
+                    "project" => {
+                        self.projects.remove(entity_id);
+                    }
+                    "category" => {
+                        self.categories.remove(entity_id);
+                    }
+                    "template" => {
+                        self.templates.remove(entity_id);
+                    }
+                    "todo" => {
+                        self.todos.remove(entity_id);
+                    }
+                    _ => {
+                        if let Some(mark) = marks.remove(entity_id) {
+                            touched_timers.push(mark.timer_id);
+                        }
+                    }
+                },
+            }
+        }
+        self.marks_by_timer = group_marks_by_timer(marks.into_values().collect());
+        for timer_id in touched_timers {
+            if let Some(timer_marks) = self.marks_by_timer.get_mut(&timer_id) {
+                let chained = dataset::chained_mark_ids(timer_marks);
+                dataset::relink_chain(timer_marks, &chained);
+                timer_marks.sort_by(|left, right| mark_key(left).cmp(&mark_key(right)));
+            }
+        }
+
+        let logged = self.entity_map();
+        let device_log = self.log_path();
+        let Some(state) = self.sync.as_mut() else {
+            return Ok(());
+        };
+        state.clock = state.clock.max(resolved.clock);
+        state.sequence = state
+            .sequence
+            .max(load_csv_rows(&device_log, LOG_HEADER)?.len() as u64);
+        state.versions = resolved.versions;
+        state.conflicts = resolved.conflicts;
+        state.signatures = signatures;
+        state.logged = logged;
+        Ok(())
+    }
+
+    fn sync_report(&self, changed: bool) -> SyncReport {
+        let Some(state) = &self.sync else {
+            return SyncReport::default();
+        };
+        let devices = state
+            .signatures
+            .keys()
+            .filter_map(|path| path.file_name()?.to_str()?.strip_suffix(sync::LOG_SUFFIX))
+            .map(str::to_string)
+            .collect();
+        SyncReport {
+            changed,
+            devices,
+            conflicts: state.conflicts.clone(),
+        }
+    }
+
+    fn entity_map(&self) -> BTreeMap<EntityKey, Entity> {
+        let contents = StoreContents {
+            timers: self.timers.values().cloned().collect(),
+            projects: self.projects.values().cloned().collect(),
+            categories: self.categories.values().cloned().collect(),
+            templates: self.templates.values().cloned().collect(),
+            todos: self.todos.values().cloned().collect(),
+            marks: self.marks_by_timer.values().flatten().cloned().collect(),
+        };
+        contents
+            .into_entities()
+            .into_iter()
+            .map(|entity| {
+                (
+                    (entity.entity_type().to_string(), entity.id().to_string()),
+                    entity,
+                )
+            })
+            .collect()
+    }
+
+    fn persist_timers(&mut self) -> AppResult<()> {
+        self.write_data(vec![(self.timers_path(), self.render_timers())])
+    }
+
+    fn persist_marks(&mut self) -> AppResult<()> {
+        self.write_data(vec![(self.marks_path(), self.render_marks())])
+    }
+
+    fn persist_todos(&mut self) -> AppResult<()> {
+        self.write_data(vec![(self.todos_path(), self.render_todos())])
+    }
+
+    fn persist_projects(&mut self) -> AppResult<()> {
+        self.write_data(vec![(self.projects_path(), self.render_projects())])
+    }
+
+    fn persist_categories(&mut self) -> AppResult<()> {
+        self.write_data(vec![(self.categories_path(), self.render_categories())])
+    }
+
+    fn persist_reminders(&mut self) -> AppResult<()> {
+        self.write_data(vec![(self.reminders_path(), self.render_reminders())])
+    }
+
+    fn persist_templates(&mut self) -> AppResult<()> {
+        self.write_data(vec![(self.templates_path(), self.render_templates())])
+    }
+
+    fn render_timers(&self) -> String {
+        let mut timers: Vec<&Timer> = self.timers.values().collect();
+        timers.sort_by(|left, right| left.id.cmp(&right.id));
+
+        let mut rows = Vec::with_capacity(timers.len() + 1);
+        rows.push(TIMERS_HEADER.to_string());
+        for timer in timers {
+            rows.push(timer_row(timer));
+        }
+
+        rows.join("\n")
+    }
+
+    fn render_marks(&self) -> String {
+        let mut marks: Vec<&Mark> = self
+            .marks_by_timer
+            .values()
+            .flat_map(|timer_marks| timer_marks.iter())
+            .collect();
+        marks.sort_by_key(|mark| {
+            (
+                mark.timer_id.clone(),
+                mark.marked_at_minute,
+                mark.id.clone(),
+            )
+        });
+
+        let mut rows = Vec::with_capacity(marks.len() + 1);
+        rows.push(MARKS_HEADER.to_string());
+        for mark in marks {
+            rows.push(mark_row(mark));
+        }
+
+        rows.join("\n")
+    }
+
+    fn render_todos(&self) -> String {
+        let mut todos: Vec<&Todo> = self.todos.values().collect();
+        todos.sort_by(|left, right| left.id.cmp(&right.id));
+
+        let mut rows = Vec::with_capacity(todos.len() + 1);
+        rows.push(TODOS_HEADER.to_string());
+        for todo in todos {
+            rows.push(todo_row(todo));
+        }
+
+        rows.join("\n")
+    }
+
+    fn render_projects(&self) -> String {
+        let mut projects: Vec<&Project> = self.projects.values().collect();
+        projects.sort_by(|left, right| left.id.cmp(&right.id));
+
+        let mut rows = Vec::with_capacity(projects.len() + 1);
+        rows.push(PROJECTS_HEADER.to_string());
+        for project in projects {
+            rows.push(project_row(project));
+        }
+
+        rows.join("\n")
+    }
+
+    fn render_categories(&self) -> String {
+        let mut categories: Vec<&Category> = self.categories.values().collect();
+        categories.sort_by(|left, right| left.id.cmp(&right.id));
+
+        let mut rows = Vec::with_capacity(categories.len() + 1);
+        rows.push(CATEGORIES_HEADER.to_string());
+        for category in categories {
+            rows.push(category_row(category));
+        }
+
+        rows.join("\n")
+    }
+
+    fn render_reminders(&self) -> String {
+        let mut reminders: Vec<&FiredReminder> = self.fired_reminders.iter().collect();
+        reminders.sort_by_key(|reminder| {
+            (
+                reminder.timer_id.clone(),
+                reminder.target_at_minute,
+                std::cmp::Reverse(reminder.offset_minutes),
+            )
+        });
+
+        let mut rows = Vec::with_capacity(reminders.len() + 1);
+        rows.push(REMINDERS_HEADER.to_string());
+        for reminder in reminders {
+            rows.push(csv_row(&[
+                &reminder.timer_id,
+                &reminder.target_at_minute.to_string(),
+                &reminder.offset_minutes.to_string(),
+                &reminder.fired_at_minute.to_string(),
+            ]));
+        }
+
+        rows.join("\n")
+    }
+
+    fn render_templates(&self) -> String {
+        let mut templates: Vec<&MarkTemplate> = self.templates.values().collect();
+        templates.sort_by(|left, right| left.id.cmp(&right.id));
+
+        let mut rows = Vec::with_capacity(templates.len() + 1);
+        rows.push(TEMPLATES_HEADER.to_string());
+        for template in templates {
+            rows.push(template_row(template));
+        }
+
+        rows.join("\n")
+    }
+}
+
+impl Store for CsvStore {
+    fn save_timer(&mut self, timer: Timer) -> AppResult<()> {
+        self.timers.insert(timer.id.clone(), timer);
+        self.persist_timers()
+    }
+
+    fn get_timer(&self, timer_id: &str) -> Option<Timer> {
+        self.timers.get(timer_id).cloned()
+    }
+
+    fn list_timers(&self, include_archived: bool) -> Vec<Timer> {
+        let mut timers: Vec<Timer> = self
+            .timers
+            .values()
+            .filter(|timer| include_archived || !timer.archived)
+            .cloned()
+            .collect();
+        timers.sort_by_key(|timer| timer.target_at_minute);
+        timers
+    }
+
+    fn delete_timer(&mut self, timer_id: &str) -> AppResult<()> {
+        self.timers
+            .remove(timer_id)
+            .ok_or_else(|| AppError::NotFound(format!("timer {timer_id}")))?;
+        self.todos.retain(|_, todo| todo.timer_id != timer_id);
+        self.marks_by_timer.remove(timer_id);
+
+        // Fired reminders stay until reopen so undoing the delete does not fire them again.
+        self.write_data(vec![
+            (self.timers_path(), self.render_timers()),
+            (self.marks_path(), self.render_marks()),
+            (self.todos_path(), self.render_todos()),
+        ])
+    }
+
+    fn save_project(&mut self, project: Project) -> AppResult<()> {
+        self.projects.insert(project.id.clone(), project);
+        self.persist_projects()
+    }
+
+    fn get_project(&self, project_id: &str) -> Option<Project> {
+        self.projects.get(project_id).cloned()
+    }
+
+    fn delete_project(&mut self, project_id: &str) -> AppResult<()> {
+        self.projects
+            .remove(project_id)
+            .map(|_| ())
+            .ok_or_else(|| AppError::NotFound(format!("project {project_id}")))?;
+        self.persist_projects()
+    }
+
+    fn list_projects(&self) -> Vec<Project> {
+        let mut projects: Vec<Project> = self.projects.values().cloned().collect();
+        projects.sort_by(|left, right| left.name.cmp(&right.name));
+        projects
+    }
+
+    fn save_category(&mut self, category: Category) -> AppResult<()> {
+        self.categories.insert(category.id.clone(), category);
+        self.persist_categories()
+    }
+
+    fn get_category(&self, category_id: &str) -> Option<Category> {
+        self.categories.get(category_id).cloned()
+    }
+
+    fn delete_category(&mut self, category_id: &str) -> AppResult<()> {
+        self.categories
+            .remove(category_id)
+            .map(|_| ())
+            .ok_or_else(|| AppError::NotFound(format!("category {category_id}")))?;
+        self.persist_categories()
+    }
+
+    fn list_categories(&self) -> Vec<Category> {
+        let mut categories: Vec<Category> = self.categories.values().cloned().collect();
+        categories.sort_by(|left, right| left.name.cmp(&right.name));
+        categories
+    }
+
+    fn save_template(&mut self, template: MarkTemplate) -> AppResult<()> {
+        self.templates.insert(template.id.clone(), template);
+        self.persist_templates()
+    }
+
+    fn get_template(&self, template_id: &str) -> Option<MarkTemplate> {
+        self.templates.get(template_id).cloned()
+    }
+
+    fn delete_template(&mut self, template_id: &str) -> AppResult<()> {
+        self.templates
+            .remove(template_id)
+            .map(|_| ())
+            .ok_or_else(|| AppError::NotFound(format!("template {template_id}")))?;
+        self.persist_templates()
+    }
+
+    fn list_templates(&self) -> Vec<MarkTemplate> {
+        let mut templates: Vec<MarkTemplate> = self.templates.values().cloned().collect();
+        templates.sort_by(|left, right| left.name.cmp(&right.name));
+        templates
+    }
+
+    fn save_todo(&mut self, todo: Todo) -> AppResult<()> {
+        self.todos.insert(todo.id.clone(), todo);
+        self.persist_todos()
+    }
+
+    fn get_todo(&self, todo_id: &str) -> Option<Todo> {
+        self.todos.get(todo_id).cloned()
+    }
+
+    fn delete_todo(&mut self, todo_id: &str) -> AppResult<()> {
+        self.todos
+            .remove(todo_id)
+            .map(|_| ())
+            .ok_or_else(|| AppError::NotFound(format!("todo {todo_id}")))?;
+        self.persist_todos()
+    }
+
+    fn list_todos_by_timer(&self, timer_id: &str) -> Vec<Todo> {
+        let mut todos: Vec<Todo> = self
+            .todos
+            .values()
+            .filter(|todo| todo.timer_id == timer_id)
+            .cloned()
+            .collect();
+        todos.sort_by(|left, right| {
+            (left.created_at_minute, &left.id).cmp(&(right.created_at_minute, &right.id))
+        });
+        todos
+    }
+
+    fn append_mark(&mut self, mark: Mark) -> AppResult<()> {
+        insert_sorted_mark(
+            self.marks_by_timer
+                .entry(mark.timer_id.clone())
+                .or_default(),
+            mark,
+        );
+        self.persist_marks()
+    }
+
+    fn update_mark(&mut self, mark: Mark) -> AppResult<()> {
+        let marks = self
+            .marks_by_timer
+            .get_mut(&mark.timer_id)
+            .ok_or_else(|| AppError::NotFound(format!("mark {}", mark.id)))?;
+        let position = marks
+            .iter()
+            .position(|existing| existing.id == mark.id)
+            .ok_or_else(|| AppError::NotFound(format!("mark {}", mark.id)))?;
+        marks.remove(position);
+        insert_sorted_mark(marks, mark);
+        self.persist_marks()
+    }
+
+    fn delete_mark(&mut self, mark_id: &str) -> AppResult<()> {
+        let marks = self
+            .marks_by_timer
+            .values_mut()
+            .find(|marks| marks.iter().any(|mark| mark.id == mark_id))
+            .ok_or_else(|| AppError::NotFound(format!("mark {mark_id}")))?;
+        marks.retain(|mark| mark.id != mark_id);
+        self.persist_marks()
+    }
+
+    fn get_mark(&self, mark_id: &str) -> Option<Mark> {
+        self.marks_by_timer
+            .values()
+            .flat_map(|marks| marks.iter())
+            .find(|mark| mark.id == mark_id)
+            .cloned()
+    }
+
+    fn list_fired_reminders(&self) -> Vec<FiredReminder> {
+        self.fired_reminders.clone()
+    }
+
+    fn record_fired_reminders(&mut self, reminders: Vec<FiredReminder>) -> AppResult<()> {
+        if reminders.is_empty() {
+            return Ok(());
         }
+        self.fired_reminders.extend(reminders);
+        self.persist_reminders()
+    }
 
-        let timer = Timer {
-            id: fields[0].clone(),
-            name: fields[1].clone(),
-            target_at_minute: parse_i64("target_at", &fields[2])?,
-            created_at_minute: parse_i64("created_at", &fields[3])?,
-            updated_at_minute: parse_i64("updated_at", &fields[4])?,
-            archived: parse_bool("archived", &fields[5])?,
+    fn list_marks_by_timer(&self, timer_id: &str) -> Vec<Mark> {
+        self.marks_by_timer
+            .get(timer_id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    fn latest_mark_by_timer(&self, timer_id: &str) -> Option<Mark> {
+        self.marks_by_timer
+            .get(timer_id)
+            .and_then(|marks| marks.last())
+            .cloned()
+    }
+
+    fn page_marks_by_timer(&self, timer_id: &str, request: &PageRequest) -> AppResult<Page<Mark>> {
+        let marks = self
+            .marks_by_timer
+            .get(timer_id)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        page::paginate(marks, mark_key, request)
+    }
+
+    fn page_todos_by_timer(&self, timer_id: &str, request: &PageRequest) -> AppResult<Page<Todo>> {
+        page_todos(self.todos.values(), timer_id, request)
+    }
+
+    fn append_events(&mut self, events: Vec<DomainEvent>) -> AppResult<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        self.ensure_writable()?;
+        let rows: Vec<String> = events.iter().map(event_row).collect();
+        append_rows(&self.events_path(), &rows)?;
+        self.events.extend(events);
+        Ok(())
+    }
+
+    fn list_events(&self) -> Vec<DomainEvent> {
+        self.events.clone()
+    }
+
+    fn replace_contents(&mut self, contents: StoreContents) -> AppResult<()> {
+        self.timers = index_by_id(contents.timers, |timer| &timer.id);
+        self.projects = index_by_id(contents.projects, |project| &project.id);
+        self.categories = index_by_id(contents.categories, |category| &category.id);
+        self.templates = index_by_id(contents.templates, |template| &template.id);
+        self.todos = index_by_id(contents.todos, |todo| &todo.id);
+        self.marks_by_timer = group_marks_by_timer(contents.marks);
+        self.persist_all()
+    }
+
+    fn apply_changes(&mut self, changes: Vec<Change>) -> AppResult<()> {
+        for change in changes {
+            self.apply_change(change);
+        }
+        self.persist_all()
+    }
+
+    fn refresh(&mut self) -> AppResult<SyncReport> {
+        if self.is_read_only() {
+            return self.reload_read_only();
+        }
+        let Some(state) = &self.sync else {
+            return self.reload_external_edits();
         };
+        if read_log_signatures(&self.root)? == state.signatures {
+            return Ok(self.sync_report(false));
+        }
+        let before = self.entity_map();
+        self.apply_logs()?;
+        let changed = before != self.entity_map();
+        Ok(self.sync_report(changed))
+    }
+
+    fn id_scope(&self) -> Option<String> {
+        self.sync.as_ref().map(|state| state.device.clone())
+    }
+
+    fn is_read_only(&self) -> bool {
+        self.lock.is_none()
+    }
+
+    fn list_backups(&self) -> AppResult<Vec<BackupInfo>> {
+        backup::list_backups(&self.root)
+    }
+
+    fn create_backup(&mut self) -> AppResult<BackupInfo> {
+        self.ensure_writable()?;
+        if self.sync.is_some() {
+            return Err(AppError::Validation(
+                "backups are not taken in sync mode; device change logs keep the history"
+                    .to_string(),
+            ));
+        }
+        let file_names: Vec<&str> = DATA_FILES.iter().map(|(file_name, _)| *file_name).collect();
+        let info = backup::create_snapshot(&self.root, &file_names)?;
+        backup::prune(&self.root, self.backup_policy.keep)?;
+        self.writes_since_backup = 0;
+        Ok(info)
+    }
+
+    fn restore_backup(&mut self, backup_id: &str) -> AppResult<BackupInfo> {
+        let snapshot_dir = backup::snapshot_dir(&self.root, backup_id)?;
+        let restored = Self::load(snapshot_dir, self.backup_policy).map_err(|error| {
+            AppError::Validation(format!("backup {backup_id} cannot be restored: {error}"))
+        })?;
+        let info = backup::list_backups(&self.root)?
+            .into_iter()
+            .find(|info| info.id == backup_id)
+            .ok_or_else(|| AppError::NotFound(format!("backup {backup_id}")))?;
+
+        self.create_backup()?;
+        self.timers = restored.timers;
+        self.projects = restored.projects;
+        self.categories = restored.categories;
+        self.templates = restored.templates;
+        self.todos = restored.todos;
+        self.marks_by_timer = restored.marks_by_timer;
+        self.fired_reminders = restored.fired_reminders;
+        self.persist_all()?;
+        Ok(info)
+    }
+}
+
+fn index_by_id<T>(items: Vec<T>, id: impl Fn(&T) -> &String) -> HashMap<String, T> {
+    items
+        .into_iter()
+        .map(|item| (id(&item).clone(), item))
+        .collect()
+}
+
+fn group_marks_by_timer(marks: Vec<Mark>) -> HashMap<String, Vec<Mark>> {
+    let mut marks_by_timer: HashMap<String, Vec<Mark>> = HashMap::new();
+    for mark in marks {
+        marks_by_timer
+            .entry(mark.timer_id.clone())
+            .or_default()
+            .push(mark);
+    }
+    for marks in marks_by_timer.values_mut() {
+        marks.sort_by(|left, right| mark_key(left).cmp(&mark_key(right)));
+    }
+    marks_by_timer
+}
+
+fn mark_key(mark: &Mark) -> (i64, &str) {
+    (mark.marked_at_minute, &mark.id)
+}
+
+fn todo_key<'a>(todo: &'a &Todo) -> (i64, &'a str) {
+    (todo.created_at_minute, &todo.id)
+}
+
+fn insert_sorted_mark(marks: &mut Vec<Mark>, mark: Mark) {
+    let position = marks.partition_point(|existing| mark_key(existing) <= mark_key(&mark));
+    marks.insert(position, mark);
+}
+
+fn remove_mark(marks_by_timer: &mut HashMap<String, Vec<Mark>>, mark_id: &str) {
+    for marks in marks_by_timer.values_mut() {
+        marks.retain(|mark| mark.id != mark_id);
+    }
+}
+
+fn page_todos<'a>(
+    todos: impl Iterator<Item = &'a Todo>,
+    timer_id: &str,
+    request: &PageRequest,
+) -> AppResult<Page<Todo>> {
+    let mut todos: Vec<&Todo> = todos.filter(|todo| todo.timer_id == timer_id).collect();
+    todos.sort_by(|left, right| todo_key(left).cmp(&todo_key(right)));
+    Ok(page::paginate(&todos, todo_key, request)?.map(Todo::clone))
+}
+
+fn ensure_csv_file(path: &Path, header: &str) -> AppResult<()> {
+    if path.exists() {
+        return Ok(());
+    }
+    write_atomic(path, header)
+}
+
+fn load_timers(path: &Path) -> AppResult<HashMap<String, Timer>> {
+    let rows = load_csv_rows(path, TIMERS_HEADER)?;
+    let mut timers = HashMap::new();
+
+    for fields in rows {
+        let timer = parse_timer(&fields)?;
         timers.insert(timer.id.clone(), timer);
     }
 
@@ -326,65 +1513,432 @@ fn load_todos(path: &Path) -> AppResult<HashMap<String, Todo>> {
     let mut todos = HashMap::new();
 
     for fields in rows {
-        if fields.len() != 7 {
+        let todo = parse_todo(&fields)?;
+        todos.insert(todo.id.clone(), todo);
+    }
+
+    Ok(todos)
+}
+
+fn load_projects(path: &Path) -> AppResult<HashMap<String, Project>> {
+    let rows = load_csv_rows(path, PROJECTS_HEADER)?;
+    let mut projects = HashMap::new();
+
+    for fields in rows {
+        let project = parse_project(&fields)?;
+        projects.insert(project.id.clone(), project);
+    }
+
+    Ok(projects)
+}
+
+fn load_categories(path: &Path) -> AppResult<HashMap<String, Category>> {
+    let rows = load_csv_rows(path, CATEGORIES_HEADER)?;
+    let mut categories = HashMap::new();
+
+    for fields in rows {
+        let category = parse_category(&fields)?;
+        categories.insert(category.id.clone(), category);
+    }
+
+    Ok(categories)
+}
+
+fn load_templates(path: &Path) -> AppResult<HashMap<String, MarkTemplate>> {
+    let rows = load_csv_rows(path, TEMPLATES_HEADER)?;
+    let mut templates = HashMap::new();
+
+    for fields in rows {
+        let template = parse_template(&fields)?;
+        templates.insert(template.id.clone(), template);
+    }
+
+    Ok(templates)
+}
+
+fn load_events(path: &Path) -> AppResult<Vec<DomainEvent>> {
+    load_csv_rows(path, EVENTS_HEADER)?
+        .iter()
+        .map(|fields| parse_event(fields))
+        .collect()
+}
+
+fn load_reminders(path: &Path) -> AppResult<Vec<FiredReminder>> {
+    let rows = load_csv_rows(path, REMINDERS_HEADER)?;
+    let mut reminders = Vec::with_capacity(rows.len());
+
+    for fields in rows {
+        if fields.len() != 4 {
             return Err(AppError::Internal(format!(
-                "todos.csv expected 7 columns, got {}",
+                "reminders.csv expected 4 columns, got {}",
                 fields.len()
             )));
         }
 
-        let status = TodoStatus::from_str(&fields[3])
-            .ok_or_else(|| AppError::Internal(format!("invalid todo status '{}'", fields[3])))?;
+        reminders.push(FiredReminder {
+            timer_id: fields[0].clone(),
+            target_at_minute: parse_i64("target_at", &fields[1])?,
+            offset_minutes: parse_i64("offset_minutes", &fields[2])?,
+            fired_at_minute: parse_i64("fired_at", &fields[3])?,
+        });
+    }
+
+    Ok(reminders)
+}
+
+fn load_marks(path: &Path) -> AppResult<HashMap<String, Vec<Mark>>> {
+    let marks = load_csv_rows(path, MARKS_HEADER)?
+        .iter()
+        .map(|fields| parse_mark(fields))
+        .collect::<AppResult<Vec<Mark>>>()?;
+    Ok(group_marks_by_timer(marks))
+}
+
+fn timer_row(timer: &Timer) -> String {
+    csv_row(&[
+        &timer.id,
+        &timer.name,
+        &timer.target_at_minute.to_string(),
+        &timer.created_at_minute.to_string(),
+        &timer.updated_at_minute.to_string(),
+        &timer.archived.to_string(),
+        timer.project_id.as_deref().unwrap_or_default(),
+        &timer.tags.join(";"),
+    ])
+}
+
+fn parse_timer(fields: &[String]) -> AppResult<Timer> {
+    if fields.len() != 8 {
+        return Err(AppError::Internal(format!(
+            "timers.csv expected 8 columns, got {}",
+            fields.len()
+        )));
+    }
+
+    Ok(Timer {
+        id: fields[0].clone(),
+        name: fields[1].clone(),
+        target_at_minute: parse_i64("target_at", &fields[2])?,
+        created_at_minute: parse_i64("created_at", &fields[3])?,
+        updated_at_minute: parse_i64("updated_at", &fields[4])?,
+        archived: parse_bool("archived", &fields[5])?,
+        project_id: parse_optional_string(&fields[6]),
+        tags: parse_list(&fields[7]),
+    })
+}
+
+fn todo_row(todo: &Todo) -> String {
+    csv_row(&[
+        &todo.id,
+        &todo.timer_id,
+        &todo.title,
+        todo.status.as_str(),
+        &todo.created_at_minute.to_string(),
+        &todo.updated_at_minute.to_string(),
+        &optional_i64_to_csv(todo.done_at_minute),
+        &todo.tags.join(";"),
+    ])
+}
+
+fn parse_todo(fields: &[String]) -> AppResult<Todo> {
+    if fields.len() != 8 {
+        return Err(AppError::Internal(format!(
+            "todos.csv expected 8 columns, got {}",
+            fields.len()
+        )));
+    }
+
+    let status = fields[3]
+        .parse::<TodoStatus>()
+        .map_err(|_| AppError::Internal(format!("invalid todo status '{}'", fields[3])))?;
+
+    Ok(Todo {
+        id: fields[0].clone(),
+        timer_id: fields[1].clone(),
+        title: fields[2].clone(),
+        status,
+        created_at_minute: parse_i64("created_at", &fields[4])?,
+        updated_at_minute: parse_i64("updated_at", &fields[5])?,
+        done_at_minute: parse_optional_i64("done_at", &fields[6])?,
+        tags: parse_list(&fields[7]),
+    })
+}
+
+fn project_row(project: &Project) -> String {
+    csv_row(&[
+        &project.id,
+        &project.name,
+        &project.created_at_minute.to_string(),
+        &project.updated_at_minute.to_string(),
+    ])
+}
+
+fn parse_project(fields: &[String]) -> AppResult<Project> {
+    if fields.len() != 4 {
+        return Err(AppError::Internal(format!(
+            "projects.csv expected 4 columns, got {}",
+            fields.len()
+        )));
+    }
+
+    Ok(Project {
+        id: fields[0].clone(),
+        name: fields[1].clone(),
+        created_at_minute: parse_i64("created_at", &fields[2])?,
+        updated_at_minute: parse_i64("updated_at", &fields[3])?,
+    })
+}
+
+fn category_row(category: &Category) -> String {
+    csv_row(&[
+        &category.id,
+        &category.name,
+        &category.created_at_minute.to_string(),
+        &category.updated_at_minute.to_string(),
+    ])
+}
+
+fn parse_category(fields: &[String]) -> AppResult<Category> {
+    if fields.len() != 4 {
+        return Err(AppError::Internal(format!(
+            "categories.csv expected 4 columns, got {}",
+            fields.len()
+        )));
+    }
+
+    Ok(Category {
+        id: fields[0].clone(),
+        name: fields[1].clone(),
+        created_at_minute: parse_i64("created_at", &fields[2])?,
+        updated_at_minute: parse_i64("updated_at", &fields[3])?,
+    })
+}
+
+fn template_row(template: &MarkTemplate) -> String {
+    csv_row(&[
+        &template.id,
+        &template.name,
+        &template.body,
+        &template.created_at_minute.to_string(),
+        &template.updated_at_minute.to_string(),
+    ])
+}
+
+fn parse_template(fields: &[String]) -> AppResult<MarkTemplate> {
+    if fields.len() != 5 {
+        return Err(AppError::Internal(format!(
+            "templates.csv expected 5 columns, got {}",
+            fields.len()
+        )));
+    }
+
+    Ok(MarkTemplate {
+        id: fields[0].clone(),
+        name: fields[1].clone(),
+        body: fields[2].clone(),
+        created_at_minute: parse_i64("created_at", &fields[3])?,
+        updated_at_minute: parse_i64("updated_at", &fields[4])?,
+    })
+}
+
+fn mark_row(mark: &Mark) -> String {
+    csv_row(&[
+        &mark.id,
+        &mark.timer_id,
+        &mark.marked_at_minute.to_string(),
+        &optional_i64_to_csv(mark.prev_marked_at_minute),
+        &optional_i64_to_csv(mark.duration_minutes),
+        &mark.description,
+        &mark.todo_ids.join(";"),
+        &mark.tags.join(";"),
+        mark.category_id.as_deref().unwrap_or_default(),
+    ])
+}
+
+fn parse_mark(fields: &[String]) -> AppResult<Mark> {
+    if fields.len() != 9 {
+        return Err(AppError::Internal(format!(
+            "marks.csv expected 9 columns, got {}",
+            fields.len()
+        )));
+    }
+
+    Ok(Mark {
+        id: fields[0].clone(),
+        timer_id: fields[1].clone(),
+        marked_at_minute: parse_i64("marked_at", &fields[2])?,
+        prev_marked_at_minute: parse_optional_i64("prev_marked_at", &fields[3])?,
+        duration_minutes: parse_optional_i64("duration_minutes", &fields[4])?,
+        description: fields[5].clone(),
+        todo_ids: parse_list(&fields[6]),
+        tags: parse_list(&fields[7]),
+        category_id: parse_optional_string(&fields[8]),
+    })
+}
+
+fn entity_row(entity: &Entity) -> String {
+    match entity {
+        Entity::Timer(timer) => timer_row(timer),
+        Entity::Project(project) => project_row(project),
+        Entity::Category(category) => category_row(category),
+        Entity::Template(template) => template_row(template),
+        Entity::Todo(todo) => todo_row(todo),
+        Entity::Mark(mark) => mark_row(mark),
+    }
+}
 
-        let todo = Todo {
-            id: fields[0].clone(),
-            timer_id: fields[1].clone(),
-            title: fields[2].clone(),
-            status,
-            created_at_minute: parse_i64("created_at", &fields[4])?,
-            updated_at_minute: parse_i64("updated_at", &fields[5])?,
-            done_at_minute: parse_optional_i64("done_at", &fields[6])?,
-        };
-        todos.insert(todo.id.clone(), todo);
+fn parse_entity_row(entity_type: &str, row: &str) -> AppResult<Option<Entity>> {
+    if row.is_empty() {
+        return Ok(None);
     }
+    let fields = parse_csv_line(row)?;
+    Ok(Some(match entity_type {
+        "timer" => Entity::Timer(parse_timer(&fields)?),
+        "project" => Entity::Project(parse_project(&fields)?),
+        "category" => Entity::Category(parse_category(&fields)?),
+        "template" => Entity::Template(parse_template(&fields)?),
+        "todo" => Entity::Todo(parse_todo(&fields)?),
+        "mark" => Entity::Mark(parse_mark(&fields)?),
+        other => return Err(AppError::Internal(format!("unknown entity type '{other}'"))),
+    }))
+}
 
-    Ok(todos)
+fn log_row(entry: &LogEntry) -> String {
+    csv_row(&[
+        &entry.version.clock.to_string(),
+        &entry.version.device,
+        &entry.sequence.to_string(),
+        &entry
+            .parent
+            .as_ref()
+            .map(Version::to_string)
+            .unwrap_or_default(),
+        &entry.entity_type,
+        &entry.entity_id,
+        &entry.state.as_ref().map(entity_row).unwrap_or_default(),
+    ])
 }
 
-fn load_marks(path: &Path) -> AppResult<HashMap<String, Vec<Mark>>> {
-    let rows = load_csv_rows(path, MARKS_HEADER)?;
-    let mut marks_by_timer: HashMap<String, Vec<Mark>> = HashMap::new();
+fn parse_log_entry(fields: &[String]) -> AppResult<LogEntry> {
+    if fields.len() != 7 {
+        return Err(AppError::Internal(format!(
+            "sync log expected 7 columns, got {}",
+            fields.len()
+        )));
+    }
+    Ok(LogEntry {
+        version: Version {
+            clock: parse_i64("clock", &fields[0])? as u64,
+            device: fields[1].clone(),
+        },
+        sequence: parse_i64("sequence", &fields[2])? as u64,
+        parent: Version::parse(&fields[3])?,
+        entity_type: fields[4].clone(),
+        entity_id: fields[5].clone(),
+        state: parse_entity_row(&fields[4], &fields[6])?,
+    })
+}
 
-    for fields in rows {
-        if fields.len() != 7 {
-            return Err(AppError::Internal(format!(
-                "marks.csv expected 7 columns, got {}",
-                fields.len()
-            )));
+fn read_log_signatures(root: &Path) -> AppResult<BTreeMap<PathBuf, FileSignature>> {
+    let sync_dir = root.join(sync::SYNC_DIR);
+    let mut signatures = BTreeMap::new();
+    if !sync_dir.exists() {
+        return Ok(signatures);
+    }
+    let entries = fs::read_dir(&sync_dir)
+        .map_err(|error| AppError::Internal(format!("failed to read {sync_dir:?}: {error}")))?;
+    for entry in entries {
+        let path = entry
+            .map_err(|error| AppError::Internal(format!("failed to read {sync_dir:?}: {error}")))?
+            .path();
+        let is_log = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.ends_with(sync::LOG_SUFFIX));
+        if is_log {
+            if let Some(signature) = FileSignature::read(&path)? {
+                signatures.insert(path, signature);
+            }
         }
+    }
+    Ok(signatures)
+}
 
-        let timer_id = fields[1].clone();
-        let mark = Mark {
-            id: fields[0].clone(),
-            timer_id: timer_id.clone(),
-            marked_at_minute: parse_i64("marked_at", &fields[2])?,
-            prev_marked_at_minute: parse_optional_i64("prev_marked_at", &fields[3])?,
-            duration_minutes: parse_optional_i64("duration_minutes", &fields[4])?,
-            description: fields[5].clone(),
-            todo_ids: parse_todo_ids(&fields[6]),
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileSignature {
+    len: u64,
+    modified: Option<SystemTime>,
+    hash: u64,
+}
+
+impl FileSignature {
+    fn read(path: &Path) -> AppResult<Option<Self>> {
+        let content = match fs::read(path) {
+            Ok(content) => content,
+            Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(error) => {
+                return Err(AppError::Internal(format!(
+                    "failed to read {path:?}: {error}"
+                )))
+            }
         };
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        Ok(Some(Self {
+            len: content.len() as u64,
+            modified: fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok(),
+            hash: hasher.finish(),
+        }))
+    }
 
-        marks_by_timer.entry(timer_id).or_default().push(mark);
+    // Hash only when size or mtime moved, so a merely touched file stays current.
+    fn is_current(&self, path: &Path) -> AppResult<bool> {
+        let unchanged_metadata = fs::metadata(path).is_ok_and(|metadata| {
+            metadata.len() == self.len && metadata.modified().ok() == self.modified
+        });
+        if unchanged_metadata {
+            return Ok(true);
+        }
+        Ok(Self::read(path)?
+            .is_some_and(|current| current.len == self.len && current.hash == self.hash))
     }
+}
 
-    for marks in marks_by_timer.values_mut() {
-        marks.sort_by_key(|mark| mark.marked_at_minute);
+fn event_row(event: &DomainEvent) -> String {
+    let state = event.state.as_ref().map(entity_row).unwrap_or_default();
+    csv_row(&[
+        &event.sequence.to_string(),
+        event.kind.as_str(),
+        &event.entity_type,
+        &event.entity_id,
+        &optional_i64_to_csv(event.at_minute),
+        &state,
+    ])
+}
+
+fn parse_event(fields: &[String]) -> AppResult<DomainEvent> {
+    if fields.len() != 6 {
+        return Err(AppError::Internal(format!(
+            "events.csv expected 6 columns, got {}",
+            fields.len()
+        )));
     }
 
-    Ok(marks_by_timer)
+    let state = parse_entity_row(&fields[2], &fields[5])?;
+
+    Ok(DomainEvent {
+        sequence: parse_i64("sequence", &fields[0])? as u64,
+        kind: EventKind::parse(&fields[1])?,
+        entity_type: fields[2].clone(),
+        entity_id: fields[3].clone(),
+        at_minute: parse_optional_i64("at", &fields[4])?,
+        state,
+    })
 }
 
-fn parse_todo_ids(value: &str) -> Vec<String> {
+fn parse_list(value: &str) -> Vec<String> {
     if value.is_empty() {
         return Vec::new();
     }
@@ -396,6 +1950,10 @@ fn parse_todo_ids(value: &str) -> Vec<String> {
 }
 
 fn load_csv_rows(path: &Path, expected_header: &str) -> AppResult<Vec<Vec<String>>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
     let mut content = String::new();
     File::open(path)
         .and_then(|mut file| file.read_to_string(&mut content))
@@ -403,24 +1961,34 @@ fn load_csv_rows(path: &Path, expected_header: &str) -> AppResult<Vec<Vec<String
 
     let mut records = split_csv_records(&content)?.into_iter();
     let header = records.next().unwrap_or_default();
-    if header != expected_header {
+    let is_legacy_header = !header.is_empty()
+        && expected_header
+            .strip_prefix(header.as_str())
+            .is_some_and(|rest| rest.starts_with(','));
+    if header != expected_header && !is_legacy_header {
         return Err(AppError::Internal(format!(
             "csv header mismatch for {path:?}, expected '{expected_header}', got '{header}'"
         )));
     }
 
+    let header_columns = header.split(',').count();
+    let expected_columns = expected_header.split(',').count();
     let mut rows = Vec::new();
     for record in records {
         if record.is_empty() {
             continue;
         }
-        rows.push(parse_csv_line(&record)?);
+        let mut fields = parse_csv_line(&record)?;
+        if is_legacy_header && fields.len() == header_columns {
+            fields.resize(expected_columns, String::new());
+        }
+        rows.push(fields);
     }
 
     Ok(rows)
 }
 
-fn split_csv_records(content: &str) -> AppResult<Vec<String>> {
+pub(crate) fn split_csv_records(content: &str) -> AppResult<Vec<String>> {
     let mut records = Vec::new();
     let mut current = String::new();
     let mut chars = content.chars().peekable();
@@ -461,7 +2029,7 @@ fn split_csv_records(content: &str) -> AppResult<Vec<String>> {
     Ok(records)
 }
 
-fn parse_csv_line(line: &str) -> AppResult<Vec<String>> {
+pub(crate) fn parse_csv_line(line: &str) -> AppResult<Vec<String>> {
     let mut values = Vec::new();
     let mut current = String::new();
     let mut chars = line.chars().peekable();
@@ -530,6 +2098,14 @@ fn parse_optional_i64(name: &str, value: &str) -> AppResult<Option<i64>> {
     parse_i64(name, value).map(Some)
 }
 
+fn parse_optional_string(value: &str) -> Option<String> {
+    if value.is_empty() {
+        None
+    } else {
+        Some(value.to_string())
+    }
+}
+
 fn parse_bool(name: &str, value: &str) -> AppResult<bool> {
     match value {
         "true" => Ok(true),
@@ -541,6 +2117,92 @@ fn parse_bool(name: &str, value: &str) -> AppResult<bool> {
 }
 
 fn write_atomic(path: &Path, content: &str) -> AppResult<()> {
+    let temporary = write_temporary(path, content)?;
+    replace_with_temporary(&temporary, path)
+}
+
+// Every temporary is synced before the journal names the batch, so a crash
+// midway through the renames is rolled forward by `recover_batch` on open
+// (or before the next batch, if a rename failed without a crash).
+fn write_atomic_batch(journal: &Path, files: &[(PathBuf, String)]) -> AppResult<()> {
+    recover_batch(journal)?;
+    let mut temporaries = Vec::with_capacity(files.len());
+    for (path, content) in files {
+        match write_temporary(path, content) {
+            Ok(temporary) => temporaries.push(temporary),
+            Err(error) => {
+                for temporary in &temporaries {
+                    let _ = fs::remove_file(temporary);
+                }
+                return Err(error);
+            }
+        }
+    }
+
+    if files.len() > 1 {
+        let dir = journal.parent().unwrap_or(Path::new(""));
+        let paths: Vec<String> = files
+            .iter()
+            .map(|(path, _)| {
+                path.strip_prefix(dir)
+                    .unwrap_or(path)
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        write_atomic(journal, &paths.join("\n"))?;
+    }
+    for (temporary, (path, _)) in temporaries.iter().zip(files) {
+        replace_with_temporary(temporary, path)?;
+    }
+    remove_journal(journal)
+}
+
+fn recover_batch(journal: &Path) -> AppResult<()> {
+    let content = match fs::read_to_string(journal) {
+        Ok(content) => content,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(error) => {
+            return Err(AppError::Internal(format!(
+                "failed to read {journal:?}: {error}"
+            )))
+        }
+    };
+    let dir = journal.parent().unwrap_or(Path::new(""));
+    for line in content.lines().filter(|line| !line.is_empty()) {
+        let path = dir.join(line);
+        let temporary = path.with_extension("tmp");
+        if temporary.exists() {
+            replace_with_temporary(&temporary, &path)?;
+        }
+    }
+    remove_journal(journal)
+}
+
+fn remove_journal(journal: &Path) -> AppResult<()> {
+    match fs::remove_file(journal) {
+        Err(error) if error.kind() != ErrorKind::NotFound => Err(AppError::Internal(format!(
+            "failed to remove {journal:?}: {error}"
+        ))),
+        _ => Ok(()),
+    }
+}
+
+fn append_rows(path: &Path, rows: &[String]) -> AppResult<()> {
+    let mut file = OpenOptions::new()
+        .append(true)
+        .open(path)
+        .map_err(|error| AppError::Internal(format!("failed to open {path:?}: {error}")))?;
+    for row in rows {
+        file.write_all(row.as_bytes())
+            .and_then(|_| file.write_all(b"\n"))
+            .map_err(|error| AppError::Internal(format!("failed to write {path:?}: {error}")))?;
+    }
+    file.sync_all()
+        .map_err(|error| AppError::Internal(format!("failed to fsync {path:?}: {error}")))
+}
+
+fn write_temporary(path: &Path, content: &str) -> AppResult<PathBuf> {
     let temporary = path.with_extension("tmp");
 
     let mut file = File::create(&temporary)
@@ -556,12 +2218,16 @@ fn write_atomic(path: &Path, content: &str) -> AppResult<()> {
         ))
     })?;
 
+    Ok(temporary)
+}
+
+fn replace_with_temporary(temporary: &Path, path: &Path) -> AppResult<()> {
     if path.exists() {
         fs::remove_file(path)
             .map_err(|error| AppError::Internal(format!("failed to remove {path:?}: {error}")))?;
     }
 
-    fs::rename(&temporary, path).map_err(|error| {
+    fs::rename(temporary, path).map_err(|error| {
         AppError::Internal(format!(
             "failed to rename {temporary:?} to {path:?}: {error}"
         ))
@@ -573,7 +2239,12 @@ mod persistence {
     use std::path::PathBuf;
     use std::time::{SystemTime, UNIX_EPOCH};
 
-    use crate::repository::{CsvStore, Store, MARKS_HEADER, TIMERS_HEADER, TODOS_HEADER};
+    use crate::backup::BackupPolicy;
+    use crate::model::Timer;
+    use crate::page::PageRequest;
+    use crate::repository::{
+        CsvStore, Store, MARKS_HEADER, PROJECTS_HEADER, TIMERS_HEADER, TODOS_HEADER,
+    };
     use crate::service::AppService;
 
     fn unique_temp_dir(prefix: &str) -> PathBuf {
@@ -602,107 +2273,525 @@ mod persistence {
             .create_mark(&timer.id, 120, "wrote csv repo", vec![todo.id])
             .expect("mark should be created");
 
-        let timers_csv = std::fs::read_to_string(root.join("timers.csv"))
-            .expect("timers csv should be readable");
-        let marks_csv =
-            std::fs::read_to_string(root.join("marks.csv")).expect("marks csv should be readable");
-        let todos_csv =
-            std::fs::read_to_string(root.join("todos.csv")).expect("todos csv should be readable");
+        let timers_csv = std::fs::read_to_string(root.join("timers.csv"))
+            .expect("timers csv should be readable");
+        let marks_csv =
+            std::fs::read_to_string(root.join("marks.csv")).expect("marks csv should be readable");
+        let todos_csv =
+            std::fs::read_to_string(root.join("todos.csv")).expect("todos csv should be readable");
+
+        assert!(timers_csv.contains(TIMERS_HEADER));
+        assert!(marks_csv.contains(MARKS_HEADER));
+        assert!(todos_csv.contains(TODOS_HEADER));
+
+        assert!(timers_csv.contains("phase2"));
+        assert!(marks_csv.contains("wrote csv repo"));
+        assert!(todos_csv.contains("implement csv"));
+    }
+
+    #[test]
+    fn tests_reloads_data_after_store_reopen() {
+        let root = unique_temp_dir("reload");
+
+        {
+            let store = CsvStore::new(&root).expect("csv store should be created");
+            let mut service = AppService::new(store);
+            let timer = service
+                .create_timer("reloadable", 300, 100)
+                .expect("timer should be created");
+            service
+                .create_mark(&timer.id, 140, "first pass", vec![])
+                .expect("mark should be created");
+        }
+
+        let reopened_store = CsvStore::new(&root).expect("csv store should reopen");
+        let reopened_service = AppService::new(reopened_store);
+        let timers = reopened_service.list_timers(false);
+
+        assert_eq!(timers.len(), 1);
+        let marks = reopened_service
+            .list_marks_by_timer(&timers[0].id)
+            .expect("marks should load");
+        assert_eq!(marks.len(), 1);
+        assert_eq!(marks[0].description, "first pass");
+    }
+
+    #[test]
+    fn tests_reloads_multiline_mark_description_after_store_reopen() {
+        let root = unique_temp_dir("reload-multiline");
+
+        {
+            let store = CsvStore::new(&root).expect("csv store should be created");
+            let mut service = AppService::new(store);
+            let timer = service
+                .create_timer("reloadable", 300, 100)
+                .expect("timer should be created");
+            service
+                .create_mark(&timer.id, 140, "- a\n- a\n- a", vec![])
+                .expect("mark should be created");
+        }
+
+        let reopened_store = CsvStore::new(&root).expect("csv store should reopen");
+        let reopened_service = AppService::new(reopened_store);
+        let timers = reopened_service.list_timers(false);
+
+        assert_eq!(timers.len(), 1);
+        let marks = reopened_service
+            .list_marks_by_timer(&timers[0].id)
+            .expect("marks should load");
+        assert_eq!(marks.len(), 1);
+        assert_eq!(marks[0].description, "- a\n- a\n- a");
+    }
+
+    #[test]
+    fn tests_persists_todo_delete_after_store_reopen() {
+        let root = unique_temp_dir("delete-todo");
+        let timer_id;
+        let todo_id;
+
+        {
+            let store = CsvStore::new(&root).expect("csv store should be created");
+            let mut service = AppService::new(store);
+            let timer = service
+                .create_timer("cleanup", 300, 100)
+                .expect("timer should be created");
+            let todo = service
+                .create_todo(&timer.id, "obsolete", 110)
+                .expect("todo should be created");
+
+            timer_id = timer.id;
+            todo_id = todo.id.clone();
+
+            service
+                .delete_todo(&todo_id)
+                .expect("todo should be deleted");
+        }
+
+        let reopened_store = CsvStore::new(&root).expect("csv store should reopen");
+        let reopened_service = AppService::new(reopened_store);
+        let todos = reopened_service
+            .list_todos_by_timer(&timer_id)
+            .expect("todos should load");
+
+        assert!(todos.is_empty());
+    }
+
+    #[test]
+    fn tests_cascading_timer_delete_rewrites_all_csv_files() {
+        let root = unique_temp_dir("delete-timer");
+        let kept_timer_id;
+
+        {
+            let store = CsvStore::new(&root).expect("csv store should be created");
+            let mut service = AppService::new(store);
+            let doomed = service
+                .create_timer("doomed", 300, 100)
+                .expect("timer should be created");
+            let kept = service
+                .create_timer("kept", 400, 100)
+                .expect("timer should be created");
+            service
+                .create_todo(&doomed.id, "doomed todo", 110)
+                .expect("todo should be created");
+            service
+                .create_mark(&doomed.id, 120, "doomed mark", vec![])
+                .expect("mark should be created");
+            service
+                .create_todo(&kept.id, "kept todo", 110)
+                .expect("todo should be created");
+
+            kept_timer_id = kept.id;
+            service
+                .delete_timer(&doomed.id, true)
+                .expect("timer should be deleted");
+        }
+
+        let marks_csv =
+            std::fs::read_to_string(root.join("marks.csv")).expect("marks csv should be readable");
+        let todos_csv =
+            std::fs::read_to_string(root.join("todos.csv")).expect("todos csv should be readable");
+        assert!(!marks_csv.contains("doomed mark"));
+        assert!(!todos_csv.contains("doomed todo"));
+
+        let reopened_store = CsvStore::new(&root).expect("csv store should reopen");
+        let timers = reopened_store.list_timers(true);
+        assert_eq!(timers.len(), 1);
+        assert_eq!(timers[0].id, kept_timer_id);
+        assert_eq!(reopened_store.list_todos_by_timer(&kept_timer_id).len(), 1);
+    }
+
+    #[test]
+    fn tests_rolls_forward_interrupted_batch_on_reopen() {
+        let root = unique_temp_dir("interrupted-batch");
+        let timer_id = {
+            let store = CsvStore::new(&root).expect("csv store should be created");
+            let mut service = AppService::new(store);
+            let timer = service
+                .create_timer("doomed", 300, 100)
+                .expect("timer should be created");
+            service
+                .create_mark(&timer.id, 120, "doomed mark", vec![])
+                .expect("mark should be created");
+            timer.id
+        };
+
+        std::fs::write(root.join("timers.csv"), format!("{TIMERS_HEADER}\n"))
+            .expect("first rename should land");
+        std::fs::remove_file(root.join("marks.csv")).expect("second target should be removed");
+        std::fs::write(root.join("marks.tmp"), format!("{MARKS_HEADER}\n"))
+            .expect("second temporary should be staged");
+        std::fs::write(root.join("batch.journal"), "timers.csv\nmarks.csv")
+            .expect("journal should be written");
+
+        let reopened = CsvStore::new(&root).expect("csv store should recover");
+        assert!(reopened.list_timers(true).is_empty());
+        assert!(reopened.list_marks_by_timer(&timer_id).is_empty());
+        assert!(!root.join("marks.tmp").exists());
+        assert!(!root.join("batch.journal").exists());
+    }
+
+    #[test]
+    fn tests_persists_projects_and_timer_assignment_after_store_reopen() {
+        let root = unique_temp_dir("projects");
+        let timer_id;
+        let project_id;
+
+        {
+            let store = CsvStore::new(&root).expect("csv store should be created");
+            let mut service = AppService::new(store);
+            let project = service
+                .create_project("release", 100)
+                .expect("project should be created");
+            let timer = service
+                .create_timer("ship", 300, 100)
+                .expect("timer should be created");
+            service
+                .set_timer_project(&timer.id, Some(&project.id), 110)
+                .expect("timer should be assigned");
+
+            timer_id = timer.id;
+            project_id = project.id;
+        }
+
+        let projects_csv = std::fs::read_to_string(root.join("projects.csv"))
+            .expect("projects csv should be readable");
+        assert!(projects_csv.contains(PROJECTS_HEADER));
+        assert!(projects_csv.contains("release"));
+
+        let reopened_store = CsvStore::new(&root).expect("csv store should reopen");
+        assert!(reopened_store.get_project(&project_id).is_some());
+        let timer = reopened_store
+            .get_timer(&timer_id)
+            .expect("timer should reload");
+        assert_eq!(timer.project_id, Some(project_id));
+    }
+
+    #[test]
+    fn tests_reloads_tags_after_store_reopen() {
+        let root = unique_temp_dir("tags");
+        let timer_id;
+
+        {
+            let store = CsvStore::new(&root).expect("csv store should be created");
+            let mut service = AppService::new(store);
+            let timer = service
+                .create_timer("tagged", 300, 100)
+                .expect("timer should be created");
+            service
+                .set_timer_tags(
+                    &timer.id,
+                    vec!["release".to_string(), "q4".to_string()],
+                    105,
+                )
+                .expect("timer tags should be set");
+            service
+                .create_todo(&timer.id, "ask for #review", 110)
+                .expect("todo should be created");
+            service
+                .create_mark(&timer.id, 120, "#backend, done", vec![])
+                .expect("mark should be created");
+            timer_id = timer.id;
+        }
+
+        let reopened_store = CsvStore::new(&root).expect("csv store should reopen");
+        let timer = reopened_store
+            .get_timer(&timer_id)
+            .expect("timer should reload");
+        assert_eq!(timer.tags, vec!["release", "q4"]);
+        assert_eq!(
+            reopened_store.list_todos_by_timer(&timer_id)[0].tags,
+            vec!["review"]
+        );
+        assert_eq!(
+            reopened_store.list_marks_by_timer(&timer_id)[0].tags,
+            vec!["backend"]
+        );
+    }
+
+    #[test]
+    fn tests_rotates_automatic_backups_and_restores_snapshot() {
+        let root = unique_temp_dir("backups");
+        let policy = BackupPolicy::new(2, 3).expect("policy should be valid");
+        let store =
+            CsvStore::with_backup_policy(&root, policy).expect("csv store should be created");
+        let mut service = AppService::new(store);
+
+        let timer = service
+            .create_timer("release", 5_000, 100)
+            .expect("timer should be created");
+        let snapshot = service.create_backup().expect("backup should be created");
+        assert!(snapshot.files.contains(&"timers.csv".to_string()));
+
+        for minute in 0..7 {
+            service
+                .create_todo(&timer.id, format!("todo {minute}"), 110 + minute)
+                .expect("todo should be created");
+        }
+        let backups = service.list_backups().expect("backups should list");
+        assert_eq!(backups.len(), 2);
+        assert!(!backups.iter().any(|backup| backup.id == snapshot.id));
+
+        let older = backups[0].clone();
+        std::fs::write(
+            root.join("backups").join(&older.id).join("todos.csv"),
+            "not,a,todos,header\n",
+        )
+        .expect("snapshot should be writable");
+        assert!(service.restore_backup(&older.id).is_err());
+        assert_eq!(
+            service
+                .list_todos_by_timer(&timer.id)
+                .expect("todos should list")
+                .len(),
+            7
+        );
+
+        let newest = service.list_backups().expect("backups should list")[1].clone();
+        service
+            .restore_backup(&newest.id)
+            .expect("backup should be restored");
+        let restored_count = service
+            .list_todos_by_timer(&timer.id)
+            .expect("todos should list")
+            .len();
+        assert!(restored_count < 7);
 
-        assert!(timers_csv.contains(TIMERS_HEADER));
-        assert!(marks_csv.contains(MARKS_HEADER));
-        assert!(todos_csv.contains(TODOS_HEADER));
+        service
+            .create_todo(&timer.id, "after restore", 200)
+            .expect("todo should be created");
 
-        assert!(timers_csv.contains("phase2"));
-        assert!(marks_csv.contains("wrote csv repo"));
-        assert!(todos_csv.contains("implement csv"));
+        let reopened = CsvStore::open_read_only(&root).expect("csv store should reopen");
+        assert_eq!(
+            reopened.list_todos_by_timer(&timer.id).len(),
+            restored_count + 1
+        );
     }
 
     #[test]
-    fn tests_reloads_data_after_store_reopen() {
-        let root = unique_temp_dir("reload");
+    fn tests_pages_marks_in_time_order_after_reopen() {
+        let root = unique_temp_dir("pages");
+        let timer_id;
 
         {
             let store = CsvStore::new(&root).expect("csv store should be created");
             let mut service = AppService::new(store);
             let timer = service
-                .create_timer("reloadable", 300, 100)
+                .create_timer("release", 5_000, 100)
                 .expect("timer should be created");
-            service
-                .create_mark(&timer.id, 140, "first pass", vec![])
-                .expect("mark should be created");
+            for minute in [300, 100, 200, 400] {
+                service
+                    .create_mark(&timer.id, minute, format!("at {minute}"), vec![])
+                    .expect("mark should be created");
+            }
+            timer_id = timer.id;
         }
 
-        let reopened_store = CsvStore::new(&root).expect("csv store should reopen");
-        let reopened_service = AppService::new(reopened_store);
-        let timers = reopened_service.list_timers(false);
-
-        assert_eq!(timers.len(), 1);
-        let marks = reopened_service
-            .list_marks_by_timer(&timers[0].id)
-            .expect("marks should load");
-        assert_eq!(marks.len(), 1);
-        assert_eq!(marks[0].description, "first pass");
+        let mut store = CsvStore::new(&root).expect("csv store should reopen");
+        let mut moved = store.list_marks_by_timer(&timer_id)[3].clone();
+        moved.marked_at_minute = 50;
+        store.update_mark(moved).expect("mark should be updated");
+
+        let page = store
+            .page_marks_by_timer(
+                &timer_id,
+                &PageRequest {
+                    until_minute: Some(300),
+                    limit: Some(2),
+                    ..PageRequest::default()
+                },
+            )
+            .expect("page should load");
+        let minutes: Vec<i64> = page
+            .items
+            .iter()
+            .map(|mark| mark.marked_at_minute)
+            .collect();
+        assert_eq!(minutes, vec![50, 100]);
+        assert_eq!(page.total, 3);
+        assert!(page.next_cursor.is_some());
     }
 
     #[test]
-    fn tests_reloads_multiline_mark_description_after_store_reopen() {
-        let root = unique_temp_dir("reload-multiline");
+    fn tests_rebuilds_csv_state_from_event_log() {
+        let root = unique_temp_dir("events");
+        let timer_id;
 
         {
             let store = CsvStore::new(&root).expect("csv store should be created");
             let mut service = AppService::new(store);
             let timer = service
-                .create_timer("reloadable", 300, 100)
+                .create_timer("release", 5_000, 100)
                 .expect("timer should be created");
+            let todo = service
+                .create_todo(&timer.id, "write, \"quoted\" notes", 110)
+                .expect("todo should be created");
             service
-                .create_mark(&timer.id, 140, "- a\n- a\n- a", vec![])
+                .create_mark(&timer.id, 120, "line one\nline two", vec![todo.id.clone()])
                 .expect("mark should be created");
+            service
+                .delete_todo(&todo.id)
+                .expect("todo should be deleted");
+            timer_id = timer.id;
         }
 
-        let reopened_store = CsvStore::new(&root).expect("csv store should reopen");
-        let reopened_service = AppService::new(reopened_store);
-        let timers = reopened_service.list_timers(false);
+        std::fs::write(root.join("marks.csv"), format!("{MARKS_HEADER}\n"))
+            .expect("marks csv should be writable");
 
-        assert_eq!(timers.len(), 1);
-        let marks = reopened_service
-            .list_marks_by_timer(&timers[0].id)
-            .expect("marks should load");
+        let store = CsvStore::new(&root).expect("csv store should reopen");
+        let mut service = AppService::new(store);
+        assert!(service
+            .list_marks_by_timer(&timer_id)
+            .expect("marks should list")
+            .is_empty());
+
+        let summary = service.rebuild_from_events().expect("state should rebuild");
+        assert_eq!(summary.event_count, 4);
+        assert_eq!(summary.todo_count, 0);
+
+        let reopened = CsvStore::open_read_only(&root).expect("csv store should reopen");
+        let marks = reopened.list_marks_by_timer(&timer_id);
         assert_eq!(marks.len(), 1);
-        assert_eq!(marks[0].description, "- a\n- a\n- a");
+        assert_eq!(marks[0].description, "line one\nline two");
     }
 
     #[test]
-    fn tests_persists_todo_delete_after_store_reopen() {
-        let root = unique_temp_dir("delete-todo");
-        let timer_id;
-        let todo_id;
-
+    fn tests_tag_merge_keeps_orphaned_rows() {
+        let root = unique_temp_dir("tag-merge-orphans");
         {
             let store = CsvStore::new(&root).expect("csv store should be created");
             let mut service = AppService::new(store);
             let timer = service
-                .create_timer("cleanup", 300, 100)
+                .create_timer("api", 1_000, 0)
                 .expect("timer should be created");
-            let todo = service
-                .create_todo(&timer.id, "obsolete", 110)
-                .expect("todo should be created");
+            service
+                .create_mark(&timer.id, 10, "#cr done", vec![])
+                .expect("mark should be created");
+        }
+        let marks_path = root.join("marks.csv");
+        let mut marks_csv = std::fs::read_to_string(&marks_path).expect("marks csv");
+        marks_csv.push_str("mark-99,timer-gone,20,,,#cr orphan,,cr,\n");
+        std::fs::write(&marks_path, marks_csv).expect("orphan row should be written");
 
-            timer_id = timer.id;
-            todo_id = todo.id.clone();
+        let store = CsvStore::new(&root).expect("csv store should reopen");
+        let mut service = AppService::new(store);
+        let usage = service
+            .merge_tags(&["cr".to_string()], "review", 30)
+            .expect("tags should merge");
+        assert_eq!(usage.mark_count, 1);
+
+        let marks_csv = std::fs::read_to_string(&marks_path).expect("marks csv");
+        assert!(marks_csv.contains("mark-99,timer-gone,20,,,#cr orphan,,cr,"));
+        assert!(marks_csv.contains("#review done"));
+    }
 
+    #[test]
+    fn tests_keeps_edits_made_while_closed_through_rebuild() {
+        let root = unique_temp_dir("events-offline-edit");
+        let timer_id = {
+            let store = CsvStore::new(&root).expect("csv store should be created");
+            let mut service = AppService::new(store);
             service
-                .delete_todo(&todo_id)
-                .expect("todo should be deleted");
+                .create_timer("release", 5_000, 100)
+                .expect("timer should be created")
+                .id
+        };
+
+        {
+            let mut store = CsvStore::new(&root).expect("csv store should reopen");
+            let mut timer = store.get_timer(&timer_id).expect("timer should exist");
+            timer.name = "edited offline".to_string();
+            store
+                .save_timer(timer)
+                .expect("timer should save without events");
+        }
+
+        let store = CsvStore::new(&root).expect("csv store should reopen");
+        let mut service = AppService::open(store).expect("service should open");
+        service.rebuild_from_events().expect("state should rebuild");
+        let timers = service.list_timers(false);
+        assert_eq!(timers.len(), 1);
+        assert_eq!(timers[0].name, "edited offline");
+    }
+
+    #[test]
+    fn tests_reports_event_log_reconcile_failure_on_open() {
+        let root = unique_temp_dir("events-unwritable");
+        let mut store = CsvStore::new(&root).expect("csv store should be created");
+        store
+            .save_timer(Timer {
+                id: "timer-1".to_string(),
+                name: "legacy".to_string(),
+                target_at_minute: 500,
+                created_at_minute: 100,
+                updated_at_minute: 100,
+                archived: false,
+                project_id: None,
+                tags: Vec::new(),
+            })
+            .expect("timer should save without events");
+
+        let reader = CsvStore::open_read_only(&root).expect("reader should open");
+        assert!(AppService::open(reader).is_ok());
+
+        std::fs::remove_file(root.join("events.csv")).expect("events csv should be removed");
+        std::fs::create_dir(root.join("events.csv")).expect("events path should be blocked");
+        assert!(AppService::open(store).is_err());
+    }
+
+    #[test]
+    fn tests_reloads_multiline_template_body_after_store_reopen() {
+        let root = unique_temp_dir("templates");
+        let template_id;
+
+        {
+            let store = CsvStore::new(&root).expect("csv store should be created");
+            let mut service = AppService::new(store);
+            let template = service
+                .create_template("daily", "[{timer}], {duration}\n{open_todos}", 100)
+                .expect("template should be created");
+            template_id = template.id;
         }
 
         let reopened_store = CsvStore::new(&root).expect("csv store should reopen");
-        let reopened_service = AppService::new(reopened_store);
-        let todos = reopened_service
-            .list_todos_by_timer(&timer_id)
-            .expect("todos should load");
+        let template = reopened_store
+            .get_template(&template_id)
+            .expect("template should reload");
+        assert_eq!(template.body, "[{timer}], {duration}\n{open_todos}");
+    }
 
-        assert!(todos.is_empty());
+    #[test]
+    fn tests_loads_timers_csv_written_before_project_column() {
+        let root = unique_temp_dir("legacy-timers");
+        std::fs::write(
+            root.join("timers.csv"),
+            "id,name,target_at,created_at,updated_at,archived\ntimer-1,legacy,300,100,100,false\n",
+        )
+        .expect("legacy csv should be written");
+
+        let store = CsvStore::new(&root).expect("legacy csv should load");
+        let timer = store.get_timer("timer-1").expect("timer should load");
+        assert_eq!(timer.name, "legacy");
+        assert_eq!(timer.project_id, None);
     }
 
     #[test]
@@ -716,6 +2805,8 @@ mod persistence {
             created_at_minute: 100,
             updated_at_minute: 100,
             archived: false,
+            project_id: None,
+            tags: vec![],
         };
 
         store.save_timer(timer).expect("save should succeed");
@@ -728,7 +2819,9 @@ mod restart {
     use std::path::PathBuf;
     use std::time::{SystemTime, UNIX_EPOCH};
 
-    use crate::repository::CsvStore;
+    use crate::dataset::ImportMode;
+    use crate::reminder::ReminderPolicy;
+    use crate::repository::{CsvStore, InMemoryStore};
     use crate::service::AppService;
 
     fn unique_temp_dir(prefix: &str) -> PathBuf {
@@ -767,6 +2860,36 @@ mod restart {
         assert_eq!(second_mark.duration_minutes, Some(45));
     }
 
+    #[test]
+    fn tests_does_not_repeat_fired_reminders_after_restart() {
+        let root = unique_temp_dir("restart-reminders");
+        let policy = ReminderPolicy::default();
+        {
+            let store = CsvStore::new(&root).expect("csv store should be created");
+            let mut service = AppService::new(store);
+            service
+                .create_timer("deadline", 500, 100)
+                .expect("timer should be created");
+            let events = service
+                .poll_reminders(450, &policy)
+                .expect("reminders should be polled");
+            assert_eq!(events.len(), 1);
+        }
+
+        let store = CsvStore::new(&root).expect("csv store should reopen");
+        let mut service = AppService::new(store);
+        let events = service
+            .poll_reminders(455, &policy)
+            .expect("reminders should be polled");
+        assert!(events.is_empty());
+
+        let events = service
+            .poll_reminders(495, &policy)
+            .expect("reminders should be polled");
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].offset_minutes, 10);
+    }
+
     #[test]
     fn tests_add_todo_after_restart_does_not_overwrite_existing_todo() {
         let root = unique_temp_dir("restart-todo-overwrite");
@@ -797,4 +2920,447 @@ mod restart {
         assert!(todos.iter().any(|todo| todo.title == "after restart"));
         assert!(todos.iter().any(|todo| todo.id != second_todo.id));
     }
+
+    #[test]
+    fn tests_imports_time_entries_into_csv_store() {
+        let root = unique_temp_dir("import-time-entries");
+        let csv = "Project,Description,Start date,Start time,End date,End time,Tags\n\
+                   Website,header,1970-01-01,01:00,1970-01-01,02:00,\n\
+                   Website,footer,1970-01-01,03:00,1970-01-01,03:45,ui\n";
+        {
+            let store = CsvStore::new(&root).expect("csv store should be created");
+            let mut service = AppService::new(store);
+            let report = service
+                .import_time_entries(csv, 0, 500)
+                .expect("time entries should import");
+            assert_eq!(report.created_timers.len(), 1);
+            assert_eq!(report.marks.len(), 2);
+        }
+
+        let store = CsvStore::new(&root).expect("csv store should reopen");
+        let mut service = AppService::new(store);
+        let timers = service.list_timers(false);
+        assert_eq!(timers.len(), 1);
+        assert_eq!(timers[0].name, "Website");
+        let marks = service
+            .list_marks_by_timer(&timers[0].id)
+            .expect("marks should load");
+        let spans: Vec<(Option<i64>, i64, Option<i64>)> = marks
+            .iter()
+            .map(|mark| {
+                (
+                    mark.prev_marked_at_minute,
+                    mark.marked_at_minute,
+                    mark.duration_minutes,
+                )
+            })
+            .collect();
+        assert_eq!(
+            spans,
+            vec![(Some(60), 120, Some(60)), (Some(180), 225, Some(45))]
+        );
+
+        let again = service
+            .import_time_entries(csv, 0, 600)
+            .expect("repeated import should succeed");
+        assert!(again.marks.is_empty());
+        assert_eq!(again.skipped.len(), 2);
+    }
+
+    #[test]
+    fn tests_replaces_csv_store_from_data_export() {
+        let root = unique_temp_dir("import-data");
+        let mut source = AppService::new(InMemoryStore::default());
+        let timer = source
+            .create_timer("release", 1_000, 0)
+            .expect("timer should be created");
+        let first = source
+            .create_todo(&timer.id, "docs; api", 1)
+            .expect("todo should be created");
+        let second = source
+            .create_todo(&timer.id, "ship", 2)
+            .expect("todo should be created");
+        source
+            .create_mark(&timer.id, 30, "done", vec![first.id, second.id])
+            .expect("mark should be created");
+        let export = source.export_data(40).expect("data should export");
+
+        {
+            let store = CsvStore::new(&root).expect("csv store should be created");
+            let mut service = AppService::new(store);
+            service
+                .create_timer("stale", 10, 0)
+                .expect("timer should be created");
+            service
+                .import_data(&export, ImportMode::Replace)
+                .expect("data should import");
+        }
+
+        let store = CsvStore::new(&root).expect("csv store should reopen");
+        let service = AppService::new(store);
+        assert_eq!(service.export_data(40), Ok(export));
+    }
+}
+
+#[cfg(test)]
+mod syncing {
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use crate::dataset::ImportMode;
+    use crate::merge::MergeSide;
+    use crate::repository::{CsvStore, InMemoryStore};
+    use crate::service::AppService;
+
+    fn unique_temp_dir(prefix: &str) -> PathBuf {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system clock should be after unix epoch")
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("countdown-todo-{prefix}-{timestamp}"));
+        std::fs::create_dir_all(&dir).expect("temporary dir should be created");
+        dir
+    }
+
+    #[test]
+    fn tests_merges_divergent_data_dirs() {
+        let left_root = unique_temp_dir("merge-left");
+        let right_root = unique_temp_dir("merge-right");
+        let mut left = AppService::new(CsvStore::new(&left_root).expect("left store"));
+        let timer = left
+            .create_timer("release", 1_000, 0)
+            .expect("timer should be created");
+        for minute in [10, 30] {
+            left.create_mark(&timer.id, minute, "", vec![])
+                .expect("mark should be created");
+        }
+        {
+            let mut right = AppService::new(CsvStore::new(&right_root).expect("right store"));
+            right
+                .import_data(&left.export_data(40).expect("export"), ImportMode::Replace)
+                .expect("right copy should import");
+            right
+                .update_timer(&timer.id, "release v2", 1_000, 50)
+                .expect("timer should be renamed");
+            right
+                .create_mark(&timer.id, 40, "right", vec![])
+                .expect("mark should be created");
+        }
+        let ours = left
+            .create_mark(&timer.id, 35, "left", vec![])
+            .expect("mark should be created");
+
+        let report = left
+            .merge_data_dir(&right_root)
+            .expect("directories should merge");
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].kept, MergeSide::Right);
+        assert_eq!(report.remapped.len(), 1);
+        assert_eq!(report.remapped[0].from, ours.id);
+        assert_eq!(report.deduplicated, 2);
+        drop(left);
+
+        let reopened = AppService::new(CsvStore::new(&left_root).expect("left store"));
+        assert_eq!(reopened.list_timers(false)[0].name, "release v2");
+        let marks = reopened
+            .list_marks_by_timer(&timer.id)
+            .expect("marks should load");
+        let chain: Vec<(i64, Option<i64>)> = marks
+            .iter()
+            .map(|mark| (mark.marked_at_minute, mark.prev_marked_at_minute))
+            .collect();
+        assert_eq!(
+            chain,
+            vec![(10, None), (30, Some(10)), (35, Some(30)), (40, Some(35))]
+        );
+        assert!(left_root.join("backups").exists());
+        assert!(AppService::new(InMemoryStore::default())
+            .merge_data_dir(&left_root.join("missing"))
+            .is_err());
+    }
+
+    #[test]
+    fn tests_syncs_devices_through_shared_folder() {
+        let root = unique_temp_dir("sync");
+        let mut laptop = AppService::new(CsvStore::with_device(&root, "laptop").expect("laptop"));
+        let mut desktop =
+            AppService::new(CsvStore::with_device(&root, "desktop").expect("desktop"));
+        let timer = laptop
+            .create_timer("release", 1_000, 0)
+            .expect("timer should be created");
+        assert_eq!(timer.id, "timer-laptop-1");
+        assert!(desktop.sync().expect("desktop should sync").changed);
+
+        let first = laptop
+            .create_mark(&timer.id, 10, "laptop", vec![])
+            .expect("mark should be created");
+        let second = desktop
+            .create_mark(&timer.id, 20, "desktop", vec![])
+            .expect("mark should be created");
+        assert_ne!(first.id, second.id);
+        laptop
+            .update_timer(&timer.id, "release from laptop", 1_000, 30)
+            .expect("laptop rename");
+        desktop
+            .update_timer(&timer.id, "release from desktop", 1_000, 30)
+            .expect("desktop rename");
+
+        let report = laptop.sync().expect("laptop should sync");
+        assert_eq!(report.devices, vec!["desktop", "laptop"]);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].kept, "3@laptop");
+        assert_eq!(report.conflicts[0].discarded, "3@desktop");
+        desktop.sync().expect("desktop should sync");
+        assert!(!desktop.sync().expect("second sync is a no-op").changed);
+
+        for service in [&laptop, &desktop] {
+            assert_eq!(service.list_timers(false)[0].name, "release from laptop");
+            let chain: Vec<(i64, Option<i64>)> = service
+                .list_marks_by_timer(&timer.id)
+                .expect("marks should load")
+                .iter()
+                .map(|mark| (mark.marked_at_minute, mark.prev_marked_at_minute))
+                .collect();
+            assert_eq!(chain, vec![(10, None), (20, Some(10))]);
+        }
+        drop(laptop);
+
+        let reopened = AppService::new(CsvStore::with_device(&root, "laptop").expect("laptop"));
+        assert_eq!(reopened.list_timers(false)[0].name, "release from laptop");
+        assert_eq!(
+            std::fs::read_to_string(root.join("timers.csv")).expect("baseline"),
+            format!("{}\n", super::TIMERS_HEADER)
+        );
+        assert!(CsvStore::with_device(&root, "bad device").is_err());
+    }
+}
+
+#[cfg(test)]
+mod locking {
+    use std::path::PathBuf;
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    use crate::error::AppError;
+    use crate::reminder::ReminderPolicy;
+    use crate::repository::{CsvStore, Store};
+    use crate::service::AppService;
+
+    fn unique_temp_dir(prefix: &str) -> PathBuf {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("system clock should be after unix epoch")
+            .as_nanos();
+        let dir = std::env::temp_dir().join(format!("countdown-todo-{prefix}-{timestamp}"));
+        std::fs::create_dir_all(&dir).expect("temporary dir should be created");
+        dir
+    }
+
+    #[test]
+    fn tests_locks_data_dir_against_second_writer() {
+        let root = unique_temp_dir("lock");
+        let mut writer = AppService::new(CsvStore::new(&root).expect("writer should lock"));
+        let timer = writer
+            .create_timer("release", 1_000, 0)
+            .expect("timer should be created");
+        assert!(matches!(
+            CsvStore::new(&root),
+            Err(AppError::Conflict(message)) if message.contains(&std::process::id().to_string())
+        ));
+
+        let mut reader =
+            AppService::new(CsvStore::open_read_only(&root).expect("reader should open"));
+        assert_eq!(reader.list_timers(false), vec![timer]);
+        assert!(matches!(
+            reader.create_timer("other", 1_000, 0),
+            Err(AppError::Conflict(_))
+        ));
+        assert_eq!(reader.list_timers(false).len(), 1);
+        assert!(CsvStore::open_read_only(root.join("missing")).is_err());
+
+        drop(writer);
+        assert!(CsvStore::new(&root).is_ok());
+    }
+
+    #[test]
+    fn tests_rejects_writes_over_external_edits_until_reloaded() {
+        let root = unique_temp_dir("external-edit");
+        let timers_path = root.join("timers.csv");
+        let mut service = AppService::new(CsvStore::new(&root).expect("store should open"));
+        let timer = service
+            .create_timer("release", 1_000, 0)
+            .expect("timer should be created");
+        let edited = std::fs::read_to_string(&timers_path)
+            .expect("timers csv should read")
+            .replace("release", "release (edited)");
+        std::fs::write(&timers_path, &edited).expect("spreadsheet edit should save");
+
+        assert!(matches!(
+            service.update_timer(&timer.id, "release v2", 1_000, 10),
+            Err(AppError::Conflict(message)) if message.contains("timers.csv")
+        ));
+        assert_eq!(
+            std::fs::read_to_string(&timers_path).expect("timers csv should read"),
+            edited
+        );
+        service
+            .create_todo(&timer.id, "unrelated file", 10)
+            .expect("untouched files stay writable");
+
+        let mut reader =
+            AppService::new(CsvStore::open_read_only(&root).expect("reader should open"));
+        assert!(service.sync().expect("writer should reload").changed);
+        assert_eq!(service.list_timers(false)[0].name, "release (edited)");
+        service
+            .update_timer(&timer.id, "release v2", 1_000, 20)
+            .expect("update after reload should save");
+        std::fs::write(
+            &timers_path,
+            std::fs::read_to_string(&timers_path).expect("timers csv should read"),
+        )
+        .expect("touching the file should succeed");
+        service
+            .update_timer(&timer.id, "release v3", 1_000, 30)
+            .expect("an unchanged rewrite is not a conflict");
+
+        assert!(reader.sync().expect("reader should reload").changed);
+        assert_eq!(reader.list_timers(false)[0].name, "release v3");
+        assert!(!reader.sync().expect("second reload is a no-op").changed);
+    }
+
+    #[test]
+    fn tests_keeps_disk_state_after_rejected_write() {
+        let root = unique_temp_dir("rejected-write");
+        let timers_path = root.join("timers.csv");
+        let mut service = AppService::new(CsvStore::new(&root).expect("store should open"));
+        service
+            .create_timer("release", 1_000, 0)
+            .expect("timer should be created");
+        let edited = std::fs::read_to_string(&timers_path)
+            .expect("timers csv should read")
+            .replace("release", "release (edited)");
+        std::fs::write(&timers_path, edited).expect("spreadsheet edit should save");
+
+        assert!(matches!(
+            service.create_timer("hotfix", 2_000, 10),
+            Err(AppError::Conflict(_))
+        ));
+        let on_disk = CsvStore::open_read_only(&root).expect("reader should open");
+        assert_eq!(service.list_timers(true), on_disk.list_timers(true));
+        assert_eq!(service.list_timers(true)[0].name, "release (edited)");
+        assert!(service.sync().expect("writer should sync").changed);
+        service
+            .create_timer("hotfix", 2_000, 20)
+            .expect("create after reload should save");
+    }
+
+    #[test]
+    fn tests_rejected_tag_merge_leaves_every_file_untouched() {
+        let root = unique_temp_dir("rejected-tag-merge");
+        let mut service = AppService::new(CsvStore::new(&root).expect("store should open"));
+        let timer = service
+            .create_timer("api", 1_000, 0)
+            .expect("timer should be created");
+        service
+            .set_timer_tags(&timer.id, vec!["cr".to_string()], 5)
+            .expect("timer tags should be set");
+        service
+            .create_mark(&timer.id, 10, "#cr done", vec![])
+            .expect("mark should be created");
+        let timers_csv = std::fs::read_to_string(root.join("timers.csv")).expect("timers csv");
+        let marks_path = root.join("marks.csv");
+        let edited = format!(
+            "{}\n",
+            std::fs::read_to_string(&marks_path).expect("marks csv")
+        );
+        std::fs::write(&marks_path, &edited).expect("spreadsheet edit should save");
+
+        assert!(matches!(
+            service.merge_tags(&["cr".to_string()], "review", 20),
+            Err(AppError::Conflict(_))
+        ));
+        assert_eq!(
+            std::fs::read_to_string(root.join("timers.csv")).expect("timers csv"),
+            timers_csv
+        );
+        assert_eq!(service.list_timers(true)[0].tags, vec!["cr"]);
+        assert_eq!(
+            service.history_status().undo_label.as_deref(),
+            Some("create mark")
+        );
+    }
+
+    #[test]
+    fn tests_read_only_store_still_polls_reminders() {
+        let root = unique_temp_dir("read-only-reminders");
+        let _writer = {
+            let mut service = AppService::new(CsvStore::new(&root).expect("writer should lock"));
+            service
+                .create_timer("release", 1_000, 0)
+                .expect("timer should be created");
+            service.into_store()
+        };
+        let reminders_csv =
+            std::fs::read_to_string(root.join("reminders.csv")).expect("reminders csv");
+
+        let mut reader =
+            AppService::new(CsvStore::open_read_only(&root).expect("reader should open"));
+        let policy = ReminderPolicy::default();
+        assert!(!reader
+            .poll_reminders(995, &policy)
+            .expect("reader should poll")
+            .is_empty());
+        assert!(reader
+            .poll_reminders(996, &policy)
+            .expect("reader should poll")
+            .is_empty());
+        assert_eq!(
+            std::fs::read_to_string(root.join("reminders.csv")).expect("reminders csv"),
+            reminders_csv
+        );
+    }
+
+    #[test]
+    fn tests_rejected_undo_stays_on_the_undo_stack() {
+        let root = unique_temp_dir("rejected-undo");
+        let mut service = AppService::new(CsvStore::new(&root).expect("store should open"));
+        let timer = service
+            .create_timer("api", 1_000, 0)
+            .expect("timer should be created");
+        service
+            .create_todo(&timer.id, "review", 5)
+            .expect("todo should be created");
+        service
+            .create_mark(&timer.id, 10, "done", vec![])
+            .expect("mark should be created");
+        service
+            .delete_timer(&timer.id, true)
+            .expect("timer should be deleted");
+        let timers_csv = std::fs::read_to_string(root.join("timers.csv")).expect("timers csv");
+        let marks_path = root.join("marks.csv");
+        let edited = format!(
+            "{}\n",
+            std::fs::read_to_string(&marks_path).expect("marks csv")
+        );
+        std::fs::write(&marks_path, &edited).expect("spreadsheet edit should save");
+
+        // Only marks.csv was edited, yet no part of the step may reach disk.
+        assert!(matches!(service.undo(), Err(AppError::Conflict(_))));
+        assert_eq!(
+            std::fs::read_to_string(root.join("timers.csv")).expect("timers csv"),
+            timers_csv
+        );
+        assert!(service.list_timers(true).is_empty());
+        assert_eq!(service.history_status().undo_depth, 4);
+
+        let step = service.undo().expect("undo should retry");
+        assert_eq!(step.change_count, 3);
+        assert_eq!(service.list_timers(true).len(), 1);
+        assert_eq!(
+            service
+                .list_marks_by_timer(&timer.id)
+                .expect("marks should list")
+                .len(),
+            1
+        );
+    }
 }
@@ -0,0 +1,550 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, AppResult};
+use crate::event::DomainEvent;
+use crate::history::Entity;
+use crate::model::{EpochMinutes, Mark, Todo, TodoStatus};
+
+pub const DEFAULT_LIMIT: usize = 50;
+
+const SNIPPET_BEFORE: usize = 20;
+const SNIPPET_AFTER: usize = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SearchKind {
+    Mark,
+    Todo,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SearchQuery {
+    pub text: String,
+    pub timer_id: Option<String>,
+    pub since_minute: Option<EpochMinutes>,
+    pub until_minute: Option<EpochMinutes>,
+    pub status: Option<TodoStatus>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SearchHit {
+    pub kind: SearchKind,
+    pub id: String,
+    pub timer_id: String,
+    pub text: String,
+    pub snippet: String,
+    pub score: u32,
+    pub at_minute: EpochMinutes,
+    pub status: Option<TodoStatus>,
+}
+
+type DocKey = (SearchKind, String);
+
+#[derive(Debug, Clone)]
+struct Document {
+    timer_id: String,
+    text: String,
+    folded: Vec<char>,
+    at_minute: EpochMinutes,
+    status: Option<TodoStatus>,
+}
+
+#[derive(Debug, Default)]
+pub struct SearchIndex {
+    documents: HashMap<DocKey, Document>,
+    postings: BTreeMap<String, BTreeSet<DocKey>>,
+}
+
+impl SearchIndex {
+    pub fn build(todos: &[Todo], marks: &[Mark]) -> Self {
+        let mut index = Self::default();
+        for todo in todos {
+            index.upsert_todo(todo);
+        }
+        for mark in marks {
+            index.upsert_mark(mark);
+        }
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    pub fn apply(&mut self, event: &DomainEvent) {
+        match &event.state {
+            Some(Entity::Todo(todo)) => self.upsert_todo(todo),
+            Some(Entity::Mark(mark)) => self.upsert_mark(mark),
+            Some(_) => {}
+            None => match event.entity_type.as_str() {
+                "todo" => self.remove(&(SearchKind::Todo, event.entity_id.clone())),
+                "mark" => self.remove(&(SearchKind::Mark, event.entity_id.clone())),
+                "timer" => self.remove_timer(&event.entity_id),
+                _ => {}
+            },
+        }
+    }
+
+    pub fn search(&self, query: &SearchQuery) -> AppResult<Vec<SearchHit>> {
+        let terms = query_terms(&query.text);
+        if terms.is_empty() {
+            return Err(AppError::Validation(
+                "search text cannot be empty".to_string(),
+            ));
+        }
+        if let (Some(since), Some(until)) = (query.since_minute, query.until_minute) {
+            if since > until {
+                return Err(AppError::Validation(format!(
+                    "search range start {since} is after end {until}"
+                )));
+            }
+        }
+
+        let mut hits = Vec::new();
+        for key in self.candidates(&terms) {
+            let document = &self.documents[&key];
+            if !matches_filters(&key, document, query) {
+                continue;
+            }
+            let Some(score) = score(document, &terms) else {
+                continue;
+            };
+            hits.push(SearchHit {
+                kind: key.0,
+                id: key.1,
+                timer_id: document.timer_id.clone(),
+                text: document.text.clone(),
+                snippet: snippet(document, &terms),
+                score,
+                at_minute: document.at_minute,
+                status: document.status.clone(),
+            });
+        }
+
+        hits.sort_by(|left, right| {
+            right
+                .score
+                .cmp(&left.score)
+                .then(right.at_minute.cmp(&left.at_minute))
+                .then(left.id.cmp(&right.id))
+        });
+        hits.truncate(query.limit.unwrap_or(DEFAULT_LIMIT));
+        Ok(hits)
+    }
+
+    fn upsert_todo(&mut self, todo: &Todo) {
+        self.insert(
+            (SearchKind::Todo, todo.id.clone()),
+            Document {
+                timer_id: todo.timer_id.clone(),
+                text: todo.title.clone(),
+                folded: fold(&todo.title),
+                at_minute: todo.created_at_minute,
+                status: Some(todo.status.clone()),
+            },
+        );
+    }
+
+    fn upsert_mark(&mut self, mark: &Mark) {
+        self.insert(
+            (SearchKind::Mark, mark.id.clone()),
+            Document {
+                timer_id: mark.timer_id.clone(),
+                text: mark.description.clone(),
+                folded: fold(&mark.description),
+                at_minute: mark.marked_at_minute,
+                status: None,
+            },
+        );
+    }
+
+    fn insert(&mut self, key: DocKey, document: Document) {
+        self.remove(&key);
+        for token in tokens(&document.folded) {
+            self.postings.entry(token).or_default().insert(key.clone());
+        }
+        self.documents.insert(key, document);
+    }
+
+    fn remove(&mut self, key: &DocKey) {
+        let Some(document) = self.documents.remove(key) else {
+            return;
+        };
+        for token in tokens(&document.folded) {
+            if let Some(keys) = self.postings.get_mut(&token) {
+                keys.remove(key);
+                if keys.is_empty() {
+                    self.postings.remove(&token);
+                }
+            }
+        }
+    }
+
+    fn remove_timer(&mut self, timer_id: &str) {
+        let keys: Vec<DocKey> = self
+            .documents
+            .iter()
+            .filter(|(_, document)| document.timer_id == timer_id)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in keys {
+            self.remove(&key);
+        }
+    }
+
+    fn candidates(&self, terms: &[Vec<char>]) -> BTreeSet<DocKey> {
+        let mut candidates: Option<BTreeSet<DocKey>> = None;
+        for term in terms {
+            let mut matched = BTreeSet::new();
+            for token in tokens(term) {
+                for (_, keys) in self
+                    .postings
+                    .range(token.clone()..)
+                    .take_while(|(indexed, _)| indexed.starts_with(&token))
+                {
+                    matched.extend(keys.iter().cloned());
+                }
+            }
+            candidates = Some(match candidates {
+                Some(current) => current.intersection(&matched).cloned().collect(),
+                None => matched,
+            });
+        }
+        candidates.unwrap_or_default()
+    }
+}
+
+pub fn fold(text: &str) -> Vec<char> {
+    text.chars().map(fold_char).collect()
+}
+
+fn fold_char(char: char) -> char {
+    let char = match char {
+        '\u{ff01}'..='\u{ff5e}' => char::from_u32(char as u32 - 0xfee0).unwrap_or(char),
+        '\u{3000}' => ' ',
+        _ => char,
+    };
+    let lower = char.to_lowercase().next().unwrap_or(char);
+    match lower {
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'ā' | 'ă' | 'ą' | 'ǎ' => 'a',
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'ď' | 'đ' => 'd',
+        'è' | 'é' | 'ê' | 'ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'ĥ' | 'ħ' => 'h',
+        'ì' | 'í' | 'î' | 'ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' | 'ǐ' => 'i',
+        'ĵ' => 'j',
+        'ķ' => 'k',
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => 'l',
+        'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'ō' | 'ŏ' | 'ő' | 'ǒ' => 'o',
+        'ŕ' | 'ŗ' | 'ř' => 'r',
+        'ś' | 'ŝ' | 'ş' | 'š' => 's',
+        'ţ' | 'ť' | 'ŧ' => 't',
+        'ù' | 'ú' | 'û' | 'ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' | 'ǔ' | 'ǖ' | 'ǘ' | 'ǚ' | 'ǜ' => {
+            'u'
+        }
+        'ŵ' => 'w',
+        'ý' | 'ÿ' | 'ŷ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        other => other,
+    }
+}
+
+fn is_cjk(char: char) -> bool {
+    matches!(
+        char,
+        '\u{3040}'..='\u{30ff}'
+            | '\u{3400}'..='\u{4dbf}'
+            | '\u{4e00}'..='\u{9fff}'
+            | '\u{ac00}'..='\u{d7af}'
+            | '\u{f900}'..='\u{faff}'
+    )
+}
+
+fn tokens(folded: &[char]) -> BTreeSet<String> {
+    let mut tokens = BTreeSet::new();
+    let mut word = String::new();
+    for (position, &char) in folded.iter().enumerate() {
+        if is_cjk(char) {
+            flush_word(&mut tokens, &mut word);
+            tokens.insert(char.to_string());
+            if let Some(&next) = folded.get(position + 1).filter(|next| is_cjk(**next)) {
+                tokens.insert([char, next].iter().collect());
+            }
+        } else if char.is_alphanumeric() {
+            word.push(char);
+        } else {
+            flush_word(&mut tokens, &mut word);
+        }
+    }
+    flush_word(&mut tokens, &mut word);
+    tokens
+}
+
+fn flush_word(tokens: &mut BTreeSet<String>, word: &mut String) {
+    if !word.is_empty() {
+        tokens.insert(std::mem::take(word));
+    }
+}
+
+// Split at the same boundaries as `tokens`, so "login-bug" looks up the
+// "login" and "bug" tokens the index stored instead of "loginbug".
+fn query_terms(text: &str) -> Vec<Vec<char>> {
+    let mut terms: Vec<Vec<char>> = Vec::new();
+    for term in fold(text).split(|char| !char.is_alphanumeric()) {
+        if !term.is_empty() && !terms.iter().any(|known| known == term) {
+            terms.push(term.to_vec());
+        }
+    }
+    terms
+}
+
+fn matches_filters(key: &DocKey, document: &Document, query: &SearchQuery) -> bool {
+    query
+        .timer_id
+        .as_ref()
+        .is_none_or(|timer_id| &document.timer_id == timer_id)
+        && query
+            .since_minute
+            .is_none_or(|since| document.at_minute >= since)
+        && query
+            .until_minute
+            .is_none_or(|until| document.at_minute < until)
+        && query.status.as_ref().is_none_or(|status| {
+            key.0 == SearchKind::Todo && document.status.as_ref() == Some(status)
+        })
+}
+
+fn find_all(haystack: &[char], needle: &[char]) -> Vec<usize> {
+    if needle.is_empty() || needle.len() > haystack.len() {
+        return Vec::new();
+    }
+    haystack
+        .windows(needle.len())
+        .enumerate()
+        .filter(|(_, window)| *window == needle)
+        .map(|(position, _)| position)
+        .collect()
+}
+
+fn score(document: &Document, terms: &[Vec<char>]) -> Option<u32> {
+    let mut score = 0u32;
+    for term in terms {
+        let positions = find_all(&document.folded, term);
+        if positions.is_empty() {
+            return None;
+        }
+        score += 10 * positions.len().min(5) as u32;
+        let whole_word = positions.iter().any(|&position| {
+            let before = position.checked_sub(1).map(|index| document.folded[index]);
+            let after = document.folded.get(position + term.len()).copied();
+            !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+        });
+        if whole_word {
+            score += 5;
+        }
+        if positions[0] == 0 {
+            score += 3;
+        }
+    }
+    if terms.len() > 1 {
+        let phrase: Vec<char> = terms.join(&' ');
+        if !find_all(&document.folded, &phrase).is_empty() {
+            score += 20;
+        }
+    }
+    Some(score)
+}
+
+fn is_word_char(char: char) -> bool {
+    char.is_alphanumeric() && !is_cjk(char)
+}
+
+fn snippet(document: &Document, terms: &[Vec<char>]) -> String {
+    let original: Vec<char> = document.text.chars().collect();
+    let position = terms
+        .iter()
+        .filter_map(|term| find_all(&document.folded, term).first().copied())
+        .min()
+        .unwrap_or(0);
+    let start = position.saturating_sub(SNIPPET_BEFORE);
+    let end = (position + SNIPPET_AFTER).min(original.len());
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push('…');
+    }
+    snippet.extend(original[start..end].iter().map(
+        |&char| {
+            if char.is_whitespace() {
+                ' '
+            } else {
+                char
+            }
+        },
+    ));
+    if end < original.len() {
+        snippet.push('…');
+    }
+    snippet
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fold, SearchIndex, SearchKind, SearchQuery};
+    use crate::model::{Mark, Todo, TodoStatus};
+    use crate::page::{paginate, PageRequest};
+
+    fn mark(id: &str, timer_id: &str, minute: i64, description: &str) -> Mark {
+        Mark {
+            id: id.to_string(),
+            timer_id: timer_id.to_string(),
+            marked_at_minute: minute,
+            prev_marked_at_minute: None,
+            duration_minutes: None,
+            description: description.to_string(),
+            todo_ids: vec![],
+            tags: vec![],
+            category_id: None,
+        }
+    }
+
+    fn query(text: &str) -> SearchQuery {
+        SearchQuery {
+            text: text.to_string(),
+            ..SearchQuery::default()
+        }
+    }
+
+    #[test]
+    fn folds_case_diacritics_and_fullwidth_forms() {
+        assert_eq!(fold("Café ÅNGSTRÖM"), fold("cafe angstrom"));
+        assert_eq!(fold("ＬＯＧＩＮ"), fold("login"));
+        assert_eq!(fold("修复登录").len(), 4);
+    }
+
+    #[test]
+    fn ranks_matches_and_builds_snippets() {
+        let todos = vec![Todo {
+            id: "todo-4".to_string(),
+            timer_id: "timer-2".to_string(),
+            title: "Fix login bug".to_string(),
+            status: TodoStatus::Done,
+            created_at_minute: 50,
+            updated_at_minute: 60,
+            done_at_minute: Some(60),
+            tags: vec![],
+        }];
+        let marks = vec![
+            mark("mark-1", "timer-1", 100, "修复了登录页面的验证码问题"),
+            mark("mark-2", "timer-1", 200, "Reviewed the logging pipeline"),
+            mark(
+                "mark-3",
+                "timer-2",
+                300,
+                "Long day.\nFinally fixed the LOGIN bug after tracing the session cookie through three services",
+            ),
+        ];
+        let index = SearchIndex::build(&todos, &marks);
+
+        let hits = index
+            .search(&query("login bug"))
+            .expect("search should run");
+        let ids: Vec<&str> = hits.iter().map(|hit| hit.id.as_str()).collect();
+        assert_eq!(ids, vec!["mark-3", "todo-4"]);
+        assert!(hits[0].snippet.starts_with('…'));
+        assert!(hits[0].snippet.contains("LOGIN bug"));
+        assert!(!hits[1].snippet.contains('\n'));
+
+        let hits = index.search(&query("登录")).expect("search should run");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "mark-1");
+        assert_eq!(
+            index.search(&query("录")).expect("search should run").len(),
+            1
+        );
+        assert!(index
+            .search(&query("登页"))
+            .expect("search should run")
+            .is_empty());
+
+        let hits = index
+            .search(&SearchQuery {
+                status: Some(TodoStatus::Done),
+                ..query("log")
+            })
+            .expect("search should run");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].kind, SearchKind::Todo);
+
+        let hits = index
+            .search(&SearchQuery {
+                timer_id: Some("timer-1".to_string()),
+                since_minute: Some(150),
+                ..query("log")
+            })
+            .expect("search should run");
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].id, "mark-2");
+
+        assert!(index.search(&query("  ")).is_err());
+    }
+
+    #[test]
+    fn splits_query_terms_like_indexed_text() {
+        let marks = vec![
+            mark("mark-1", "timer-1", 100, "fixed login-bug in v1.2"),
+            mark("mark-2", "timer-1", 200, "loginbug v12"),
+        ];
+        let index = SearchIndex::build(&[], &marks);
+
+        for text in ["login-bug", "v1.2"] {
+            let hits = index.search(&query(text)).expect("search should run");
+            let ids: Vec<&str> = hits.iter().map(|hit| hit.id.as_str()).collect();
+            assert_eq!(ids, vec!["mark-1"], "query {text}");
+        }
+    }
+
+    #[test]
+    fn excludes_until_minute_like_pagination() {
+        let marks = vec![
+            mark("mark-1", "timer-1", 100, "log start"),
+            mark("mark-2", "timer-1", 200, "log end"),
+        ];
+        let index = SearchIndex::build(&[], &marks);
+        let window = |since, until| SearchQuery {
+            since_minute: Some(since),
+            until_minute: Some(until),
+            ..query("log")
+        };
+
+        let hits = index.search(&window(100, 200)).expect("search should run");
+        let ids: Vec<&str> = hits.iter().map(|hit| hit.id.as_str()).collect();
+        assert_eq!(ids, vec!["mark-1"]);
+        let page = paginate(
+            &marks,
+            |mark| (mark.marked_at_minute, mark.id.as_str()),
+            &PageRequest {
+                since_minute: Some(100),
+                until_minute: Some(200),
+                ..PageRequest::default()
+            },
+        )
+        .expect("page should load");
+        let paged: Vec<&str> = page.items.iter().map(|mark| mark.id.as_str()).collect();
+        assert_eq!(paged, ids);
+
+        assert_eq!(
+            index.search(&window(200, 201)).expect("search should run")[0].id,
+            "mark-2"
+        );
+    }
+}
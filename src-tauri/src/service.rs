@@ -1,52 +1,276 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 use std::sync::atomic::{AtomicU64, Ordering};
 
+use crate::backup::BackupInfo;
+use crate::dataset::{self, DataImportReport, ImportMode, RemappedId};
 use crate::error::{AppError, AppResult};
-use crate::model::{EpochMinutes, Mark, Timer, Todo, TodoStatus};
-use crate::repository::Store;
+use crate::event::{self, DomainEvent, EventKind, EventSubscriber, RebuildSummary};
+use crate::history::{Change, Entity, History, HistoryStatus, HistoryStep};
+use crate::ics;
+use crate::merge::{self, MergeReport};
+use crate::model::{
+    Category, EpochMinutes, Mark, MarkTemplate, Project, ProjectSummary, TagUsage, Timer, Todo,
+    TodoStatus,
+};
+use crate::page::{Page, PageRequest};
+use crate::query::{self, QueryContext, QueryResults};
+use crate::reminder::{self, FiredReminder, ReminderEvent, ReminderPolicy};
+use crate::report::{self, CategoryReport};
+use crate::repository::{Store, StoreContents};
+use crate::search::{SearchHit, SearchIndex, SearchQuery};
+use crate::stats::{self, DailyStats, TimerActivity};
+use crate::sync::SyncReport;
+use crate::tag;
+use crate::taskwarrior::{self, TaskImportReport};
+use crate::template::{self, TemplateContext};
+use crate::timeline::{self, TimelineEntry, TimelineSource};
+use crate::timesheet::{self, SkippedRow, TimesheetReport};
+use crate::timewarrior;
+use crate::todotxt;
+use crate::worklog;
+
+const MINUTES_PER_WEEK: EpochMinutes = 7 * 24 * 60;
 
 #[derive(Debug)]
 struct IdGenerator {
     sequence: AtomicU64,
+    scope: Option<String>,
 }
 
 impl Default for IdGenerator {
     fn default() -> Self {
-        Self::new(1)
+        Self::new(1, None)
     }
 }
 
 impl IdGenerator {
-    fn new(initial_sequence: u64) -> Self {
+    fn new(initial_sequence: u64, scope: Option<String>) -> Self {
         Self {
             sequence: AtomicU64::new(initial_sequence.max(1)),
+            scope,
         }
     }
 
     fn next(&self, prefix: &str) -> String {
         let number = self.sequence.fetch_add(1, Ordering::SeqCst);
-        format!("{prefix}-{number}")
+        match &self.scope {
+            Some(scope) => format!("{prefix}-{scope}-{number}"),
+            None => format!("{prefix}-{number}"),
+        }
+    }
+
+    fn advance_to(&self, sequence: u64) {
+        self.sequence.fetch_max(sequence, Ordering::SeqCst);
+    }
+}
+
+#[derive(Default)]
+struct ImportBatch {
+    changes: Vec<Change>,
+    events: Vec<DomainEvent>,
+    created_timers: Vec<Timer>,
+    marks_by_timer: HashMap<String, Vec<Mark>>,
+}
+
+impl ImportBatch {
+    fn created(&mut self, kind: EventKind, entity: Entity, at_minute: Option<EpochMinutes>) {
+        self.events
+            .push(DomainEvent::saved(kind, entity.clone(), at_minute));
+        self.changes.push(Change {
+            before: None,
+            after: Some(entity),
+        });
+    }
+
+    fn updated(&mut self, kind: EventKind, before: Entity, after: Entity) {
+        self.events
+            .push(DomainEvent::saved(kind, after.clone(), None));
+        self.changes.push(Change::updated(before, after));
     }
 }
 
 pub struct AppService<S: Store> {
     store: S,
     ids: IdGenerator,
+    history: History,
+    next_event_sequence: u64,
+    subscribers: Vec<Box<dyn EventSubscriber>>,
+    search_index: SearchIndex,
+    session_reminders: Vec<FiredReminder>,
 }
 
 impl<S: Store> AppService<S> {
     pub fn new(store: S) -> Self {
         let initial_sequence = Self::next_sequence_from_store(&store);
+        let id_scope = store.id_scope();
 
-        Self {
+        let last_event_sequence = store.list_events().last().map(|event| event.sequence);
+
+        let mut service = Self {
             store,
-            ids: IdGenerator::new(initial_sequence),
+            ids: IdGenerator::new(initial_sequence, id_scope),
+            history: History::default(),
+            next_event_sequence: last_event_sequence.map_or(1, |sequence| sequence + 1),
+            subscribers: Vec::new(),
+            search_index: SearchIndex::default(),
+            session_reminders: Vec::new(),
+        };
+        service.rebuild_search_index();
+        service
+    }
+
+    /// Like `new`, but first records any edits made to the data while the app
+    /// was closed, so a later rebuild from events keeps them.
+    pub fn open(store: S) -> AppResult<Self> {
+        let mut service = Self::new(store);
+        // A read-only store cannot append to the log; its writer reconciles it.
+        if !service.store.is_read_only() {
+            service.reconcile_event_log(EventKind::Imported)?;
         }
+        Ok(service)
+    }
+
+    pub fn subscribe(&mut self, subscriber: Box<dyn EventSubscriber>) {
+        self.subscribers.push(subscriber);
     }
 
     pub fn into_store(self) -> S {
         self.store
     }
 
+    pub fn list_backups(&self) -> AppResult<Vec<BackupInfo>> {
+        self.store.list_backups()
+    }
+
+    pub fn create_backup(&mut self) -> AppResult<BackupInfo> {
+        self.store.create_backup()
+    }
+
+    pub fn restore_backup(&mut self, backup_id: &str) -> AppResult<BackupInfo> {
+        let info = self.store.restore_backup(backup_id)?;
+        self.history.clear();
+        self.reconcile_event_log(EventKind::BackupRestored)?;
+        self.ids
+            .advance_to(Self::next_sequence_from_store(&self.store));
+        Ok(info)
+    }
+
+    pub fn sync(&mut self) -> AppResult<SyncReport> {
+        let report = self.store.refresh()?;
+        if report.changed {
+            self.history.clear();
+            self.reconcile_event_log(EventKind::Imported)?;
+            self.rebuild_search_index();
+            self.ids
+                .advance_to(Self::next_sequence_from_store(&self.store));
+        }
+        Ok(report)
+    }
+
+    pub fn history_status(&self) -> HistoryStatus {
+        self.history.status()
+    }
+
+    pub fn entity_history(
+        &self,
+        entity_type: &str,
+        entity_id: &str,
+    ) -> AppResult<Vec<DomainEvent>> {
+        event::validate_entity_type(entity_type)?;
+        Ok(self
+            .store
+            .list_events()
+            .into_iter()
+            .filter(|event| event.entity_type == entity_type && event.entity_id == entity_id)
+            .collect())
+    }
+
+    pub fn rebuild_from_events(&mut self) -> AppResult<RebuildSummary> {
+        let events = self.store.list_events();
+        let state = event::replay(&events);
+        let contents = StoreContents {
+            timers: state.timers.into_values().collect(),
+            projects: state.projects.into_values().collect(),
+            categories: state.categories.into_values().collect(),
+            templates: state.templates.into_values().collect(),
+            todos: state.todos.into_values().collect(),
+            marks: state.marks.into_values().collect(),
+        };
+        let summary = RebuildSummary {
+            event_count: events.len(),
+            timer_count: contents.timers.len(),
+            project_count: contents.projects.len(),
+            category_count: contents.categories.len(),
+            template_count: contents.templates.len(),
+            todo_count: contents.todos.len(),
+            mark_count: contents.marks.len(),
+        };
+
+        self.store.replace_contents(contents)?;
+        self.rebuild_search_index();
+        self.history.clear();
+        self.ids
+            .advance_to(Self::next_sequence_from_store(&self.store));
+        Ok(summary)
+    }
+
+    pub fn undo(&mut self) -> AppResult<HistoryStep> {
+        let operation = self
+            .history
+            .take_undo()
+            .ok_or_else(|| AppError::Validation("nothing to undo".to_string()))?;
+        let changes: Vec<Change> = operation
+            .changes
+            .iter()
+            .rev()
+            .map(Change::reversed)
+            .collect();
+        // One batch write; a rejected save keeps the step on the undo stack.
+        if let Err(error) = self.store.apply_changes(changes.clone()) {
+            self.history.push_redone(operation);
+            return Err(error);
+        }
+        let events = changes
+            .iter()
+            .filter_map(|change| {
+                state_event(
+                    EventKind::Undone,
+                    change.before.as_ref(),
+                    change.after.as_ref(),
+                )
+            })
+            .collect();
+        self.history.push_undone(operation.clone());
+        self.emit(events)?;
+        Ok(self.history.step(&operation))
+    }
+
+    pub fn redo(&mut self) -> AppResult<HistoryStep> {
+        let operation = self
+            .history
+            .take_redo()
+            .ok_or_else(|| AppError::Validation("nothing to redo".to_string()))?;
+        if let Err(error) = self.store.apply_changes(operation.changes.clone()) {
+            self.history.push_undone(operation);
+            return Err(error);
+        }
+        let events = operation
+            .changes
+            .iter()
+            .filter_map(|change| {
+                state_event(
+                    EventKind::Redone,
+                    change.before.as_ref(),
+                    change.after.as_ref(),
+                )
+            })
+            .collect();
+        self.history.push_redone(operation.clone());
+        self.emit(events)?;
+        Ok(self.history.step(&operation))
+    }
+
     pub fn create_timer(
         &mut self,
         name: impl Into<String>,
@@ -67,9 +291,15 @@ impl<S: Store> AppService<S> {
             created_at_minute: now_minute,
             updated_at_minute: now_minute,
             archived: false,
+            project_id: None,
+            tags: Vec::new(),
         };
 
-        self.store.save_timer(timer.clone())?;
+        self.save_recorded(
+            EventKind::TimerCreated,
+            Entity::Timer(timer.clone()),
+            Some(now_minute),
+        )?;
         Ok(timer)
     }
 
@@ -96,7 +326,11 @@ impl<S: Store> AppService<S> {
         timer.target_at_minute = target_at_minute;
         timer.updated_at_minute = now_minute;
 
-        self.store.save_timer(timer.clone())?;
+        self.save_recorded(
+            EventKind::TimerUpdated,
+            Entity::Timer(timer.clone()),
+            Some(now_minute),
+        )?;
         Ok(timer)
     }
 
@@ -107,7 +341,75 @@ impl<S: Store> AppService<S> {
             .ok_or_else(|| AppError::NotFound(format!("timer {timer_id}")))?;
         timer.archived = true;
         timer.updated_at_minute = now_minute;
-        self.store.save_timer(timer.clone())?;
+        self.save_recorded(
+            EventKind::TimerArchived,
+            Entity::Timer(timer.clone()),
+            Some(now_minute),
+        )?;
+        Ok(timer)
+    }
+
+    pub fn unarchive_timer(
+        &mut self,
+        timer_id: &str,
+        now_minute: EpochMinutes,
+    ) -> AppResult<Timer> {
+        let mut timer = self
+            .store
+            .get_timer(timer_id)
+            .ok_or_else(|| AppError::NotFound(format!("timer {timer_id}")))?;
+        timer.archived = false;
+        timer.updated_at_minute = now_minute;
+        self.save_recorded(
+            EventKind::TimerUnarchived,
+            Entity::Timer(timer.clone()),
+            Some(now_minute),
+        )?;
+        Ok(timer)
+    }
+
+    pub fn delete_timer(&mut self, timer_id: &str, cascade: bool) -> AppResult<Timer> {
+        let timer = self
+            .store
+            .get_timer(timer_id)
+            .ok_or_else(|| AppError::NotFound(format!("timer {timer_id}")))?;
+
+        let todos = self.store.list_todos_by_timer(timer_id);
+        let marks = self.store.list_marks_by_timer(timer_id);
+        if !cascade && (!todos.is_empty() || !marks.is_empty()) {
+            return Err(AppError::Conflict(format!(
+                "timer {timer_id} still has {} todos and {} marks",
+                todos.len(),
+                marks.len()
+            )));
+        }
+
+        self.store.delete_timer(timer_id)?;
+        let mut changes: Vec<Change> = marks
+            .into_iter()
+            .map(|mark| Change::deleted(Entity::Mark(mark)))
+            .collect();
+        changes.extend(
+            todos
+                .into_iter()
+                .map(|todo| Change::deleted(Entity::Todo(todo))),
+        );
+        changes.push(Change::deleted(Entity::Timer(timer.clone())));
+        let events = changes
+            .iter()
+            .filter_map(|change| change.before.as_ref())
+            .map(|entity| {
+                let kind = match entity {
+                    Entity::Mark(_) => EventKind::MarkDeleted,
+                    Entity::Todo(_) => EventKind::TodoDeleted,
+                    _ => EventKind::TimerDeleted,
+                };
+                DomainEvent::deleted(kind, entity, None)
+            })
+            .collect();
+        self.history
+            .record(EventKind::TimerDeleted.label(), changes);
+        self.emit(events)?;
         Ok(timer)
     }
 
@@ -115,6 +417,193 @@ impl<S: Store> AppService<S> {
         self.store.list_timers(include_archived)
     }
 
+    pub fn poll_reminders(
+        &mut self,
+        now_minute: EpochMinutes,
+        policy: &ReminderPolicy,
+    ) -> AppResult<Vec<ReminderEvent>> {
+        let mut fired = self.store.list_fired_reminders();
+        fired.extend(self.session_reminders.iter().cloned());
+        let plan =
+            reminder::plan_reminders(&self.store.list_timers(false), &fired, policy, now_minute);
+        if self.store.is_read_only() {
+            // Still notify, but only remember what fired for this session.
+            self.session_reminders.extend(plan.fired);
+        } else {
+            self.store.record_fired_reminders(plan.fired)?;
+        }
+        Ok(plan.events)
+    }
+
+    pub fn set_timer_project(
+        &mut self,
+        timer_id: &str,
+        project_id: Option<&str>,
+        now_minute: EpochMinutes,
+    ) -> AppResult<Timer> {
+        let mut timer = self
+            .store
+            .get_timer(timer_id)
+            .ok_or_else(|| AppError::NotFound(format!("timer {timer_id}")))?;
+        if let Some(project_id) = project_id {
+            self.ensure_project_exists(project_id)?;
+        }
+
+        timer.project_id = project_id.map(str::to_string);
+        timer.updated_at_minute = now_minute;
+        self.save_recorded(
+            EventKind::TimerProjectChanged,
+            Entity::Timer(timer.clone()),
+            Some(now_minute),
+        )?;
+        Ok(timer)
+    }
+
+    pub fn list_timers_by_project(
+        &self,
+        project_id: Option<&str>,
+        include_archived: bool,
+    ) -> AppResult<Vec<Timer>> {
+        if let Some(project_id) = project_id {
+            self.ensure_project_exists(project_id)?;
+        }
+
+        Ok(self
+            .store
+            .list_timers(include_archived)
+            .into_iter()
+            .filter(|timer| timer.project_id.as_deref() == project_id)
+            .collect())
+    }
+
+    pub fn create_project(
+        &mut self,
+        name: impl Into<String>,
+        now_minute: EpochMinutes,
+    ) -> AppResult<Project> {
+        let name = name.into().trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::Validation(
+                "project name cannot be empty".to_string(),
+            ));
+        }
+
+        let project = Project {
+            id: self.ids.next("project"),
+            name,
+            created_at_minute: now_minute,
+            updated_at_minute: now_minute,
+        };
+
+        self.save_recorded(
+            EventKind::ProjectCreated,
+            Entity::Project(project.clone()),
+            Some(now_minute),
+        )?;
+        Ok(project)
+    }
+
+    pub fn rename_project(
+        &mut self,
+        project_id: &str,
+        name: impl Into<String>,
+        now_minute: EpochMinutes,
+    ) -> AppResult<Project> {
+        let mut project = self
+            .store
+            .get_project(project_id)
+            .ok_or_else(|| AppError::NotFound(format!("project {project_id}")))?;
+
+        let name = name.into().trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::Validation(
+                "project name cannot be empty".to_string(),
+            ));
+        }
+
+        project.name = name;
+        project.updated_at_minute = now_minute;
+        self.save_recorded(
+            EventKind::ProjectRenamed,
+            Entity::Project(project.clone()),
+            Some(now_minute),
+        )?;
+        Ok(project)
+    }
+
+    pub fn delete_project(&mut self, project_id: &str) -> AppResult<Project> {
+        let project = self
+            .store
+            .get_project(project_id)
+            .ok_or_else(|| AppError::NotFound(format!("project {project_id}")))?;
+
+        let timer_count = self
+            .store
+            .list_timers(true)
+            .iter()
+            .filter(|timer| timer.project_id.as_deref() == Some(project_id))
+            .count();
+        if timer_count > 0 {
+            return Err(AppError::Conflict(format!(
+                "project {project_id} still has {timer_count} timers"
+            )));
+        }
+
+        self.delete_recorded(
+            EventKind::ProjectDeleted,
+            Entity::Project(project.clone()),
+            None,
+        )?;
+        Ok(project)
+    }
+
+    pub fn list_projects(&self) -> Vec<Project> {
+        self.store.list_projects()
+    }
+
+    pub fn list_project_summaries(&self, now_minute: EpochMinutes) -> Vec<ProjectSummary> {
+        let timers = self.store.list_timers(true);
+        let week_start_minute = now_minute - MINUTES_PER_WEEK;
+
+        self.store
+            .list_projects()
+            .into_iter()
+            .map(|project| {
+                let project_timers: Vec<&Timer> = timers
+                    .iter()
+                    .filter(|timer| timer.project_id.as_deref() == Some(project.id.as_str()))
+                    .collect();
+
+                let mut open_todo_count = 0;
+                let mut marks_this_week = 0;
+                for timer in &project_timers {
+                    open_todo_count += self
+                        .store
+                        .list_todos_by_timer(&timer.id)
+                        .iter()
+                        .filter(|todo| matches!(todo.status, TodoStatus::Open))
+                        .count();
+                    marks_this_week += self
+                        .store
+                        .list_marks_by_timer(&timer.id)
+                        .iter()
+                        .filter(|mark| {
+                            mark.marked_at_minute > week_start_minute
+                                && mark.marked_at_minute <= now_minute
+                        })
+                        .count();
+                }
+
+                ProjectSummary {
+                    project,
+                    timer_count: project_timers.len(),
+                    open_todo_count,
+                    marks_this_week,
+                }
+            })
+            .collect()
+    }
+
     pub fn create_todo(
         &mut self,
         timer_id: &str,
@@ -130,6 +619,7 @@ impl<S: Store> AppService<S> {
             ));
         }
 
+        let tags = tag::parse_tags(&title);
         let todo = Todo {
             id: self.ids.next("todo"),
             timer_id: timer_id.to_string(),
@@ -138,8 +628,13 @@ impl<S: Store> AppService<S> {
             created_at_minute: now_minute,
             updated_at_minute: now_minute,
             done_at_minute: None,
+            tags,
         };
-        self.store.save_todo(todo.clone())?;
+        self.save_recorded(
+            EventKind::TodoCreated,
+            Entity::Todo(todo.clone()),
+            Some(now_minute),
+        )?;
         Ok(todo)
     }
 
@@ -162,7 +657,11 @@ impl<S: Store> AppService<S> {
             None
         };
 
-        self.store.save_todo(todo.clone())?;
+        self.save_recorded(
+            EventKind::TodoStatusChanged,
+            Entity::Todo(todo.clone()),
+            Some(now_minute),
+        )?;
         Ok(todo)
     }
 
@@ -172,7 +671,7 @@ impl<S: Store> AppService<S> {
             .get_todo(todo_id)
             .ok_or_else(|| AppError::NotFound(format!("todo {todo_id}")))?;
 
-        self.store.delete_todo(todo_id)?;
+        self.delete_recorded(EventKind::TodoDeleted, Entity::Todo(todo.clone()), None)?;
         Ok(todo)
     }
 
@@ -181,22 +680,52 @@ impl<S: Store> AppService<S> {
         Ok(self.store.list_todos_by_timer(timer_id))
     }
 
+    pub fn page_todos_by_timer(
+        &self,
+        timer_id: &str,
+        request: &PageRequest,
+    ) -> AppResult<Page<Todo>> {
+        self.ensure_timer_exists(timer_id)?;
+        self.store.page_todos_by_timer(timer_id, request)
+    }
+
     pub fn create_mark(
         &mut self,
         timer_id: &str,
         marked_at_minute: EpochMinutes,
         description: impl Into<String>,
         todo_ids: Vec<String>,
+    ) -> AppResult<Mark> {
+        self.create_mark_with_details(
+            timer_id,
+            marked_at_minute,
+            description,
+            todo_ids,
+            Vec::new(),
+            None,
+        )
+    }
+
+    pub fn create_mark_with_details(
+        &mut self,
+        timer_id: &str,
+        marked_at_minute: EpochMinutes,
+        description: impl Into<String>,
+        todo_ids: Vec<String>,
+        tags: Vec<String>,
+        category_id: Option<String>,
     ) -> AppResult<Mark> {
         self.ensure_timer_exists(timer_id)?;
+        if let Some(category_id) = &category_id {
+            self.ensure_category_exists(category_id)?;
+        }
+        let description = description.into();
+        let tags = tag::merge_tags(&tag::normalize_tags(&tags)?, &tag::parse_tags(&description));
 
-        let previous_mark = self
+        let prev_marked_at_minute = self
             .store
-            .list_marks_by_timer(timer_id)
-            .into_iter()
-            .max_by_key(|mark| mark.marked_at_minute);
-
-        let prev_marked_at_minute = previous_mark.as_ref().map(|mark| mark.marked_at_minute);
+            .latest_mark_by_timer(timer_id)
+            .map(|mark| mark.marked_at_minute);
         let duration_minutes = prev_marked_at_minute.map(|prev| marked_at_minute - prev);
 
         let mark = Mark {
@@ -205,11 +734,17 @@ impl<S: Store> AppService<S> {
             marked_at_minute,
             prev_marked_at_minute,
             duration_minutes,
-            description: description.into(),
+            description,
             todo_ids,
+            tags,
+            category_id,
         };
 
-        self.store.append_mark(mark.clone())?;
+        self.save_recorded(
+            EventKind::MarkAppended,
+            Entity::Mark(mark.clone()),
+            Some(marked_at_minute),
+        )?;
         Ok(mark)
     }
 
@@ -218,78 +753,1480 @@ impl<S: Store> AppService<S> {
         Ok(self.store.list_marks_by_timer(timer_id))
     }
 
-    fn ensure_timer_exists(&self, timer_id: &str) -> AppResult<()> {
-        if self.store.get_timer(timer_id).is_none() {
-            return Err(AppError::NotFound(format!("timer {timer_id}")));
-        }
-        Ok(())
+    pub fn page_marks_by_timer(
+        &self,
+        timer_id: &str,
+        request: &PageRequest,
+    ) -> AppResult<Page<Mark>> {
+        self.ensure_timer_exists(timer_id)?;
+        self.store.page_marks_by_timer(timer_id, request)
     }
 
-    fn next_sequence_from_store(store: &S) -> u64 {
-        let timers = store.list_timers(true);
-        let mut max_sequence = 0;
+    pub fn create_mark_from_template(
+        &mut self,
+        timer_id: &str,
+        template_id: &str,
+        marked_at_minute: EpochMinutes,
+    ) -> AppResult<Mark> {
+        let timer = self
+            .store
+            .get_timer(timer_id)
+            .ok_or_else(|| AppError::NotFound(format!("timer {timer_id}")))?;
+        let template = self
+            .store
+            .get_template(template_id)
+            .ok_or_else(|| AppError::NotFound(format!("template {template_id}")))?;
 
-        for timer in &timers {
-            max_sequence = max_sequence.max(id_sequence_number(&timer.id).unwrap_or(0));
+        let prev_marked_at_minute = self
+            .store
+            .latest_mark_by_timer(timer_id)
+            .map(|mark| mark.marked_at_minute);
+        let (open_todos, done_todos): (Vec<Todo>, Vec<Todo>) = self
+            .store
+            .list_todos_by_timer(timer_id)
+            .into_iter()
+            .partition(|todo| matches!(todo.status, TodoStatus::Open));
+        let done_since_last_mark: Vec<Todo> = done_todos
+            .into_iter()
+            .filter(|todo| {
+                todo.done_at_minute.is_some_and(|done_at| {
+                    prev_marked_at_minute.is_none_or(|prev| done_at > prev)
+                        && done_at <= marked_at_minute
+                })
+            })
+            .collect();
 
-            for todo in store.list_todos_by_timer(&timer.id) {
-                max_sequence = max_sequence.max(id_sequence_number(&todo.id).unwrap_or(0));
-            }
+        let rendered = template::render_template(
+            &template.body,
+            &TemplateContext {
+                timer_name: &timer.name,
+                duration_minutes: prev_marked_at_minute.map(|prev| marked_at_minute - prev),
+                open_todos: &open_todos,
+                done_since_last_mark: &done_since_last_mark,
+            },
+        )?;
 
-            for mark in store.list_marks_by_timer(&timer.id) {
-                max_sequence = max_sequence.max(id_sequence_number(&mark.id).unwrap_or(0));
-            }
+        self.create_mark_with_details(
+            timer_id,
+            marked_at_minute,
+            rendered.description,
+            rendered.todo_ids,
+            Vec::new(),
+            None,
+        )
+    }
+
+    pub fn create_template(
+        &mut self,
+        name: impl Into<String>,
+        body: impl Into<String>,
+        now_minute: EpochMinutes,
+    ) -> AppResult<MarkTemplate> {
+        let name = name.into().trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::Validation(
+                "template name cannot be empty".to_string(),
+            ));
         }
+        let body = body.into();
+        template::validate_template(&body)?;
 
-        max_sequence.saturating_add(1).max(1)
+        let template = MarkTemplate {
+            id: self.ids.next("template"),
+            name,
+            body,
+            created_at_minute: now_minute,
+            updated_at_minute: now_minute,
+        };
+
+        self.save_recorded(
+            EventKind::TemplateCreated,
+            Entity::Template(template.clone()),
+            Some(now_minute),
+        )?;
+        Ok(template)
     }
-}
 
-fn id_sequence_number(id: &str) -> Option<u64> {
-    let (_, suffix) = id.rsplit_once('-')?;
-    suffix.parse::<u64>().ok()
-}
+    pub fn update_template(
+        &mut self,
+        template_id: &str,
+        name: impl Into<String>,
+        body: impl Into<String>,
+        now_minute: EpochMinutes,
+    ) -> AppResult<MarkTemplate> {
+        let mut template = self
+            .store
+            .get_template(template_id)
+            .ok_or_else(|| AppError::NotFound(format!("template {template_id}")))?;
 
-#[cfg(test)]
-mod tests {
-    use crate::model::TodoStatus;
-    use crate::repository::InMemoryStore;
+        let name = name.into().trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::Validation(
+                "template name cannot be empty".to_string(),
+            ));
+        }
+        let body = body.into();
+        template::validate_template(&body)?;
 
-    use super::AppService;
+        template.name = name;
+        template.body = body;
+        template.updated_at_minute = now_minute;
+        self.save_recorded(
+            EventKind::TemplateUpdated,
+            Entity::Template(template.clone()),
+            Some(now_minute),
+        )?;
+        Ok(template)
+    }
 
-    #[test]
-    fn calculates_timer_remaining_minutes() {
-        let mut service = AppService::new(InMemoryStore::default());
-        let timer = service
-            .create_timer("vacation", 200, 100)
-            .expect("timer should be created");
+    pub fn delete_template(&mut self, template_id: &str) -> AppResult<MarkTemplate> {
+        let template = self
+            .store
+            .get_template(template_id)
+            .ok_or_else(|| AppError::NotFound(format!("template {template_id}")))?;
 
-        assert_eq!(timer.remaining_minutes(160), 40);
-        assert_eq!(timer.remaining_minutes(250), -50);
+        self.delete_recorded(
+            EventKind::TemplateDeleted,
+            Entity::Template(template.clone()),
+            None,
+        )?;
+        Ok(template)
     }
 
-    #[test]
-    fn creates_mark_chain_with_duration() {
-        let mut service = AppService::new(InMemoryStore::default());
-        let timer = service
-            .create_timer("study", 300, 100)
-            .expect("timer should be created");
+    pub fn list_templates(&self) -> Vec<MarkTemplate> {
+        self.store.list_templates()
+    }
 
-        let first_mark = service
-            .create_mark(&timer.id, 120, "wrote architecture", vec![])
-            .expect("first mark should be created");
-        assert_eq!(first_mark.prev_marked_at_minute, None);
-        assert_eq!(first_mark.duration_minutes, None);
+    pub fn set_mark_category(
+        &mut self,
+        mark_id: &str,
+        category_id: Option<&str>,
+    ) -> AppResult<Mark> {
+        let mut mark = self
+            .store
+            .get_mark(mark_id)
+            .ok_or_else(|| AppError::NotFound(format!("mark {mark_id}")))?;
+        if let Some(category_id) = category_id {
+            self.ensure_category_exists(category_id)?;
+        }
 
-        let second_mark = service
-            .create_mark(&timer.id, 170, "built service", vec![])
-            .expect("second mark should be created");
-        assert_eq!(second_mark.prev_marked_at_minute, Some(120));
-        assert_eq!(second_mark.duration_minutes, Some(50));
+        mark.category_id = category_id.map(str::to_string);
+        self.save_recorded(
+            EventKind::MarkCategoryChanged,
+            Entity::Mark(mark.clone()),
+            None,
+        )?;
+        Ok(mark)
     }
 
-    #[test]
-    fn updates_todo_status_and_done_timestamp() {
+    pub fn create_category(
+        &mut self,
+        name: impl Into<String>,
+        now_minute: EpochMinutes,
+    ) -> AppResult<Category> {
+        let name = name.into().trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::Validation(
+                "category name cannot be empty".to_string(),
+            ));
+        }
+        if self
+            .store
+            .list_categories()
+            .iter()
+            .any(|category| category.name.eq_ignore_ascii_case(&name))
+        {
+            return Err(AppError::Conflict(format!(
+                "category '{name}' already exists"
+            )));
+        }
+
+        let category = Category {
+            id: self.ids.next("category"),
+            name,
+            created_at_minute: now_minute,
+            updated_at_minute: now_minute,
+        };
+
+        self.save_recorded(
+            EventKind::CategoryCreated,
+            Entity::Category(category.clone()),
+            Some(now_minute),
+        )?;
+        Ok(category)
+    }
+
+    pub fn rename_category(
+        &mut self,
+        category_id: &str,
+        name: impl Into<String>,
+        now_minute: EpochMinutes,
+    ) -> AppResult<Category> {
+        let mut category = self
+            .store
+            .get_category(category_id)
+            .ok_or_else(|| AppError::NotFound(format!("category {category_id}")))?;
+
+        let name = name.into().trim().to_string();
+        if name.is_empty() {
+            return Err(AppError::Validation(
+                "category name cannot be empty".to_string(),
+            ));
+        }
+        if self
+            .store
+            .list_categories()
+            .iter()
+            .any(|other| other.id != category_id && other.name.eq_ignore_ascii_case(&name))
+        {
+            return Err(AppError::Conflict(format!(
+                "category '{name}' already exists"
+            )));
+        }
+
+        category.name = name;
+        category.updated_at_minute = now_minute;
+        self.save_recorded(
+            EventKind::CategoryRenamed,
+            Entity::Category(category.clone()),
+            Some(now_minute),
+        )?;
+        Ok(category)
+    }
+
+    pub fn delete_category(&mut self, category_id: &str) -> AppResult<Category> {
+        let category = self
+            .store
+            .get_category(category_id)
+            .ok_or_else(|| AppError::NotFound(format!("category {category_id}")))?;
+
+        let mark_count = self
+            .all_marks()
+            .iter()
+            .filter(|mark| mark.category_id.as_deref() == Some(category_id))
+            .count();
+        if mark_count > 0 {
+            return Err(AppError::Conflict(format!(
+                "category {category_id} is still used by {mark_count} marks"
+            )));
+        }
+
+        self.delete_recorded(
+            EventKind::CategoryDeleted,
+            Entity::Category(category.clone()),
+            None,
+        )?;
+        Ok(category)
+    }
+
+    pub fn list_categories(&self) -> Vec<Category> {
+        self.store.list_categories()
+    }
+
+    pub fn category_report(
+        &self,
+        timer_id: Option<&str>,
+        from_minute: EpochMinutes,
+        to_minute: EpochMinutes,
+        utc_offset_minutes: EpochMinutes,
+    ) -> AppResult<CategoryReport> {
+        if to_minute < from_minute {
+            return Err(AppError::Validation(
+                "report range end must not be before its start".to_string(),
+            ));
+        }
+
+        let timers = match timer_id {
+            Some(timer_id) => vec![self
+                .store
+                .get_timer(timer_id)
+                .ok_or_else(|| AppError::NotFound(format!("timer {timer_id}")))?],
+            None => self.store.list_timers(true),
+        };
+        let marks: Vec<Mark> = timers
+            .iter()
+            .flat_map(|timer| self.store.list_marks_by_timer(&timer.id))
+            .collect();
+
+        Ok(report::category_report(
+            &timers,
+            &marks,
+            &self.store.list_categories(),
+            from_minute,
+            to_minute,
+            utc_offset_minutes,
+        ))
+    }
+
+    pub fn daily_stats(
+        &self,
+        from_date: &str,
+        to_date: &str,
+        utc_offset_minutes: EpochMinutes,
+    ) -> AppResult<DailyStats> {
+        let timers = self.store.list_timers(true);
+        let marks: Vec<Vec<Mark>> = timers
+            .iter()
+            .map(|timer| self.store.list_marks_by_timer(&timer.id))
+            .collect();
+        let todos: Vec<Vec<Todo>> = timers
+            .iter()
+            .map(|timer| self.store.list_todos_by_timer(&timer.id))
+            .collect();
+        let activities: Vec<TimerActivity<'_>> = timers
+            .iter()
+            .zip(marks.iter().zip(todos.iter()))
+            .map(|(timer, (marks, todos))| TimerActivity {
+                timer,
+                marks,
+                todos,
+            })
+            .collect();
+
+        stats::daily_stats(&activities, from_date, to_date, utc_offset_minutes)
+    }
+
+    pub fn export_markdown(
+        &self,
+        timer_id: Option<&str>,
+        from_date: &str,
+        to_date: &str,
+        utc_offset_minutes: EpochMinutes,
+    ) -> AppResult<String> {
+        let (timers, marks, todos) = self.load_activity(timer_id, true)?;
+        let activities = activity_view(&timers, &marks, &todos);
+        worklog::render_markdown(&activities, from_date, to_date, utc_offset_minutes)
+    }
+
+    pub fn export_ics(
+        &self,
+        timer_id: Option<&str>,
+        include_archived: bool,
+        policy: &ReminderPolicy,
+    ) -> AppResult<String> {
+        let (timers, marks, todos) = self.load_activity(timer_id, include_archived)?;
+        let activities = activity_view(&timers, &marks, &todos);
+        Ok(ics::render_calendar(&activities, policy))
+    }
+
+    pub fn import_todotxt(
+        &mut self,
+        timer_id: &str,
+        text: &str,
+        utc_offset_minutes: EpochMinutes,
+        now_minute: EpochMinutes,
+    ) -> AppResult<Vec<Todo>> {
+        self.ensure_timer_exists(timer_id)?;
+        let entries = todotxt::parse(text, utc_offset_minutes)?;
+
+        let mut batch = ImportBatch::default();
+        let mut todos = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let done_at_minute = match entry.status {
+                TodoStatus::Done => Some(entry.done_at_minute.unwrap_or(now_minute)),
+                TodoStatus::Open => None,
+            };
+            let todo = Todo {
+                id: self.ids.next("todo"),
+                timer_id: timer_id.to_string(),
+                title: entry.title,
+                status: entry.status,
+                created_at_minute: entry.created_at_minute.unwrap_or(now_minute),
+                updated_at_minute: now_minute,
+                done_at_minute,
+                tags: entry.tags,
+            };
+            self.store.save_todo(todo.clone())?;
+            batch.created(
+                EventKind::TodoCreated,
+                Entity::Todo(todo.clone()),
+                Some(now_minute),
+            );
+            todos.push(todo);
+        }
+        self.commit_import("import todo.txt", batch)?;
+        Ok(todos)
+    }
+
+    pub fn import_time_entries(
+        &mut self,
+        text: &str,
+        utc_offset_minutes: EpochMinutes,
+        now_minute: EpochMinutes,
+    ) -> AppResult<TimesheetReport> {
+        let parsed = timesheet::parse(text, utc_offset_minutes)?;
+        let mut targets: HashMap<String, EpochMinutes> = HashMap::new();
+        for entry in &parsed.entries {
+            let target = targets
+                .entry(import_timer_name(&entry.project))
+                .or_default();
+            *target = (*target).max(entry.end_minute);
+        }
+
+        let mut report = TimesheetReport {
+            skipped: parsed.skipped,
+            ..TimesheetReport::default()
+        };
+        let mut timers = self.timers_by_name();
+        let mut batch = ImportBatch::default();
+        for entry in parsed.entries {
+            let name = import_timer_name(&entry.project);
+            let timer_id =
+                self.import_timer(&mut timers, &name, targets[&name], now_minute, &mut batch)?;
+            let tags = tag::merge_tags(&entry.tags, &tag::parse_tags(&entry.description));
+            match self.import_segment(
+                &mut batch,
+                &timer_id,
+                (entry.start_minute, entry.end_minute),
+                entry.description,
+                tags,
+            ) {
+                Ok(mark) => report.marks.push(mark),
+                Err(AppError::Conflict(reason)) => report.skipped.push(SkippedRow {
+                    row: entry.row,
+                    reason,
+                }),
+                Err(error) => return Err(error),
+            }
+        }
+
+        report.skipped.sort_by_key(|skipped| skipped.row);
+        report.created_timers = batch.created_timers.clone();
+        self.commit_import("import time entries", batch)?;
+        Ok(report)
+    }
+
+    pub fn export_data(&self, now_minute: EpochMinutes) -> AppResult<String> {
+        dataset::render(self.current_contents(), now_minute)
+    }
+
+    pub fn import_data(&mut self, text: &str, mode: ImportMode) -> AppResult<DataImportReport> {
+        let contents = dataset::parse(text)?;
+        match mode {
+            ImportMode::Replace => self.replace_data(contents),
+            ImportMode::Merge => self.merge_data(contents),
+        }
+    }
+
+    fn replace_data(&mut self, contents: StoreContents) -> AppResult<DataImportReport> {
+        let imported = contents.timers.len()
+            + contents.projects.len()
+            + contents.categories.len()
+            + contents.templates.len()
+            + contents.todos.len()
+            + contents.marks.len();
+        self.store.replace_contents(contents)?;
+        self.history.clear();
+        self.reconcile_event_log(EventKind::Imported)?;
+        self.ids
+            .advance_to(Self::next_sequence_from_store(&self.store));
+        Ok(DataImportReport {
+            mode: ImportMode::Replace,
+            imported,
+            unchanged: 0,
+            relinked_marks: 0,
+            remapped: Vec::new(),
+        })
+    }
+
+    fn merge_data(&mut self, contents: StoreContents) -> AppResult<DataImportReport> {
+        let imported_sequence = contents
+            .ids()
+            .filter_map(id_sequence_number)
+            .max()
+            .unwrap_or(0);
+        self.ids.advance_to(imported_sequence.saturating_add(1));
+        let mut entities = contents.into_entities();
+        let marks: Vec<Mark> = entities
+            .iter()
+            .filter_map(|entity| match entity {
+                Entity::Mark(mark) => Some(mark.clone()),
+                _ => None,
+            })
+            .collect();
+        entities.retain(|entity| !matches!(entity, Entity::Mark(_)));
+
+        let mut report = DataImportReport {
+            mode: ImportMode::Merge,
+            imported: 0,
+            unchanged: 0,
+            relinked_marks: 0,
+            remapped: Vec::new(),
+        };
+        let mut remap: HashMap<String, String> = HashMap::new();
+        let mut batch = ImportBatch::default();
+        for mut entity in entities {
+            entity.remap_references(&remap);
+            if !self.merge_entity_id(&mut entity, &mut remap, &mut report) {
+                continue;
+            }
+            self.apply_entity_state(None, Some(&entity))?;
+            batch.created(EventKind::Imported, entity, None);
+            report.imported += 1;
+        }
+
+        let mut file_marks: BTreeMap<String, Vec<Mark>> = BTreeMap::new();
+        let mut new_marks: HashMap<String, Vec<Mark>> = HashMap::new();
+        for mark in marks {
+            let mut entity = Entity::Mark(mark);
+            entity.remap_references(&remap);
+            let keep = self.merge_entity_id(&mut entity, &mut remap, &mut report);
+            let Entity::Mark(mark) = entity else {
+                unreachable!("marks stay marks");
+            };
+            file_marks
+                .entry(mark.timer_id.clone())
+                .or_default()
+                .push(mark.clone());
+            if keep {
+                new_marks
+                    .entry(mark.timer_id.clone())
+                    .or_default()
+                    .push(mark);
+            }
+        }
+
+        for (timer_id, marks) in file_marks {
+            let Some(incoming) = new_marks.remove(&timer_id) else {
+                continue;
+            };
+            let existing = self.store.list_marks_by_timer(&timer_id);
+            let mut chained = dataset::chained_mark_ids(&existing);
+            chained.extend(dataset::chained_mark_ids(&marks));
+            let before: HashMap<String, Mark> = existing
+                .iter()
+                .map(|mark| (mark.id.clone(), mark.clone()))
+                .collect();
+
+            let mut merged = [existing, incoming].concat();
+            dataset::relink_chain(&mut merged, &chained);
+            for mark in merged {
+                match before.get(&mark.id) {
+                    Some(previous) if *previous == mark => {}
+                    Some(previous) => {
+                        self.store.update_mark(mark.clone())?;
+                        batch.updated(
+                            EventKind::Imported,
+                            Entity::Mark(previous.clone()),
+                            Entity::Mark(mark),
+                        );
+                        report.relinked_marks += 1;
+                    }
+                    None => {
+                        self.store.append_mark(mark.clone())?;
+                        batch.created(EventKind::Imported, Entity::Mark(mark), None);
+                        report.imported += 1;
+                    }
+                }
+            }
+        }
+
+        self.commit_import("import data", batch)?;
+        Ok(report)
+    }
+
+    pub fn merge_data_dir(&mut self, other_dir: &Path) -> AppResult<MergeReport> {
+        let other = merge::load_data_dir(other_dir)?;
+        let (merged, report) = merge::merge(self.current_contents(), other);
+        let mut batch = ImportBatch::default();
+        for entity in merged.into_entities() {
+            match self.load_entity(&entity) {
+                Some(before) if before == entity => {}
+                Some(before) => {
+                    self.apply_entity_state(Some(&before), Some(&entity))?;
+                    batch.updated(EventKind::Imported, before, entity);
+                }
+                None => {
+                    self.apply_entity_state(None, Some(&entity))?;
+                    batch.created(EventKind::Imported, entity, None);
+                }
+            }
+        }
+        self.ids
+            .advance_to(Self::next_sequence_from_store(&self.store));
+        self.commit_import("merge data directory", batch)?;
+        Ok(report)
+    }
+
+    fn merge_entity_id(
+        &self,
+        entity: &mut Entity,
+        remap: &mut HashMap<String, String>,
+        report: &mut DataImportReport,
+    ) -> bool {
+        match self.load_entity(entity) {
+            Some(existing) if existing == *entity => {
+                report.unchanged += 1;
+                false
+            }
+            Some(_) => {
+                let from = entity.id().to_string();
+                let to = self.ids.next(entity.entity_type());
+                entity.set_id(&to);
+                remap.insert(from.clone(), to.clone());
+                report.remapped.push(RemappedId {
+                    entity_type: entity.entity_type().to_string(),
+                    from,
+                    to,
+                });
+                true
+            }
+            None => true,
+        }
+    }
+
+    pub fn export_taskwarrior(&self, timer_id: Option<&str>) -> AppResult<String> {
+        let (timers, marks, todos) = self.load_activity(timer_id, true)?;
+        taskwarrior::render(&activity_view(&timers, &marks, &todos))
+    }
+
+    pub fn import_taskwarrior(
+        &mut self,
+        timer_id: Option<&str>,
+        text: &str,
+        now_minute: EpochMinutes,
+    ) -> AppResult<TaskImportReport> {
+        if let Some(timer_id) = timer_id {
+            self.ensure_timer_exists(timer_id)?;
+        }
+        let parsed = taskwarrior::parse(text)?;
+        let mut targets: HashMap<String, Option<EpochMinutes>> = HashMap::new();
+        for entry in &parsed.entries {
+            let target = targets
+                .entry(import_timer_name(&entry.project))
+                .or_default();
+            *target = (*target).max(entry.due_minute);
+        }
+        let known: HashMap<String, String> = self
+            .all_todos()
+            .into_iter()
+            .map(|todo| (taskwarrior::stable_uuid(&todo.id), todo.id))
+            .collect();
+
+        let mut report = TaskImportReport {
+            skipped: parsed.skipped,
+            ..TaskImportReport::default()
+        };
+        let mut timers = self.timers_by_name();
+        let mut batch = ImportBatch::default();
+        for entry in parsed.entries {
+            if let Some(existing) = entry.uuid.as_ref().and_then(|uuid| known.get(uuid)) {
+                report.skipped.push(SkippedRow {
+                    row: entry.row,
+                    reason: format!("duplicate of {existing}"),
+                });
+                continue;
+            }
+            let timer_id = match timer_id {
+                Some(timer_id) => timer_id.to_string(),
+                None => {
+                    let name = import_timer_name(&entry.project);
+                    let target = targets[&name].unwrap_or(now_minute);
+                    self.import_timer(&mut timers, &name, target, now_minute, &mut batch)?
+                }
+            };
+            let done_at_minute = match entry.status {
+                TodoStatus::Done => Some(
+                    entry
+                        .done_at_minute
+                        .or(entry.updated_at_minute)
+                        .unwrap_or(now_minute),
+                ),
+                TodoStatus::Open => None,
+            };
+            let todo = Todo {
+                id: self.ids.next("todo"),
+                timer_id,
+                title: entry.title,
+                status: entry.status,
+                created_at_minute: entry.created_at_minute.unwrap_or(now_minute),
+                updated_at_minute: entry.updated_at_minute.unwrap_or(now_minute),
+                done_at_minute,
+                tags: entry.tags,
+            };
+            self.store.save_todo(todo.clone())?;
+            batch.created(
+                EventKind::TodoCreated,
+                Entity::Todo(todo.clone()),
+                Some(now_minute),
+            );
+            report.todos.push(todo);
+        }
+
+        report.skipped.sort_by_key(|skipped| skipped.row);
+        report.created_timers = batch.created_timers.clone();
+        self.commit_import("import Taskwarrior tasks", batch)?;
+        Ok(report)
+    }
+
+    pub fn export_timewarrior(&self, timer_id: Option<&str>) -> AppResult<String> {
+        let (timers, marks, todos) = self.load_activity(timer_id, true)?;
+        Ok(timewarrior::render(&activity_view(&timers, &marks, &todos)))
+    }
+
+    pub fn import_timewarrior(
+        &mut self,
+        timer_id: Option<&str>,
+        text: &str,
+        now_minute: EpochMinutes,
+    ) -> AppResult<TimesheetReport> {
+        let fixed_timer = timer_id
+            .map(|timer_id| {
+                self.store
+                    .get_timer(timer_id)
+                    .ok_or_else(|| AppError::NotFound(format!("timer {timer_id}")))
+            })
+            .transpose()?;
+        let parsed = timewarrior::parse(text)?;
+        let mut timers = self.timers_by_name();
+        let names: Vec<String> = parsed
+            .entries
+            .iter()
+            .map(|interval| match &fixed_timer {
+                Some(timer) => timer.name.clone(),
+                None => interval
+                    .tags
+                    .iter()
+                    .find(|tag| timers.contains_key(*tag))
+                    .or(interval.tags.first())
+                    .cloned()
+                    .unwrap_or_else(|| import_timer_name("")),
+            })
+            .collect();
+        let mut targets: HashMap<&str, EpochMinutes> = HashMap::new();
+        for (interval, name) in parsed.entries.iter().zip(&names) {
+            let target = targets.entry(name).or_default();
+            *target = (*target).max(interval.end_minute);
+        }
+        let targets: HashMap<String, EpochMinutes> = targets
+            .into_iter()
+            .map(|(name, target)| (name.to_string(), target))
+            .collect();
+
+        let mut report = TimesheetReport {
+            skipped: parsed.skipped,
+            ..TimesheetReport::default()
+        };
+        let mut batch = ImportBatch::default();
+        for (interval, name) in parsed.entries.into_iter().zip(names) {
+            let timer_id = match &fixed_timer {
+                Some(timer) => timer.id.clone(),
+                None => {
+                    self.import_timer(&mut timers, &name, targets[&name], now_minute, &mut batch)?
+                }
+            };
+            let tags = tag::merge_tags(
+                &tag::sanitize_tags(
+                    interval
+                        .tags
+                        .iter()
+                        .filter(|tag| **tag != name)
+                        .map(String::as_str),
+                ),
+                &tag::parse_tags(&interval.annotation),
+            );
+            match self.import_segment(
+                &mut batch,
+                &timer_id,
+                (interval.start_minute, interval.end_minute),
+                interval.annotation,
+                tags,
+            ) {
+                Ok(mark) => report.marks.push(mark),
+                Err(AppError::Conflict(reason)) => report.skipped.push(SkippedRow {
+                    row: interval.row,
+                    reason,
+                }),
+                Err(error) => return Err(error),
+            }
+        }
+
+        report.skipped.sort_by_key(|skipped| skipped.row);
+        report.created_timers = batch.created_timers.clone();
+        self.commit_import("import Timewarrior intervals", batch)?;
+        Ok(report)
+    }
+
+    pub fn export_todotxt(
+        &self,
+        timer_id: &str,
+        utc_offset_minutes: EpochMinutes,
+    ) -> AppResult<String> {
+        self.ensure_timer_exists(timer_id)?;
+        let todos = self.store.list_todos_by_timer(timer_id);
+        Ok(todotxt::render(&todos, utc_offset_minutes))
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn load_activity(
+        &self,
+        timer_id: Option<&str>,
+        include_archived: bool,
+    ) -> AppResult<(Vec<Timer>, Vec<Vec<Mark>>, Vec<Vec<Todo>>)> {
+        let timers = match timer_id {
+            Some(timer_id) => vec![self
+                .store
+                .get_timer(timer_id)
+                .ok_or_else(|| AppError::NotFound(format!("timer {timer_id}")))?],
+            None => self.store.list_timers(include_archived),
+        };
+        let marks = timers
+            .iter()
+            .map(|timer| self.store.list_marks_by_timer(&timer.id))
+            .collect();
+        let todos = timers
+            .iter()
+            .map(|timer| self.store.list_todos_by_timer(&timer.id))
+            .collect();
+        Ok((timers, marks, todos))
+    }
+
+    pub fn set_timer_tags(
+        &mut self,
+        timer_id: &str,
+        tags: Vec<String>,
+        now_minute: EpochMinutes,
+    ) -> AppResult<Timer> {
+        let mut timer = self
+            .store
+            .get_timer(timer_id)
+            .ok_or_else(|| AppError::NotFound(format!("timer {timer_id}")))?;
+
+        timer.tags = tag::normalize_tags(&tags)?;
+        timer.updated_at_minute = now_minute;
+        self.save_recorded(
+            EventKind::TimerTagsChanged,
+            Entity::Timer(timer.clone()),
+            Some(now_minute),
+        )?;
+        Ok(timer)
+    }
+
+    pub fn set_todo_tags(
+        &mut self,
+        todo_id: &str,
+        tags: Vec<String>,
+        now_minute: EpochMinutes,
+    ) -> AppResult<Todo> {
+        let mut todo = self
+            .store
+            .get_todo(todo_id)
+            .ok_or_else(|| AppError::NotFound(format!("todo {todo_id}")))?;
+
+        todo.tags = tag::merge_tags(&tag::normalize_tags(&tags)?, &tag::parse_tags(&todo.title));
+        todo.updated_at_minute = now_minute;
+        self.save_recorded(
+            EventKind::TodoTagsChanged,
+            Entity::Todo(todo.clone()),
+            Some(now_minute),
+        )?;
+        Ok(todo)
+    }
+
+    pub fn list_timers_by_tag(&self, raw_tag: &str) -> AppResult<Vec<Timer>> {
+        let tag = tag::normalize_tag(raw_tag)?;
+        Ok(self
+            .store
+            .list_timers(true)
+            .into_iter()
+            .filter(|timer| timer.tags.contains(&tag))
+            .collect())
+    }
+
+    pub fn list_todos_by_tag(&self, raw_tag: &str) -> AppResult<Vec<Todo>> {
+        let tag = tag::normalize_tag(raw_tag)?;
+        let mut todos: Vec<Todo> = self
+            .all_todos()
+            .into_iter()
+            .filter(|todo| todo.tags.contains(&tag))
+            .collect();
+        todos.sort_by_key(|todo| todo.created_at_minute);
+        Ok(todos)
+    }
+
+    /// Marks carrying the tag inside the half-open window `[since, until)`,
+    /// the same bounds paging, search and the timeline use.
+    pub fn list_marks_by_tag(
+        &self,
+        raw_tag: &str,
+        since_minute: Option<EpochMinutes>,
+        until_minute: Option<EpochMinutes>,
+    ) -> AppResult<Vec<Mark>> {
+        let tag = tag::normalize_tag(raw_tag)?;
+        let mut marks: Vec<Mark> = self
+            .all_marks()
+            .into_iter()
+            .filter(|mark| mark.tags.contains(&tag))
+            .filter(|mark| since_minute.is_none_or(|since| mark.marked_at_minute >= since))
+            .filter(|mark| until_minute.is_none_or(|until| mark.marked_at_minute < until))
+            .collect();
+        marks.sort_by_key(|mark| mark.marked_at_minute);
+        Ok(marks)
+    }
+
+    pub fn search(&self, query: &SearchQuery) -> AppResult<Vec<SearchHit>> {
+        self.search_index.search(query)
+    }
+
+    pub fn query(
+        &self,
+        expression: &str,
+        now_minute: EpochMinutes,
+        utc_offset_minutes: EpochMinutes,
+    ) -> AppResult<QueryResults> {
+        let parsed = query::parse(expression)?;
+        let timers = self.store.list_timers(true);
+        let projects = self.store.list_projects();
+        let categories = self.store.list_categories();
+        let context = QueryContext {
+            now_minute,
+            utc_offset_minutes,
+            timers: &timers,
+            projects: &projects,
+            categories: &categories,
+        };
+        Ok(query::evaluate(
+            &parsed,
+            &context,
+            self.all_todos(),
+            self.all_marks(),
+        ))
+    }
+
+    pub fn timeline(
+        &self,
+        since_minute: EpochMinutes,
+        until_minute: EpochMinutes,
+    ) -> AppResult<Vec<TimelineEntry>> {
+        timeline::validate_range(since_minute, until_minute)?;
+        let mut archived_at = HashMap::new();
+        for event in self.store.list_events() {
+            match (event.kind, event.at_minute) {
+                (EventKind::TimerArchived, Some(at_minute)) => {
+                    archived_at.insert(event.entity_id, at_minute);
+                }
+                (EventKind::TimerUnarchived, _) => {
+                    archived_at.remove(&event.entity_id);
+                }
+                _ => {}
+            }
+        }
+
+        let timers = self.store.list_timers(true);
+        let todos = self.all_todos();
+        let marks = self.all_marks();
+        timeline::build(
+            &TimelineSource {
+                timers: &timers,
+                todos: &todos,
+                marks: &marks,
+                archived_at: &archived_at,
+            },
+            since_minute,
+            until_minute,
+        )
+    }
+
+    pub fn list_tags(&self) -> Vec<TagUsage> {
+        let mut usages: BTreeMap<String, TagUsage> = BTreeMap::new();
+
+        for timer in self.store.list_timers(true) {
+            for tag in &timer.tags {
+                tag_usage_entry(&mut usages, tag).timer_count += 1;
+            }
+        }
+        for todo in self.all_todos() {
+            for tag in &todo.tags {
+                tag_usage_entry(&mut usages, tag).todo_count += 1;
+            }
+        }
+        for mark in self.all_marks() {
+            for tag in &mark.tags {
+                tag_usage_entry(&mut usages, tag).mark_count += 1;
+            }
+        }
+
+        usages.into_values().collect()
+    }
+
+    pub fn rename_tag(
+        &mut self,
+        raw_from: &str,
+        raw_to: &str,
+        now_minute: EpochMinutes,
+    ) -> AppResult<TagUsage> {
+        self.merge_tags(&[raw_from.to_string()], raw_to, now_minute)
+    }
+
+    pub fn merge_tags(
+        &mut self,
+        raw_sources: &[String],
+        raw_target: &str,
+        now_minute: EpochMinutes,
+    ) -> AppResult<TagUsage> {
+        let sources = tag::normalize_tags(raw_sources)?;
+        let target = tag::normalize_tag(raw_target)?;
+        if sources.is_empty() {
+            return Err(AppError::Validation(
+                "at least one source tag is required".to_string(),
+            ));
+        }
+
+        let sources: Vec<String> = sources
+            .into_iter()
+            .filter(|source| *source != target)
+            .collect();
+        let mut changes = Vec::new();
+        for mut timer in self.store.list_timers(true) {
+            let before = timer.clone();
+            for source in &sources {
+                if timer.tags.contains(source) {
+                    timer.tags = tag::replace_tag(&timer.tags, source, &target);
+                }
+            }
+            if timer != before {
+                timer.updated_at_minute = now_minute;
+                changes.push(Change::updated(Entity::Timer(before), Entity::Timer(timer)));
+            }
+        }
+        for mut todo in self.all_todos() {
+            let before = todo.clone();
+            for source in &sources {
+                if todo.tags.contains(source) {
+                    todo.title = tag::replace_tag_in_text(&todo.title, source, &target);
+                    todo.tags = tag::replace_tag(&todo.tags, source, &target);
+                }
+            }
+            if todo != before {
+                todo.updated_at_minute = now_minute;
+                changes.push(Change::updated(Entity::Todo(before), Entity::Todo(todo)));
+            }
+        }
+        for mut mark in self.all_marks() {
+            let before = mark.clone();
+            for source in &sources {
+                if mark.tags.contains(source) {
+                    mark.description = tag::replace_tag_in_text(&mark.description, source, &target);
+                    mark.tags = tag::replace_tag(&mark.tags, source, &target);
+                }
+            }
+            if mark != before {
+                changes.push(Change::updated(Entity::Mark(before), Entity::Mark(mark)));
+            }
+        }
+        if changes.is_empty() {
+            return Ok(self.tag_usage(target));
+        }
+
+        // One batch write of the changed rows, so a failed save leaves no entity
+        // half-renamed and rows the tag lists cannot reach stay on disk.
+        self.store.apply_changes(changes.clone())?;
+        let events = changes
+            .iter()
+            .filter_map(|change| change.after.clone())
+            .map(|entity| {
+                let kind = match entity {
+                    Entity::Timer(_) => EventKind::TimerTagsChanged,
+                    Entity::Todo(_) => EventKind::TodoTagsChanged,
+                    _ => EventKind::MarkTagsChanged,
+                };
+                DomainEvent::saved(kind, entity, Some(now_minute))
+            })
+            .collect();
+        self.history.record("merge tags", changes);
+        self.emit(events)?;
+        Ok(self.tag_usage(target))
+    }
+
+    fn tag_usage(&self, tag: String) -> TagUsage {
+        self.list_tags()
+            .into_iter()
+            .find(|usage| usage.tag == tag)
+            .unwrap_or(TagUsage {
+                tag,
+                timer_count: 0,
+                todo_count: 0,
+                mark_count: 0,
+            })
+    }
+
+    fn save_recorded(
+        &mut self,
+        kind: EventKind,
+        entity: Entity,
+        at_minute: Option<EpochMinutes>,
+    ) -> AppResult<()> {
+        let before = self.load_entity(&entity);
+        self.apply_entity_state(before.as_ref(), Some(&entity))?;
+        self.history.record(
+            kind.label(),
+            vec![Change {
+                before,
+                after: Some(entity.clone()),
+            }],
+        );
+        self.emit(vec![DomainEvent::saved(kind, entity, at_minute)])
+    }
+
+    fn delete_recorded(
+        &mut self,
+        kind: EventKind,
+        entity: Entity,
+        at_minute: Option<EpochMinutes>,
+    ) -> AppResult<()> {
+        self.apply_entity_state(Some(&entity), None)?;
+        let event = DomainEvent::deleted(kind, &entity, at_minute);
+        self.history
+            .record(kind.label(), vec![Change::deleted(entity)]);
+        self.emit(vec![event])
+    }
+
+    fn emit(&mut self, mut events: Vec<DomainEvent>) -> AppResult<()> {
+        for event in &mut events {
+            event.sequence = self.next_event_sequence;
+            self.next_event_sequence += 1;
+        }
+        self.store.append_events(events.clone())?;
+        for event in &events {
+            self.search_index.apply(event);
+        }
+        for subscriber in &mut self.subscribers {
+            for event in &events {
+                subscriber.on_event(event);
+            }
+        }
+        Ok(())
+    }
+
+    fn rebuild_search_index(&mut self) {
+        self.search_index = SearchIndex::build(&self.all_todos(), &self.all_marks());
+    }
+
+    fn current_contents(&self) -> StoreContents {
+        StoreContents::load(&self.store)
+    }
+
+    fn reconcile_event_log(&mut self, kind: EventKind) -> AppResult<()> {
+        let mut replayed: BTreeMap<(&'static str, String), Entity> =
+            event::replay(&self.store.list_events())
+                .entities()
+                .into_iter()
+                .map(|entity| ((entity.entity_type(), entity.id().to_string()), entity))
+                .collect();
+
+        let contents = self.current_contents();
+        let mut current: Vec<Entity> = Vec::new();
+        current.extend(contents.timers.into_iter().map(Entity::Timer));
+        current.extend(contents.projects.into_iter().map(Entity::Project));
+        current.extend(contents.categories.into_iter().map(Entity::Category));
+        current.extend(contents.templates.into_iter().map(Entity::Template));
+        current.extend(contents.todos.into_iter().map(Entity::Todo));
+        current.extend(contents.marks.into_iter().map(Entity::Mark));
+
+        let mut events = Vec::new();
+        for entity in current {
+            let key = (entity.entity_type(), entity.id().to_string());
+            if replayed.remove(&key).as_ref() != Some(&entity) {
+                events.push(DomainEvent::saved(kind, entity, None));
+            }
+        }
+        for entity in replayed.values() {
+            events.push(DomainEvent::deleted(kind, entity, None));
+        }
+        self.emit(events)
+    }
+
+    fn load_entity(&self, entity: &Entity) -> Option<Entity> {
+        match entity {
+            Entity::Timer(timer) => self.store.get_timer(&timer.id).map(Entity::Timer),
+            Entity::Project(project) => self.store.get_project(&project.id).map(Entity::Project),
+            Entity::Category(category) => {
+                self.store.get_category(&category.id).map(Entity::Category)
+            }
+            Entity::Template(template) => {
+                self.store.get_template(&template.id).map(Entity::Template)
+            }
+            Entity::Todo(todo) => self.store.get_todo(&todo.id).map(Entity::Todo),
+            Entity::Mark(mark) => self.store.get_mark(&mark.id).map(Entity::Mark),
+        }
+    }
+
+    fn apply_entity_state(
+        &mut self,
+        current: Option<&Entity>,
+        target: Option<&Entity>,
+    ) -> AppResult<()> {
+        match (current, target) {
+            (_, Some(Entity::Timer(timer))) => self.store.save_timer(timer.clone()),
+            (_, Some(Entity::Project(project))) => self.store.save_project(project.clone()),
+            (_, Some(Entity::Category(category))) => self.store.save_category(category.clone()),
+            (_, Some(Entity::Template(template))) => self.store.save_template(template.clone()),
+            (_, Some(Entity::Todo(todo))) => self.store.save_todo(todo.clone()),
+            (Some(_), Some(Entity::Mark(mark))) => self.store.update_mark(mark.clone()),
+            (None, Some(Entity::Mark(mark))) => self.store.append_mark(mark.clone()),
+            (Some(Entity::Timer(timer)), None) => self.store.delete_timer(&timer.id),
+            (Some(Entity::Project(project)), None) => self.store.delete_project(&project.id),
+            (Some(Entity::Category(category)), None) => self.store.delete_category(&category.id),
+            (Some(Entity::Template(template)), None) => self.store.delete_template(&template.id),
+            (Some(Entity::Todo(todo)), None) => self.store.delete_todo(&todo.id),
+            (Some(Entity::Mark(mark)), None) => self.store.delete_mark(&mark.id),
+            (None, None) => Ok(()),
+        }
+    }
+
+    fn all_todos(&self) -> Vec<Todo> {
+        self.store
+            .list_timers(true)
+            .iter()
+            .flat_map(|timer| self.store.list_todos_by_timer(&timer.id))
+            .collect()
+    }
+
+    fn all_marks(&self) -> Vec<Mark> {
+        self.store
+            .list_timers(true)
+            .iter()
+            .flat_map(|timer| self.store.list_marks_by_timer(&timer.id))
+            .collect()
+    }
+
+    fn timers_by_name(&self) -> HashMap<String, Timer> {
+        let mut timers = self.store.list_timers(true);
+        timers.sort_by_key(|timer| !timer.archived);
+        timers
+            .into_iter()
+            .map(|timer| (timer.name.clone(), timer))
+            .collect()
+    }
+
+    fn import_timer(
+        &mut self,
+        timers: &mut HashMap<String, Timer>,
+        name: &str,
+        target_at_minute: EpochMinutes,
+        now_minute: EpochMinutes,
+        batch: &mut ImportBatch,
+    ) -> AppResult<String> {
+        if let Some(timer) = timers.get(name) {
+            return Ok(timer.id.clone());
+        }
+        let timer = Timer {
+            id: self.ids.next("timer"),
+            name: name.to_string(),
+            target_at_minute,
+            created_at_minute: now_minute,
+            updated_at_minute: now_minute,
+            archived: false,
+            project_id: None,
+            tags: Vec::new(),
+        };
+        self.store.save_timer(timer.clone())?;
+        batch.created(
+            EventKind::TimerCreated,
+            Entity::Timer(timer.clone()),
+            Some(now_minute),
+        );
+        batch.created_timers.push(timer.clone());
+        timers.insert(timer.name.clone(), timer.clone());
+        Ok(timer.id)
+    }
+
+    fn import_segment(
+        &mut self,
+        batch: &mut ImportBatch,
+        timer_id: &str,
+        (start_minute, end_minute): (EpochMinutes, EpochMinutes),
+        description: String,
+        tags: Vec<String>,
+    ) -> AppResult<Mark> {
+        let marks = batch
+            .marks_by_timer
+            .entry(timer_id.to_string())
+            .or_insert_with(|| self.store.list_marks_by_timer(timer_id));
+        if let Some(duplicate) = marks.iter().find(|mark| {
+            mark.prev_marked_at_minute == Some(start_minute)
+                && mark.marked_at_minute == end_minute
+                && mark.description == description
+        }) {
+            return Err(AppError::Conflict(format!("duplicate of {}", duplicate.id)));
+        }
+
+        let mark = Mark {
+            id: self.ids.next("mark"),
+            timer_id: timer_id.to_string(),
+            marked_at_minute: end_minute,
+            prev_marked_at_minute: Some(start_minute),
+            duration_minutes: Some(end_minute - start_minute),
+            description,
+            todo_ids: Vec::new(),
+            tags,
+            category_id: None,
+        };
+        self.store.append_mark(mark.clone())?;
+        marks.push(mark.clone());
+        batch.created(
+            EventKind::MarkAppended,
+            Entity::Mark(mark.clone()),
+            Some(end_minute),
+        );
+        Ok(mark)
+    }
+
+    fn commit_import(&mut self, label: &str, batch: ImportBatch) -> AppResult<()> {
+        self.history.record(label, batch.changes);
+        self.emit(batch.events)
+    }
+
+    fn ensure_timer_exists(&self, timer_id: &str) -> AppResult<()> {
+        if self.store.get_timer(timer_id).is_none() {
+            return Err(AppError::NotFound(format!("timer {timer_id}")));
+        }
+        Ok(())
+    }
+
+    fn ensure_category_exists(&self, category_id: &str) -> AppResult<()> {
+        if self.store.get_category(category_id).is_none() {
+            return Err(AppError::NotFound(format!("category {category_id}")));
+        }
+        Ok(())
+    }
+
+    fn ensure_project_exists(&self, project_id: &str) -> AppResult<()> {
+        if self.store.get_project(project_id).is_none() {
+            return Err(AppError::NotFound(format!("project {project_id}")));
+        }
+        Ok(())
+    }
+
+    fn next_sequence_from_store(store: &S) -> u64 {
+        let timers = store.list_timers(true);
+        let mut max_sequence = 0;
+
+        for project in store.list_projects() {
+            max_sequence = max_sequence.max(id_sequence_number(&project.id).unwrap_or(0));
+        }
+
+        for category in store.list_categories() {
+            max_sequence = max_sequence.max(id_sequence_number(&category.id).unwrap_or(0));
+        }
+
+        for template in store.list_templates() {
+            max_sequence = max_sequence.max(id_sequence_number(&template.id).unwrap_or(0));
+        }
+
+        for timer in &timers {
+            max_sequence = max_sequence.max(id_sequence_number(&timer.id).unwrap_or(0));
+
+            for todo in store.list_todos_by_timer(&timer.id) {
+                max_sequence = max_sequence.max(id_sequence_number(&todo.id).unwrap_or(0));
+            }
+
+            for mark in store.list_marks_by_timer(&timer.id) {
+                max_sequence = max_sequence.max(id_sequence_number(&mark.id).unwrap_or(0));
+            }
+        }
+
+        max_sequence.saturating_add(1).max(1)
+    }
+}
+
+fn tag_usage_entry<'a>(usages: &'a mut BTreeMap<String, TagUsage>, tag: &str) -> &'a mut TagUsage {
+    usages.entry(tag.to_string()).or_insert_with(|| TagUsage {
+        tag: tag.to_string(),
+        timer_count: 0,
+        todo_count: 0,
+        mark_count: 0,
+    })
+}
+
+fn id_sequence_number(id: &str) -> Option<u64> {
+    let (_, suffix) = id.rsplit_once('-')?;
+    suffix.parse::<u64>().ok()
+}
+
+fn state_event(
+    kind: EventKind,
+    current: Option<&Entity>,
+    target: Option<&Entity>,
+) -> Option<DomainEvent> {
+    match (current, target) {
+        (_, Some(entity)) => Some(DomainEvent::saved(kind, entity.clone(), None)),
+        (Some(entity), None) => Some(DomainEvent::deleted(kind, entity, None)),
+        (None, None) => None,
+    }
+}
+
+fn import_timer_name(project: &str) -> String {
+    if project.is_empty() {
+        timesheet::NO_PROJECT_TIMER_NAME.to_string()
+    } else {
+        project.to_string()
+    }
+}
+
+fn activity_view<'a>(
+    timers: &'a [Timer],
+    marks: &'a [Vec<Mark>],
+    todos: &'a [Vec<Todo>],
+) -> Vec<TimerActivity<'a>> {
+    timers
+        .iter()
+        .zip(marks.iter().zip(todos.iter()))
+        .map(|(timer, (marks, todos))| TimerActivity {
+            timer,
+            marks,
+            todos,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::AppError;
+    use std::sync::{Arc, Mutex};
+
+    use crate::event::{DomainEvent, EventKind, EventSubscriber};
+    use crate::history::Entity;
+    use crate::model::TodoStatus;
+    use crate::reminder::ReminderPolicy;
+    use crate::repository::{InMemoryStore, Store};
+    use crate::search::SearchQuery;
+
+    use super::AppService;
+
+    #[test]
+    fn calculates_timer_remaining_minutes() {
+        let mut service = AppService::new(InMemoryStore::default());
+        let timer = service
+            .create_timer("vacation", 200, 100)
+            .expect("timer should be created");
+
+        assert_eq!(timer.remaining_minutes(160), 40);
+        assert_eq!(timer.remaining_minutes(250), -50);
+    }
+
+    #[test]
+    fn creates_mark_chain_with_duration() {
+        let mut service = AppService::new(InMemoryStore::default());
+        let timer = service
+            .create_timer("study", 300, 100)
+            .expect("timer should be created");
+
+        let first_mark = service
+            .create_mark(&timer.id, 120, "wrote architecture", vec![])
+            .expect("first mark should be created");
+        assert_eq!(first_mark.prev_marked_at_minute, None);
+        assert_eq!(first_mark.duration_minutes, None);
+
+        let second_mark = service
+            .create_mark(&timer.id, 170, "built service", vec![])
+            .expect("second mark should be created");
+        assert_eq!(second_mark.prev_marked_at_minute, Some(120));
+        assert_eq!(second_mark.duration_minutes, Some(50));
+    }
+
+    #[test]
+    fn updates_todo_status_and_done_timestamp() {
         let mut service = AppService::new(InMemoryStore::default());
         let timer = service
             .create_timer("project", 500, 100)
@@ -321,6 +2258,527 @@ mod tests {
         assert!(error.to_string().contains("missing-timer"));
     }
 
+    #[test]
+    fn unarchives_timer_back_into_active_list() {
+        let mut service = AppService::new(InMemoryStore::default());
+        let timer = service
+            .create_timer("project", 500, 100)
+            .expect("timer should be created");
+
+        service
+            .archive_timer(&timer.id, 110)
+            .expect("timer should be archived");
+        assert!(service.list_timers(false).is_empty());
+
+        let restored = service
+            .unarchive_timer(&timer.id, 120)
+            .expect("timer should be unarchived");
+        assert!(!restored.archived);
+        assert_eq!(restored.updated_at_minute, 120);
+        assert_eq!(service.list_timers(false).len(), 1);
+    }
+
+    #[test]
+    fn refuses_timer_delete_with_children_unless_cascading() {
+        let mut service = AppService::new(InMemoryStore::default());
+        let timer = service
+            .create_timer("scratch", 500, 100)
+            .expect("timer should be created");
+        service
+            .create_todo(&timer.id, "leftover", 110)
+            .expect("todo should be created");
+        service
+            .create_mark(&timer.id, 120, "leftover mark", vec![])
+            .expect("mark should be created");
+
+        let error = service
+            .delete_timer(&timer.id, false)
+            .expect_err("delete should be refused");
+        assert!(matches!(error, AppError::Conflict(_)));
+        assert_eq!(service.list_timers(true).len(), 1);
+
+        service
+            .delete_timer(&timer.id, true)
+            .expect("cascading delete should succeed");
+        assert!(service.list_timers(true).is_empty());
+        assert!(service.list_todos_by_timer(&timer.id).is_err());
+    }
+
+    #[test]
+    fn groups_timers_by_project_with_summary_counts() {
+        let mut service = AppService::new(InMemoryStore::default());
+        let project = service
+            .create_project("release", 100)
+            .expect("project should be created");
+        let grouped = service
+            .create_timer("ship", 20_000, 100)
+            .expect("timer should be created");
+        let loose = service
+            .create_timer("chores", 20_000, 100)
+            .expect("timer should be created");
+        service
+            .set_timer_project(&grouped.id, Some(&project.id), 110)
+            .expect("timer should be assigned");
+
+        service
+            .create_todo(&grouped.id, "open todo", 120)
+            .expect("todo should be created");
+        let done = service
+            .create_todo(&grouped.id, "done todo", 120)
+            .expect("todo should be created");
+        service
+            .set_todo_status(&done.id, TodoStatus::Done, 130)
+            .expect("todo should be completed");
+        service
+            .create_mark(&grouped.id, 200, "too old", vec![])
+            .expect("mark should be created");
+        service
+            .create_mark(&grouped.id, 12_000, "recent", vec![])
+            .expect("mark should be created");
+
+        let in_project = service
+            .list_timers_by_project(Some(&project.id), false)
+            .expect("timers should list");
+        assert_eq!(in_project.len(), 1);
+        assert_eq!(in_project[0].id, grouped.id);
+
+        let ungrouped = service
+            .list_timers_by_project(None, false)
+            .expect("timers should list");
+        assert_eq!(ungrouped.len(), 1);
+        assert_eq!(ungrouped[0].id, loose.id);
+
+        let summaries = service.list_project_summaries(12_500);
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].timer_count, 1);
+        assert_eq!(summaries[0].open_todo_count, 1);
+        assert_eq!(summaries[0].marks_this_week, 1);
+
+        let error = service
+            .delete_project(&project.id)
+            .expect_err("project with timers should not be deleted");
+        assert!(matches!(error, AppError::Conflict(_)));
+    }
+
+    #[test]
+    fn queries_marks_by_tag_across_timers_within_window() {
+        let mut service = AppService::new(InMemoryStore::default());
+        let api = service
+            .create_timer("api", 20_000, 100)
+            .expect("timer should be created");
+        let web = service
+            .create_timer("web", 20_000, 100)
+            .expect("timer should be created");
+
+        service
+            .create_mark(&api.id, 200, "old #review", vec![])
+            .expect("mark should be created");
+        service
+            .create_mark(&api.id, 12_000, "api #Review pass", vec![])
+            .expect("mark should be created");
+        service
+            .create_mark_with_details(
+                &web.id,
+                12_100,
+                "explicit only",
+                vec![],
+                vec!["#review".to_string()],
+                None,
+            )
+            .expect("mark should be created");
+        service
+            .create_mark(&web.id, 12_200, "#backend only", vec![])
+            .expect("mark should be created");
+        service
+            .create_mark(&web.id, 12_500, "boundary #review", vec![])
+            .expect("mark should be created");
+
+        let recent = service
+            .list_marks_by_tag("#review", Some(12_500 - 7 * 24 * 60), Some(12_500))
+            .expect("marks should be queried");
+        let descriptions: Vec<&str> = recent
+            .iter()
+            .map(|mark| mark.description.as_str())
+            .collect();
+        assert_eq!(descriptions, vec!["api #Review pass", "explicit only"]);
+    }
+
+    #[test]
+    fn merges_tags_across_timers_todos_and_marks() {
+        let mut service = AppService::new(InMemoryStore::default());
+        let timer = service
+            .create_timer("api", 500, 100)
+            .expect("timer should be created");
+        service
+            .set_timer_tags(&timer.id, vec!["cr".to_string()], 105)
+            .expect("timer tags should be set");
+        let todo = service
+            .create_todo(&timer.id, "address #cr comments", 110)
+            .expect("todo should be created");
+        assert_eq!(todo.tags, vec!["cr"]);
+        service
+            .create_mark(&timer.id, 120, "#review and #cr done", vec![])
+            .expect("mark should be created");
+
+        let usage = service
+            .merge_tags(&["cr".to_string()], "review", 130)
+            .expect("tags should merge");
+        assert_eq!(usage.timer_count, 1);
+        assert_eq!(usage.todo_count, 1);
+        assert_eq!(usage.mark_count, 1);
+
+        let marks = service
+            .list_marks_by_timer(&timer.id)
+            .expect("marks should list");
+        assert_eq!(marks[0].description, "#review and #review done");
+        assert_eq!(marks[0].tags, vec!["review"]);
+        assert!(service.list_tags().iter().all(|usage| usage.tag != "cr"));
+    }
+
+    #[test]
+    fn reports_minutes_per_category_for_timer() {
+        let mut service = AppService::new(InMemoryStore::default());
+        let timer = service
+            .create_timer("sprint", 5_000, 100)
+            .expect("timer should be created");
+        let meetings = service
+            .create_category("meetings", 100)
+            .expect("category should be created");
+        let coding = service
+            .create_category("coding", 100)
+            .expect("category should be created");
+
+        service
+            .create_mark(&timer.id, 100, "start", vec![])
+            .expect("mark should be created");
+        service
+            .create_mark_with_details(
+                &timer.id,
+                130,
+                "standup",
+                vec![],
+                vec![],
+                Some(meetings.id.clone()),
+            )
+            .expect("mark should be created");
+        let feature = service
+            .create_mark(&timer.id, 250, "feature", vec![])
+            .expect("mark should be created");
+        service
+            .set_mark_category(&feature.id, Some(&coding.id))
+            .expect("category should be set");
+
+        let report = service
+            .category_report(Some(&timer.id), 0, 1_000, 0)
+            .expect("report should be built");
+        let minutes: Vec<(Option<&str>, i64)> = report
+            .totals
+            .iter()
+            .map(|row| (row.category_name.as_deref(), row.minutes))
+            .collect();
+        assert_eq!(minutes, vec![(Some("coding"), 120), (Some("meetings"), 30)]);
+
+        let error = service
+            .delete_category(&meetings.id)
+            .expect_err("category in use should not be deleted");
+        assert!(matches!(error, AppError::Conflict(_)));
+    }
+
+    #[test]
+    fn creates_mark_from_template_with_linked_todos() {
+        let mut service = AppService::new(InMemoryStore::default());
+        let timer = service
+            .create_timer("release", 5_000, 100)
+            .expect("timer should be created");
+        let template = service
+            .create_template(
+                "progress",
+                "{timer} +{duration}\ndone:\n{done_since_last_mark}\nnext:\n{open_todos}",
+                100,
+            )
+            .expect("template should be created");
+
+        let old = service
+            .create_todo(&timer.id, "old work", 100)
+            .expect("todo should be created");
+        service
+            .set_todo_status(&old.id, TodoStatus::Done, 105)
+            .expect("todo should be completed");
+        service
+            .create_mark(&timer.id, 110, "checkpoint", vec![])
+            .expect("mark should be created");
+        let fixed = service
+            .create_todo(&timer.id, "fix login", 120)
+            .expect("todo should be created");
+        service
+            .set_todo_status(&fixed.id, TodoStatus::Done, 150)
+            .expect("todo should be completed");
+        let next = service
+            .create_todo(&timer.id, "write notes", 160)
+            .expect("todo should be created");
+
+        let mark = service
+            .create_mark_from_template(&timer.id, &template.id, 200)
+            .expect("mark should be created from template");
+
+        assert_eq!(
+            mark.description,
+            "release +1小时30分\ndone:\n- fix login\nnext:\n- write notes"
+        );
+        assert_eq!(mark.todo_ids, vec![fixed.id, next.id]);
+        assert_eq!(mark.duration_minutes, Some(90));
+
+        let error = service
+            .create_template("broken", "{nope}", 100)
+            .expect_err("unknown placeholder should be rejected");
+        assert!(matches!(error, AppError::Validation(_)));
+    }
+
+    #[test]
+    fn undoes_and_redoes_status_archive_and_cascade_delete() {
+        let mut service = AppService::new(InMemoryStore::default());
+        let timer = service
+            .create_timer("release", 5_000, 100)
+            .expect("timer should be created");
+        let todo = service
+            .create_todo(&timer.id, "ship", 110)
+            .expect("todo should be created");
+        let mark = service
+            .create_mark(&timer.id, 120, "started", vec![todo.id.clone()])
+            .expect("mark should be created");
+
+        service
+            .set_todo_status(&todo.id, TodoStatus::Done, 130)
+            .expect("todo should be completed");
+        service.undo().expect("status change should undo");
+        let reverted = service
+            .list_todos_by_timer(&timer.id)
+            .expect("todos should list");
+        assert_eq!(reverted, vec![todo.clone()]);
+        service.redo().expect("status change should redo");
+        assert!(matches!(
+            service
+                .list_todos_by_timer(&timer.id)
+                .expect("todos should list")[0]
+                .status,
+            TodoStatus::Done
+        ));
+
+        service
+            .archive_timer(&timer.id, 140)
+            .expect("timer should archive");
+        let step = service.undo().expect("archive should undo");
+        assert_eq!(step.label, "archive timer");
+        assert_eq!(service.list_timers(false).len(), 1);
+
+        service
+            .delete_timer(&timer.id, true)
+            .expect("timer should be deleted");
+        assert!(service.list_timers(true).is_empty());
+        let step = service.undo().expect("delete should undo");
+        assert_eq!(step.change_count, 3);
+        assert_eq!(service.list_timers(true).len(), 1);
+        assert_eq!(
+            service
+                .list_marks_by_timer(&timer.id)
+                .expect("marks should list"),
+            vec![mark]
+        );
+        assert_eq!(
+            service
+                .list_todos_by_timer(&timer.id)
+                .expect("todos should list")
+                .len(),
+            1
+        );
+
+        service
+            .create_todo(&timer.id, "follow up", 150)
+            .expect("todo should be created");
+        assert!(service.redo().is_err());
+    }
+
+    #[test]
+    fn keeps_fired_reminders_when_a_cascade_delete_is_undone() {
+        let mut service = AppService::new(InMemoryStore::default());
+        let timer = service
+            .create_timer("release", 1_000, 100)
+            .expect("timer should be created");
+        service
+            .create_mark(&timer.id, 120, "started", vec![])
+            .expect("mark should be created");
+        let policy = ReminderPolicy::default();
+        assert!(!service
+            .poll_reminders(995, &policy)
+            .expect("reminders should be polled")
+            .is_empty());
+
+        service
+            .delete_timer(&timer.id, true)
+            .expect("timer should be deleted");
+        service.undo().expect("delete should undo");
+        assert!(service
+            .poll_reminders(996, &policy)
+            .expect("reminders should be polled")
+            .is_empty());
+    }
+
+    #[derive(Clone, Default)]
+    struct RecordingSubscriber {
+        events: Arc<Mutex<Vec<DomainEvent>>>,
+    }
+
+    impl EventSubscriber for RecordingSubscriber {
+        fn on_event(&mut self, event: &DomainEvent) {
+            self.events
+                .lock()
+                .expect("events lock should not be poisoned")
+                .push(event.clone());
+        }
+    }
+
+    #[test]
+    fn publishes_committed_events_to_subscribers() {
+        let mut service = AppService::new(InMemoryStore::default());
+        let subscriber = RecordingSubscriber::default();
+        service.subscribe(Box::new(subscriber.clone()));
+
+        let timer = service
+            .create_timer("release", 5_000, 100)
+            .expect("timer should be created");
+        let todo = service
+            .create_todo(&timer.id, "ship", 110)
+            .expect("todo should be created");
+        assert!(service.create_todo(&timer.id, "  ", 111).is_err());
+        service
+            .delete_timer(&timer.id, true)
+            .expect("timer should be deleted");
+        service.undo().expect("delete should be undone");
+
+        let events = subscriber
+            .events
+            .lock()
+            .expect("events lock should not be poisoned")
+            .clone();
+        let summary: Vec<(EventKind, &str)> = events
+            .iter()
+            .map(|event| (event.kind, event.entity_id.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (EventKind::TimerCreated, timer.id.as_str()),
+                (EventKind::TodoCreated, todo.id.as_str()),
+                (EventKind::TodoDeleted, todo.id.as_str()),
+                (EventKind::TimerDeleted, timer.id.as_str()),
+                (EventKind::Undone, timer.id.as_str()),
+                (EventKind::Undone, todo.id.as_str()),
+            ]
+        );
+        assert_eq!(events, service.store.list_events()[..events.len()].to_vec());
+    }
+
+    #[test]
+    fn keeps_search_index_in_sync_with_mutations() {
+        let mut service = AppService::new(InMemoryStore::default());
+        let timer = service
+            .create_timer("release", 5_000, 100)
+            .expect("timer should be created");
+        service
+            .create_mark(&timer.id, 110, "Fixed the login bug #auth", vec![])
+            .expect("mark should be created");
+        let todo = service
+            .create_todo(&timer.id, "Résumé review", 120)
+            .expect("todo should be created");
+        service
+            .archive_timer(&timer.id, 130)
+            .expect("timer should be archived");
+
+        let login = SearchQuery {
+            text: "LOGIN".to_string(),
+            ..SearchQuery::default()
+        };
+        assert_eq!(service.search(&login).expect("search should run").len(), 1);
+
+        service
+            .rename_tag("auth", "security", 140)
+            .expect("tag should be renamed");
+        let renamed = SearchQuery {
+            text: "security".to_string(),
+            ..SearchQuery::default()
+        };
+        assert_eq!(
+            service.search(&renamed).expect("search should run").len(),
+            1
+        );
+
+        service
+            .set_todo_status(&todo.id, TodoStatus::Done, 150)
+            .expect("todo should be completed");
+        let done = SearchQuery {
+            text: "resume".to_string(),
+            status: Some(TodoStatus::Done),
+            ..SearchQuery::default()
+        };
+        assert_eq!(
+            service.search(&done).expect("search should run")[0].id,
+            todo.id
+        );
+
+        service
+            .delete_timer(&timer.id, true)
+            .expect("timer should be deleted");
+        assert!(service
+            .search(&login)
+            .expect("search should run")
+            .is_empty());
+        service.undo().expect("delete should be undone");
+        assert_eq!(service.search(&login).expect("search should run").len(), 1);
+    }
+
+    #[test]
+    fn records_entity_history_for_reopened_todo() {
+        let mut service = AppService::new(InMemoryStore::default());
+        let timer = service
+            .create_timer("release", 5_000, 100)
+            .expect("timer should be created");
+        let todo = service
+            .create_todo(&timer.id, "ship #v1", 110)
+            .expect("todo should be created");
+        service
+            .set_todo_status(&todo.id, TodoStatus::Done, 120)
+            .expect("todo should be completed");
+        service
+            .rename_tag("v1", "v2", 125)
+            .expect("tag should be renamed");
+        service
+            .set_todo_status(&todo.id, TodoStatus::Open, 130)
+            .expect("todo should be reopened");
+
+        let history = service
+            .entity_history("todo", &todo.id)
+            .expect("history should load");
+        let kinds: Vec<EventKind> = history.iter().map(|event| event.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                EventKind::TodoCreated,
+                EventKind::TodoStatusChanged,
+                EventKind::TodoTagsChanged,
+                EventKind::TodoStatusChanged,
+            ]
+        );
+        assert_eq!(history[3].at_minute, Some(130));
+        assert!(matches!(
+            &history[1].state,
+            Some(Entity::Todo(before)) if before.title == "ship #v1"
+        ));
+        assert!(history
+            .windows(2)
+            .all(|pair| pair[0].sequence < pair[1].sequence));
+        assert!(service.entity_history("widget", &todo.id).is_err());
+    }
+
     #[test]
     fn deletes_todo_and_removes_from_list() {
         let mut service = AppService::new(InMemoryStore::default());
@@ -0,0 +1,226 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::calendar;
+use crate::error::{AppError, AppResult};
+use crate::model::{EpochMinutes, Mark, Timer, Todo};
+
+pub const MAX_RANGE_DAYS: i64 = 366;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub struct DayTotals {
+    pub minutes_logged: EpochMinutes,
+    pub mark_count: usize,
+    pub todos_completed: usize,
+}
+
+impl DayTotals {
+    fn add(&mut self, other: &DayTotals) {
+        self.minutes_logged += other.minutes_logged;
+        self.mark_count += other.mark_count;
+        self.todos_completed += other.todos_completed;
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DailyStat {
+    pub date: String,
+    #[serde(flatten)]
+    pub totals: DayTotals,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TimerDailyStats {
+    pub timer_id: String,
+    pub timer_name: String,
+    pub days: Vec<DailyStat>,
+    pub total: DayTotals,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DailyStats {
+    pub from_date: String,
+    pub to_date: String,
+    pub utc_offset_minutes: EpochMinutes,
+    pub days: Vec<DailyStat>,
+    pub total: DayTotals,
+    pub timers: Vec<TimerDailyStats>,
+}
+
+pub struct TimerActivity<'a> {
+    pub timer: &'a Timer,
+    pub marks: &'a [Mark],
+    pub todos: &'a [Todo],
+}
+
+pub fn daily_stats(
+    activities: &[TimerActivity<'_>],
+    from_date: &str,
+    to_date: &str,
+    utc_offset_minutes: EpochMinutes,
+) -> AppResult<DailyStats> {
+    let first_day = calendar::parse_day(from_date)?;
+    let last_day = calendar::parse_day(to_date)?;
+    if last_day < first_day {
+        return Err(AppError::Validation(format!(
+            "stats range end {to_date} is before its start {from_date}"
+        )));
+    }
+    if last_day - first_day + 1 > MAX_RANGE_DAYS {
+        return Err(AppError::Validation(format!(
+            "stats range cannot exceed {MAX_RANGE_DAYS} days"
+        )));
+    }
+
+    let range_start = calendar::day_start_minute(first_day, utc_offset_minutes);
+    let range_end = calendar::day_start_minute(last_day + 1, utc_offset_minutes);
+    let in_range = |day: i64| (first_day..=last_day).contains(&day);
+
+    let mut overall: BTreeMap<i64, DayTotals> = BTreeMap::new();
+    let mut timers = Vec::new();
+
+    for activity in activities {
+        let mut per_day: BTreeMap<i64, DayTotals> = BTreeMap::new();
+
+        for mark in activity.marks {
+            let mark_day = calendar::day_index(mark.marked_at_minute, utc_offset_minutes);
+            if in_range(mark_day) {
+                per_day.entry(mark_day).or_default().mark_count += 1;
+            }
+
+            let Some(prev_marked_at_minute) = mark.prev_marked_at_minute else {
+                continue;
+            };
+            let start = prev_marked_at_minute.max(range_start);
+            let end = mark.marked_at_minute.min(range_end);
+            for (day, minutes) in calendar::split_by_day(start, end, utc_offset_minutes) {
+                per_day.entry(day).or_default().minutes_logged += minutes;
+            }
+        }
+
+        for todo in activity.todos {
+            let Some(done_at_minute) = todo.done_at_minute else {
+                continue;
+            };
+            let done_day = calendar::day_index(done_at_minute, utc_offset_minutes);
+            if in_range(done_day) {
+                per_day.entry(done_day).or_default().todos_completed += 1;
+            }
+        }
+
+        if per_day.is_empty() {
+            continue;
+        }
+
+        let mut total = DayTotals::default();
+        for (day, totals) in &per_day {
+            total.add(totals);
+            overall.entry(*day).or_default().add(totals);
+        }
+        timers.push(TimerDailyStats {
+            timer_id: activity.timer.id.clone(),
+            timer_name: activity.timer.name.clone(),
+            days: fill_days(&per_day, first_day, last_day),
+            total,
+        });
+    }
+
+    let mut total = DayTotals::default();
+    for totals in overall.values() {
+        total.add(totals);
+    }
+
+    Ok(DailyStats {
+        from_date: calendar::format_day(first_day),
+        to_date: calendar::format_day(last_day),
+        utc_offset_minutes,
+        days: fill_days(&overall, first_day, last_day),
+        total,
+        timers,
+    })
+}
+
+fn fill_days(per_day: &BTreeMap<i64, DayTotals>, first_day: i64, last_day: i64) -> Vec<DailyStat> {
+    (first_day..=last_day)
+        .map(|day| DailyStat {
+            date: calendar::format_day(day),
+            totals: per_day.get(&day).copied().unwrap_or_default(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{daily_stats, TimerActivity};
+    use crate::calendar::{self, MINUTES_PER_DAY};
+    use crate::model::{Mark, Timer, Todo, TodoStatus};
+
+    #[test]
+    fn splits_minutes_across_local_midnight_and_counts_completions() {
+        let day = calendar::parse_day("2026-10-12").expect("date should parse");
+        let offset = 8 * 60;
+        let local_midnight = calendar::day_start_minute(day + 1, offset);
+
+        let timer = Timer {
+            id: "timer-1".to_string(),
+            name: "sprint".to_string(),
+            target_at_minute: local_midnight + 10 * MINUTES_PER_DAY,
+            created_at_minute: 0,
+            updated_at_minute: 0,
+            archived: false,
+            project_id: None,
+            tags: vec![],
+        };
+        let marks = vec![Mark {
+            id: "mark-1".to_string(),
+            timer_id: timer.id.clone(),
+            marked_at_minute: local_midnight + 30,
+            prev_marked_at_minute: Some(local_midnight - 90),
+            duration_minutes: Some(120),
+            description: "late night".to_string(),
+            todo_ids: vec![],
+            tags: vec![],
+            category_id: None,
+        }];
+        let todos = vec![Todo {
+            id: "todo-1".to_string(),
+            timer_id: timer.id.clone(),
+            title: "ship".to_string(),
+            status: TodoStatus::Done,
+            created_at_minute: 0,
+            updated_at_minute: local_midnight - 5,
+            done_at_minute: Some(local_midnight - 5),
+            tags: vec![],
+        }];
+
+        let stats = daily_stats(
+            &[TimerActivity {
+                timer: &timer,
+                marks: &marks,
+                todos: &todos,
+            }],
+            "2026-10-12",
+            "2026-10-14",
+            offset,
+        )
+        .expect("stats should compute");
+
+        assert_eq!(stats.days.len(), 3);
+        assert_eq!(stats.days[0].date, "2026-10-12");
+        assert_eq!(stats.days[0].totals.minutes_logged, 90);
+        assert_eq!(stats.days[0].totals.mark_count, 0);
+        assert_eq!(stats.days[0].totals.todos_completed, 1);
+        assert_eq!(stats.days[1].totals.minutes_logged, 30);
+        assert_eq!(stats.days[1].totals.mark_count, 1);
+        assert_eq!(stats.days[2].totals.minutes_logged, 0);
+        assert_eq!(stats.total.minutes_logged, 120);
+        assert_eq!(stats.timers.len(), 1);
+        assert_eq!(stats.timers[0].total.mark_count, 1);
+    }
+
+    #[test]
+    fn rejects_reversed_range() {
+        assert!(daily_stats(&[], "2026-10-14", "2026-10-12", 0).is_err());
+    }
+}
@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+
+use serde::Serialize;
+
+use crate::error::{AppError, AppResult};
+use crate::history::Entity;
+
+pub const SYNC_DIR: &str = "sync";
+pub const LOG_SUFFIX: &str = ".log.csv";
+pub const EVENTS_SUFFIX: &str = ".events.csv";
+pub const REMINDERS_SUFFIX: &str = ".reminders.csv";
+pub const LOCK_SUFFIX: &str = ".lock";
+
+pub type EntityKey = (String, String);
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub clock: u64,
+    pub device: String,
+}
+
+impl Version {
+    pub fn parse(value: &str) -> AppResult<Option<Self>> {
+        if value.is_empty() {
+            return Ok(None);
+        }
+        let invalid = || AppError::Internal(format!("invalid sync version '{value}'"));
+        let (clock, device) = value.split_once('@').ok_or_else(invalid)?;
+        Ok(Some(Self {
+            clock: clock.parse().map_err(|_| invalid())?,
+            device: device.to_string(),
+        }))
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{}", self.clock, self.device)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogEntry {
+    pub version: Version,
+    pub sequence: u64,
+    pub parent: Option<Version>,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub state: Option<Entity>,
+}
+
+impl LogEntry {
+    pub fn key(&self) -> EntityKey {
+        (self.entity_type.clone(), self.entity_id.clone())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SyncConflict {
+    pub entity_type: String,
+    pub entity_id: String,
+    pub kept: String,
+    pub discarded: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct SyncReport {
+    pub changed: bool,
+    pub devices: Vec<String>,
+    pub conflicts: Vec<SyncConflict>,
+}
+
+#[derive(Debug, Default)]
+pub struct Resolved {
+    pub states: BTreeMap<EntityKey, Option<Entity>>,
+    pub versions: HashMap<EntityKey, Version>,
+    pub conflicts: Vec<SyncConflict>,
+    pub clock: u64,
+}
+
+pub fn validate_device(device: &str) -> AppResult<()> {
+    if device.is_empty()
+        || !device
+            .chars()
+            .all(|character| character.is_ascii_alphanumeric() || matches!(character, '-' | '_'))
+    {
+        return Err(AppError::Validation(format!(
+            "device name '{device}' may only contain letters, digits, '-' and '_'"
+        )));
+    }
+    Ok(())
+}
+
+pub fn resolve(mut entries: Vec<LogEntry>) -> Resolved {
+    entries.sort_by(|left, right| {
+        (&left.version, left.sequence).cmp(&(&right.version, right.sequence))
+    });
+    let mut resolved = Resolved::default();
+    for entry in entries {
+        let key = entry.key();
+        if let Some(current) = resolved.versions.get(&key) {
+            if entry.parent.as_ref() != Some(current) {
+                resolved.conflicts.push(SyncConflict {
+                    entity_type: entry.entity_type.clone(),
+                    entity_id: entry.entity_id.clone(),
+                    kept: entry.version.to_string(),
+                    discarded: current.to_string(),
+                });
+            }
+        }
+        resolved.clock = resolved.clock.max(entry.version.clock);
+        resolved.versions.insert(key.clone(), entry.version);
+        resolved.states.insert(key, entry.state);
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{resolve, validate_device, LogEntry, Version};
+    use crate::history::Entity;
+    use crate::model::Project;
+
+    fn entry(clock: u64, device: &str, parent: Option<(u64, &str)>, name: &str) -> LogEntry {
+        LogEntry {
+            version: Version {
+                clock,
+                device: device.to_string(),
+            },
+            sequence: clock,
+            parent: parent.map(|(clock, device)| Version {
+                clock,
+                device: device.to_string(),
+            }),
+            entity_type: "project".to_string(),
+            entity_id: "project-1".to_string(),
+            state: Some(Entity::Project(Project {
+                id: "project-1".to_string(),
+                name: name.to_string(),
+                created_at_minute: 0,
+                updated_at_minute: clock as i64,
+            })),
+        }
+    }
+
+    #[test]
+    fn resolves_concurrent_writes_in_clock_then_device_order() {
+        let entries = vec![
+            entry(2, "laptop", Some((1, "desktop")), "laptop edit"),
+            entry(1, "desktop", None, "created"),
+            entry(2, "desktop", Some((1, "desktop")), "desktop edit"),
+            entry(3, "laptop", Some((2, "laptop")), "after merge"),
+        ];
+        let forward = resolve(entries.clone());
+        let backward = resolve(entries.into_iter().rev().collect());
+        assert_eq!(forward.states, backward.states);
+        assert_eq!(forward.clock, 3);
+
+        let Some(Some(Entity::Project(project))) = forward.states.values().next() else {
+            panic!("project should resolve");
+        };
+        assert_eq!(project.name, "after merge");
+        assert_eq!(forward.conflicts.len(), 1);
+        assert_eq!(forward.conflicts[0].kept, "2@laptop");
+        assert_eq!(forward.conflicts[0].discarded, "2@desktop");
+
+        assert_eq!(
+            Version::parse("12@pc-1").expect("version should parse"),
+            Some(Version {
+                clock: 12,
+                device: "pc-1".to_string()
+            })
+        );
+        assert!(validate_device("pc 1").is_err());
+    }
+}
@@ -0,0 +1,158 @@
+use crate::error::{AppError, AppResult};
+
+pub fn normalize_tag(raw: &str) -> AppResult<String> {
+    let tag = raw.trim().trim_start_matches('#').to_lowercase();
+    if tag.is_empty() {
+        return Err(AppError::Validation("tag cannot be empty".to_string()));
+    }
+    if !tag.chars().all(is_tag_char) {
+        return Err(AppError::Validation(format!(
+            "tag '{raw}' may only contain letters, digits, '-', '_' and '/'"
+        )));
+    }
+    Ok(tag)
+}
+
+pub fn normalize_tags(raw_tags: &[String]) -> AppResult<Vec<String>> {
+    let mut tags = Vec::with_capacity(raw_tags.len());
+    for raw in raw_tags {
+        push_unique(&mut tags, normalize_tag(raw)?);
+    }
+    Ok(tags)
+}
+
+pub fn sanitize_tags<'a>(raw_tags: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut tags = Vec::new();
+    for raw in raw_tags {
+        let joined = raw.split_whitespace().collect::<Vec<&str>>().join("-");
+        if let Ok(tag) = normalize_tag(&joined) {
+            push_unique(&mut tags, tag);
+        }
+    }
+    tags
+}
+
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    for (start, end) in hashtag_spans(text) {
+        push_unique(&mut tags, text[start + 1..end].to_lowercase());
+    }
+    tags
+}
+
+pub fn merge_tags(left: &[String], right: &[String]) -> Vec<String> {
+    let mut tags = left.to_vec();
+    for tag in right {
+        push_unique(&mut tags, tag.clone());
+    }
+    tags
+}
+
+pub fn replace_tag(tags: &[String], from: &str, to: &str) -> Vec<String> {
+    let mut replaced = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = if tag == from { to } else { tag.as_str() };
+        push_unique(&mut replaced, tag.to_string());
+    }
+    replaced
+}
+
+pub fn replace_tag_in_text(text: &str, from: &str, to: &str) -> String {
+    let mut replaced = String::with_capacity(text.len());
+    let mut cursor = 0;
+    for (start, end) in hashtag_spans(text) {
+        if text[start + 1..end].to_lowercase() == from {
+            replaced.push_str(&text[cursor..start]);
+            replaced.push('#');
+            replaced.push_str(to);
+            cursor = end;
+        }
+    }
+    replaced.push_str(&text[cursor..]);
+    replaced
+}
+
+fn hashtag_spans(text: &str) -> Vec<(usize, usize)> {
+    let mut spans = Vec::new();
+    let mut previous: Option<char> = None;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((index, character)) = chars.next() {
+        let at_boundary = previous.is_none_or(|value| !is_tag_char(value) && value != '#');
+        previous = Some(character);
+        if character != '#' || !at_boundary {
+            continue;
+        }
+
+        let start = index;
+        let mut end = index + 1;
+        while let Some(&(next_index, next)) = chars.peek() {
+            if !is_tag_char(next) {
+                break;
+            }
+            end = next_index + next.len_utf8();
+            previous = Some(next);
+            let _ = chars.next();
+        }
+
+        let body = text[start + 1..end].trim_end_matches(['-', '_', '/']);
+        if !body.is_empty() {
+            spans.push((start, start + 1 + body.len()));
+        }
+    }
+
+    spans
+}
+
+fn is_tag_char(character: char) -> bool {
+    character.is_alphanumeric() || matches!(character, '-' | '_' | '/')
+}
+
+fn push_unique(tags: &mut Vec<String>, tag: String) {
+    if !tags.contains(&tag) {
+        tags.push(tag);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_tag, parse_tags, replace_tag_in_text, sanitize_tags};
+
+    #[test]
+    fn parses_hashtags_from_text() {
+        let tags = parse_tags("fixed #Backend login, needs #review. see #后端 and #review again");
+        assert_eq!(tags, vec!["backend", "review", "后端"]);
+    }
+
+    #[test]
+    fn ignores_hashes_inside_words_and_headings() {
+        assert!(parse_tags("# Heading\nwrote C# code, issue#12, ## notes").is_empty());
+        assert_eq!(parse_tags("(#ops) #release-"), vec!["ops", "release"]);
+    }
+
+    #[test]
+    fn normalizes_explicit_tags() {
+        assert_eq!(
+            normalize_tag(" #Review ").expect("tag should normalize"),
+            "review"
+        );
+        assert!(normalize_tag("#").is_err());
+        assert!(normalize_tag("two words").is_err());
+    }
+
+    #[test]
+    fn rewrites_hashtags_in_text() {
+        assert_eq!(
+            replace_tag_in_text("needs #Review, not #reviewer", "review", "code-review"),
+            "needs #code-review, not #reviewer"
+        );
+    }
+
+    #[test]
+    fn sanitizes_foreign_tags() {
+        assert_eq!(
+            sanitize_tags(["Deep  Work", "ui", "UI", "a+b", " "]),
+            vec!["deep-work", "ui"]
+        );
+    }
+}
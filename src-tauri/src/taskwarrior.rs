@@ -0,0 +1,273 @@
+use serde::{Deserialize, Serialize};
+
+use crate::calendar;
+use crate::error::{AppError, AppResult};
+use crate::model::{EpochMinutes, Timer, Todo, TodoStatus};
+use crate::stats::TimerActivity;
+use crate::tag;
+use crate::timesheet::SkippedRow;
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct Task {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uuid: Option<String>,
+    #[serde(default)]
+    pub description: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entry: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskEntry {
+    pub row: usize,
+    pub uuid: Option<String>,
+    pub title: String,
+    pub status: TodoStatus,
+    pub created_at_minute: Option<EpochMinutes>,
+    pub updated_at_minute: Option<EpochMinutes>,
+    pub done_at_minute: Option<EpochMinutes>,
+    pub due_minute: Option<EpochMinutes>,
+    pub project: String,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ParsedTasks {
+    pub entries: Vec<TaskEntry>,
+    pub skipped: Vec<SkippedRow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct TaskImportReport {
+    pub created_timers: Vec<Timer>,
+    pub todos: Vec<Todo>,
+    pub skipped: Vec<SkippedRow>,
+}
+
+pub fn parse(text: &str) -> AppResult<ParsedTasks> {
+    let text = text.trim();
+    let tasks: Vec<(usize, Task)> = if text.starts_with('[') {
+        serde_json::from_str::<Vec<Task>>(text)
+            .map_err(json_error)?
+            .into_iter()
+            .enumerate()
+            .map(|(index, task)| (index + 1, task))
+            .collect()
+    } else {
+        text.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(index, line)| {
+                serde_json::from_str::<Task>(line.trim().trim_end_matches(','))
+                    .map(|task| (index + 1, task))
+                    .map_err(json_error)
+            })
+            .collect::<AppResult<_>>()?
+    };
+
+    let mut parsed = ParsedTasks::default();
+    for (row, task) in tasks {
+        match parse_task(row, task) {
+            Ok(entry) => parsed.entries.push(entry),
+            Err(AppError::Validation(reason)) => parsed.skipped.push(SkippedRow { row, reason }),
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(parsed)
+}
+
+pub fn render(activities: &[TimerActivity<'_>]) -> AppResult<String> {
+    let tasks: Vec<Task> = activities
+        .iter()
+        .flat_map(|activity| {
+            activity
+                .todos
+                .iter()
+                .map(|todo| task_from_todo(activity.timer, todo))
+        })
+        .collect();
+    let mut json = serde_json::to_string_pretty(&tasks)
+        .map_err(|error| AppError::Internal(format!("failed to encode tasks: {error}")))?;
+    json.push('\n');
+    Ok(json)
+}
+
+pub fn stable_uuid(entity_id: &str) -> String {
+    let high = fnv1a(entity_id.as_bytes(), 0xcbf2_9ce4_8422_2325);
+    let low = fnv1a(entity_id.as_bytes(), high);
+    let mut value = (u128::from(high) << 64) | u128::from(low);
+    value = (value & !(0xf << 76)) | (0x8 << 76);
+    value = (value & !(0b11 << 62)) | (0b10 << 62);
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        value >> 96,
+        (value >> 80) & 0xffff,
+        (value >> 64) & 0xffff,
+        (value >> 48) & 0xffff,
+        value & 0xffff_ffff_ffff
+    )
+}
+
+fn task_from_todo(timer: &Timer, todo: &Todo) -> Task {
+    let done = todo.status == TodoStatus::Done;
+    Task {
+        uuid: Some(stable_uuid(&todo.id)),
+        description: todo.title.clone(),
+        status: if done { "completed" } else { "pending" }.to_string(),
+        entry: Some(calendar::format_utc_stamp(todo.created_at_minute)),
+        modified: Some(calendar::format_utc_stamp(todo.updated_at_minute)),
+        end: todo
+            .done_at_minute
+            .filter(|_| done)
+            .map(calendar::format_utc_stamp),
+        due: Some(calendar::format_utc_stamp(timer.target_at_minute)),
+        project: Some(timer.name.clone()),
+        tags: todo.tags.clone(),
+    }
+}
+
+fn parse_task(row: usize, task: Task) -> AppResult<TaskEntry> {
+    let status = match task.status.as_str() {
+        "" | "pending" | "waiting" => TodoStatus::Open,
+        "completed" => TodoStatus::Done,
+        "deleted" => return Err(AppError::Validation("task is deleted".to_string())),
+        "recurring" => {
+            return Err(AppError::Validation(
+                "recurring task templates are not imported".to_string(),
+            ))
+        }
+        other => {
+            return Err(AppError::Validation(format!(
+                "unknown task status '{other}'"
+            )))
+        }
+    };
+    let title = task.description.trim().to_string();
+    if title.is_empty() {
+        return Err(AppError::Validation("task has no description".to_string()));
+    }
+    let stamp = |value: Option<String>| value.as_deref().map(calendar::parse_utc_stamp).transpose();
+    let tags = tag::sanitize_tags(task.tags.iter().map(String::as_str));
+    let done_at_minute = stamp(task.end)?.filter(|_| status == TodoStatus::Done);
+
+    Ok(TaskEntry {
+        row,
+        uuid: task.uuid,
+        status,
+        created_at_minute: stamp(task.entry)?,
+        updated_at_minute: stamp(task.modified)?,
+        done_at_minute,
+        due_minute: stamp(task.due)?,
+        project: task.project.unwrap_or_default().trim().to_string(),
+        tags: tag::merge_tags(&tags, &tag::parse_tags(&title)),
+        title,
+    })
+}
+
+fn fnv1a(bytes: &[u8], seed: u64) -> u64 {
+    bytes.iter().fold(seed, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn json_error(error: serde_json::Error) -> AppError {
+    AppError::Validation(format!("invalid Taskwarrior JSON: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, render, stable_uuid};
+    use crate::model::{Timer, Todo, TodoStatus};
+    use crate::stats::TimerActivity;
+
+    #[test]
+    fn round_trips_todos_through_task_json() {
+        let timer = Timer {
+            id: "timer-1".to_string(),
+            name: "release".to_string(),
+            target_at_minute: 10_000,
+            created_at_minute: 0,
+            updated_at_minute: 0,
+            archived: false,
+            project_id: None,
+            tags: vec![],
+        };
+        let todos = vec![
+            Todo {
+                id: "todo-2".to_string(),
+                timer_id: "timer-1".to_string(),
+                title: "write docs".to_string(),
+                status: TodoStatus::Open,
+                created_at_minute: 60,
+                updated_at_minute: 90,
+                done_at_minute: None,
+                tags: vec!["docs".to_string()],
+            },
+            Todo {
+                id: "todo-3".to_string(),
+                timer_id: "timer-1".to_string(),
+                title: "ship".to_string(),
+                status: TodoStatus::Done,
+                created_at_minute: 60,
+                updated_at_minute: 150,
+                done_at_minute: Some(150),
+                tags: vec![],
+            },
+        ];
+        let activities = [TimerActivity {
+            timer: &timer,
+            marks: &[],
+            todos: &todos,
+        }];
+
+        let json = render(&activities).expect("tasks should encode");
+        assert!(json.contains("\"due\": \"19700107T224000Z\""));
+        let parsed = parse(&json).expect("tasks should parse");
+        assert!(parsed.skipped.is_empty());
+        for (entry, todo) in parsed.entries.iter().zip(&todos) {
+            assert_eq!(entry.uuid.as_deref(), Some(stable_uuid(&todo.id).as_str()));
+            assert_eq!(entry.title, todo.title);
+            assert_eq!(entry.status, todo.status);
+            assert_eq!(entry.created_at_minute, Some(todo.created_at_minute));
+            assert_eq!(entry.updated_at_minute, Some(todo.updated_at_minute));
+            assert_eq!(entry.done_at_minute, todo.done_at_minute);
+            assert_eq!(entry.due_minute, Some(timer.target_at_minute));
+            assert_eq!(entry.project, "release");
+            assert_eq!(entry.tags, todo.tags);
+        }
+    }
+
+    #[test]
+    fn parses_line_delimited_export_and_skips_unsupported_tasks() {
+        let parsed = parse(
+            "{\"description\":\"call +mom\",\"status\":\"waiting\",\"tags\":[\"Phone\",\"a+b\"]}\n\
+             {\"description\":\"old\",\"status\":\"deleted\"}\n\
+             {\"description\":\"weekly\",\"status\":\"recurring\"}\n\
+             {\"description\":\"bad\",\"status\":\"pending\",\"entry\":\"yesterday\"}\n",
+        )
+        .expect("tasks should parse");
+        assert_eq!(parsed.entries.len(), 1);
+        assert_eq!(parsed.entries[0].status, TodoStatus::Open);
+        assert_eq!(parsed.entries[0].tags, vec!["phone"]);
+        let rows: Vec<usize> = parsed.skipped.iter().map(|skipped| skipped.row).collect();
+        assert_eq!(rows, vec![2, 3, 4]);
+
+        assert!(parse("[{\"description\": 5}]").is_err());
+        let uuid = stable_uuid("todo-2");
+        assert_eq!(uuid.len(), 36);
+        assert_eq!(&uuid[14..15], "8");
+        assert_ne!(uuid, stable_uuid("todo-3"));
+    }
+}
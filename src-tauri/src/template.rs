@@ -0,0 +1,160 @@
+use crate::error::{AppError, AppResult};
+use crate::model::{EpochMinutes, Todo};
+
+pub const PLACEHOLDERS: [&str; 4] = ["timer", "duration", "open_todos", "done_since_last_mark"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+pub struct TemplateContext<'a> {
+    pub timer_name: &'a str,
+    pub duration_minutes: Option<EpochMinutes>,
+    pub open_todos: &'a [Todo],
+    pub done_since_last_mark: &'a [Todo],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedTemplate {
+    pub description: String,
+    pub todo_ids: Vec<String>,
+}
+
+pub fn validate_template(body: &str) -> AppResult<()> {
+    parse_segments(body).map(|_| ())
+}
+
+pub fn render_template(body: &str, context: &TemplateContext<'_>) -> AppResult<RenderedTemplate> {
+    let mut description = String::with_capacity(body.len());
+    let mut todo_ids: Vec<String> = Vec::new();
+
+    for segment in parse_segments(body)? {
+        match segment {
+            Segment::Text(text) => description.push_str(text),
+            Segment::Placeholder("timer") => description.push_str(context.timer_name),
+            Segment::Placeholder("duration") => match context.duration_minutes {
+                Some(minutes) => description.push_str(&format_duration(minutes)),
+                None => description.push('-'),
+            },
+            Segment::Placeholder("open_todos") => {
+                push_todo_lines(&mut description, &mut todo_ids, context.open_todos)
+            }
+            Segment::Placeholder(_) => push_todo_lines(
+                &mut description,
+                &mut todo_ids,
+                context.done_since_last_mark,
+            ),
+        }
+    }
+
+    Ok(RenderedTemplate {
+        description,
+        todo_ids,
+    })
+}
+
+pub fn format_duration(total_minutes: EpochMinutes) -> String {
+    let minutes = total_minutes.abs();
+    if minutes < 60 {
+        return format!("{minutes}分钟");
+    }
+    let hours = minutes / 60;
+    let rest = minutes % 60;
+    if rest == 0 {
+        format!("{hours}小时")
+    } else {
+        format!("{hours}小时{rest}分")
+    }
+}
+
+fn push_todo_lines(description: &mut String, todo_ids: &mut Vec<String>, todos: &[Todo]) {
+    let lines: Vec<String> = todos
+        .iter()
+        .map(|todo| format!("- {}", todo.title))
+        .collect();
+    description.push_str(&lines.join("\n"));
+    for todo in todos {
+        if !todo_ids.contains(&todo.id) {
+            todo_ids.push(todo.id.clone());
+        }
+    }
+}
+
+fn parse_segments(body: &str) -> AppResult<Vec<Segment<'_>>> {
+    let mut segments = Vec::new();
+    let mut rest = body;
+
+    while let Some(open) = rest.find('{') {
+        if open > 0 {
+            segments.push(Segment::Text(&rest[..open]));
+        }
+        let after_open = &rest[open + 1..];
+        let close = after_open.find('}').ok_or_else(|| {
+            AppError::Validation("template has an unclosed '{' placeholder".to_string())
+        })?;
+        let name = &after_open[..close];
+        if !PLACEHOLDERS.contains(&name) {
+            return Err(AppError::Validation(format!(
+                "unknown template placeholder '{{{name}}}', expected one of {}",
+                PLACEHOLDERS.join(", ")
+            )));
+        }
+        segments.push(Segment::Placeholder(name));
+        rest = &after_open[close + 1..];
+    }
+
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+    Ok(segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render_template, validate_template, TemplateContext};
+    use crate::model::{Todo, TodoStatus};
+
+    fn todo(id: &str, title: &str) -> Todo {
+        Todo {
+            id: id.to_string(),
+            timer_id: "timer-1".to_string(),
+            title: title.to_string(),
+            status: TodoStatus::Open,
+            created_at_minute: 0,
+            updated_at_minute: 0,
+            done_at_minute: None,
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn renders_placeholders_and_collects_todo_ids() {
+        let open = vec![todo("todo-1", "write tests")];
+        let done = vec![todo("todo-2", "fix login")];
+        let rendered = render_template(
+            "[{timer}] {duration}\n{done_since_last_mark}\nnext:\n{open_todos}",
+            &TemplateContext {
+                timer_name: "release",
+                duration_minutes: Some(95),
+                open_todos: &open,
+                done_since_last_mark: &done,
+            },
+        )
+        .expect("template should render");
+
+        assert_eq!(
+            rendered.description,
+            "[release] 1小时35分\n- fix login\nnext:\n- write tests"
+        );
+        assert_eq!(rendered.todo_ids, vec!["todo-2", "todo-1"]);
+    }
+
+    #[test]
+    fn rejects_unknown_or_unclosed_placeholders() {
+        assert!(validate_template("{timer} {mood}").is_err());
+        assert!(validate_template("{timer").is_err());
+        assert!(validate_template("plain text").is_ok());
+    }
+}
@@ -0,0 +1,248 @@
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::calendar::MINUTES_PER_DAY;
+use crate::error::{AppError, AppResult};
+use crate::model::{EpochMinutes, Mark, Timer, Todo, TodoStatus};
+
+pub const MAX_RANGE_MINUTES: EpochMinutes = 366 * MINUTES_PER_DAY;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TimelineKind {
+    TimerCreated,
+    TodoCreated,
+    MarkRecorded,
+    TodoCompleted,
+    TimerArchived,
+    Deadline,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TimelineEntry {
+    pub at_minute: EpochMinutes,
+    pub kind: TimelineKind,
+    pub timer_id: String,
+    pub timer_name: String,
+    pub entity_id: String,
+    pub title: String,
+    pub duration_minutes: Option<EpochMinutes>,
+}
+
+pub struct TimelineSource<'a> {
+    pub timers: &'a [Timer],
+    pub todos: &'a [Todo],
+    pub marks: &'a [Mark],
+    pub archived_at: &'a HashMap<String, EpochMinutes>,
+}
+
+pub fn validate_range(since_minute: EpochMinutes, until_minute: EpochMinutes) -> AppResult<()> {
+    if since_minute >= until_minute {
+        return Err(AppError::Validation(format!(
+            "timeline range start {since_minute} must be before end {until_minute}"
+        )));
+    }
+    if until_minute - since_minute > MAX_RANGE_MINUTES {
+        return Err(AppError::Validation(format!(
+            "timeline range cannot exceed {} days",
+            MAX_RANGE_MINUTES / MINUTES_PER_DAY
+        )));
+    }
+    Ok(())
+}
+
+pub fn build(
+    source: &TimelineSource<'_>,
+    since_minute: EpochMinutes,
+    until_minute: EpochMinutes,
+) -> AppResult<Vec<TimelineEntry>> {
+    validate_range(since_minute, until_minute)?;
+    let in_range = |minute: EpochMinutes| since_minute <= minute && minute < until_minute;
+    let timers: HashMap<&str, &Timer> = source
+        .timers
+        .iter()
+        .map(|timer| (timer.id.as_str(), timer))
+        .collect();
+    let mut entries = Vec::new();
+    let mut push = |timer: &Timer, at_minute, kind, entity_id: &str, title: &str, duration| {
+        entries.push(TimelineEntry {
+            at_minute,
+            kind,
+            timer_id: timer.id.clone(),
+            timer_name: timer.name.clone(),
+            entity_id: entity_id.to_string(),
+            title: title.to_string(),
+            duration_minutes: duration,
+        });
+    };
+
+    for timer in source.timers {
+        if in_range(timer.created_at_minute) {
+            push(
+                timer,
+                timer.created_at_minute,
+                TimelineKind::TimerCreated,
+                &timer.id,
+                &timer.name,
+                None,
+            );
+        }
+        if in_range(timer.target_at_minute) {
+            push(
+                timer,
+                timer.target_at_minute,
+                TimelineKind::Deadline,
+                &timer.id,
+                &timer.name,
+                None,
+            );
+        }
+        if timer.archived {
+            let archived_at = source
+                .archived_at
+                .get(&timer.id)
+                .copied()
+                .unwrap_or(timer.updated_at_minute);
+            if in_range(archived_at) {
+                push(
+                    timer,
+                    archived_at,
+                    TimelineKind::TimerArchived,
+                    &timer.id,
+                    &timer.name,
+                    None,
+                );
+            }
+        }
+    }
+
+    for todo in source.todos {
+        let Some(timer) = timers.get(todo.timer_id.as_str()) else {
+            continue;
+        };
+        if in_range(todo.created_at_minute) {
+            push(
+                timer,
+                todo.created_at_minute,
+                TimelineKind::TodoCreated,
+                &todo.id,
+                &todo.title,
+                None,
+            );
+        }
+        if let (TodoStatus::Done, Some(done_at)) = (&todo.status, todo.done_at_minute) {
+            if in_range(done_at) {
+                push(
+                    timer,
+                    done_at,
+                    TimelineKind::TodoCompleted,
+                    &todo.id,
+                    &todo.title,
+                    None,
+                );
+            }
+        }
+    }
+
+    for mark in source.marks {
+        let Some(timer) = timers.get(mark.timer_id.as_str()) else {
+            continue;
+        };
+        if in_range(mark.marked_at_minute) {
+            push(
+                timer,
+                mark.marked_at_minute,
+                TimelineKind::MarkRecorded,
+                &mark.id,
+                &mark.description,
+                mark.duration_minutes,
+            );
+        }
+    }
+
+    entries.sort_by(|left, right| {
+        left.at_minute
+            .cmp(&right.at_minute)
+            .then(left.kind.cmp(&right.kind))
+            .then(left.timer_id.cmp(&right.timer_id))
+            .then(left.entity_id.cmp(&right.entity_id))
+    });
+    Ok(entries)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{build, TimelineKind, TimelineSource};
+    use crate::model::{Mark, Timer, Todo, TodoStatus};
+
+    fn timer(id: &str, created: i64, target: i64, archived: bool) -> Timer {
+        Timer {
+            id: id.to_string(),
+            name: format!("{id} name"),
+            target_at_minute: target,
+            created_at_minute: created,
+            updated_at_minute: created,
+            archived,
+            project_id: None,
+            tags: vec![],
+        }
+    }
+
+    #[test]
+    fn merges_activity_across_timers_in_order() {
+        let timers = vec![
+            timer("timer-1", 10, 500, false),
+            timer("timer-2", 20, 90, true),
+        ];
+        let todos = vec![Todo {
+            id: "todo-3".to_string(),
+            timer_id: "timer-2".to_string(),
+            title: "ship".to_string(),
+            status: TodoStatus::Done,
+            created_at_minute: 30,
+            updated_at_minute: 60,
+            done_at_minute: Some(60),
+            tags: vec![],
+        }];
+        let marks = vec![Mark {
+            id: "mark-4".to_string(),
+            timer_id: "timer-1".to_string(),
+            marked_at_minute: 60,
+            prev_marked_at_minute: None,
+            duration_minutes: Some(50),
+            description: "drafted".to_string(),
+            todo_ids: vec![],
+            tags: vec![],
+            category_id: None,
+        }];
+        let archived_at = HashMap::from([("timer-2".to_string(), 95)]);
+        let source = TimelineSource {
+            timers: &timers,
+            todos: &todos,
+            marks: &marks,
+            archived_at: &archived_at,
+        };
+
+        let entries = build(&source, 15, 100).expect("timeline should build");
+        let summary: Vec<(i64, TimelineKind, &str)> = entries
+            .iter()
+            .map(|entry| (entry.at_minute, entry.kind, entry.entity_id.as_str()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (20, TimelineKind::TimerCreated, "timer-2"),
+                (30, TimelineKind::TodoCreated, "todo-3"),
+                (60, TimelineKind::MarkRecorded, "mark-4"),
+                (60, TimelineKind::TodoCompleted, "todo-3"),
+                (90, TimelineKind::Deadline, "timer-2"),
+                (95, TimelineKind::TimerArchived, "timer-2"),
+            ]
+        );
+        assert_eq!(entries[2].timer_name, "timer-1 name");
+        assert!(build(&source, 100, 100).is_err());
+    }
+}
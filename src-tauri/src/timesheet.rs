@@ -0,0 +1,252 @@
+use serde::Serialize;
+
+use crate::calendar;
+use crate::error::{AppError, AppResult};
+use crate::model::{EpochMinutes, Mark, Timer};
+use crate::repository::{parse_csv_line, split_csv_records};
+use crate::tag;
+
+pub const NO_PROJECT_TIMER_NAME: &str = "无项目";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeEntry {
+    pub row: usize,
+    pub project: String,
+    pub description: String,
+    pub start_minute: EpochMinutes,
+    pub end_minute: EpochMinutes,
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedRow {
+    pub row: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ParsedTimesheet {
+    pub entries: Vec<TimeEntry>,
+    pub skipped: Vec<SkippedRow>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize)]
+pub struct TimesheetReport {
+    pub created_timers: Vec<Timer>,
+    pub marks: Vec<Mark>,
+    pub skipped: Vec<SkippedRow>,
+}
+
+struct Columns {
+    project: Option<usize>,
+    description: Option<usize>,
+    task: Option<usize>,
+    tags: Option<usize>,
+    start_date: usize,
+    start_time: usize,
+    end_date: usize,
+    end_time: usize,
+}
+
+impl Columns {
+    fn locate(header: &[String]) -> AppResult<Self> {
+        let find = |name: &str| {
+            header
+                .iter()
+                .position(|column| column.trim().eq_ignore_ascii_case(name))
+        };
+        let require = |name: &str| {
+            find(name).ok_or_else(|| {
+                AppError::Validation(format!("time entry csv is missing column '{name}'"))
+            })
+        };
+        Ok(Self {
+            project: find("project"),
+            description: find("description"),
+            task: find("task"),
+            tags: find("tags"),
+            start_date: require("start date")?,
+            start_time: require("start time")?,
+            end_date: require("end date")?,
+            end_time: require("end time")?,
+        })
+    }
+}
+
+pub fn parse(text: &str, utc_offset_minutes: EpochMinutes) -> AppResult<ParsedTimesheet> {
+    let text = text.trim_start_matches('\u{feff}');
+    let records = split_csv_records(text).map_err(csv_error)?;
+    let mut records = records
+        .iter()
+        .enumerate()
+        .filter(|(_, record)| !record.trim().is_empty());
+    let Some((_, header)) = records.next() else {
+        return Err(AppError::Validation("time entry csv is empty".to_string()));
+    };
+    let columns = Columns::locate(&parse_csv_line(header).map_err(csv_error)?)?;
+
+    let mut parsed = ParsedTimesheet::default();
+    for (index, record) in records {
+        let row = index + 1;
+        let result = parse_csv_line(record)
+            .map_err(csv_error)
+            .and_then(|fields| parse_entry(&columns, &fields, row, utc_offset_minutes));
+        match result {
+            Ok(entry) => parsed.entries.push(entry),
+            Err(error) => parsed.skipped.push(SkippedRow {
+                row,
+                reason: match error {
+                    AppError::Validation(message) => message,
+                    other => other.to_string(),
+                },
+            }),
+        }
+    }
+    Ok(parsed)
+}
+
+fn parse_entry(
+    columns: &Columns,
+    fields: &[String],
+    row: usize,
+    utc_offset_minutes: EpochMinutes,
+) -> AppResult<TimeEntry> {
+    let field = |index: Option<usize>| {
+        index
+            .and_then(|index| fields.get(index))
+            .map_or("", |value| value.trim())
+    };
+    let moment = |date_index: usize, time_index: usize| -> AppResult<EpochMinutes> {
+        let date = field(Some(date_index));
+        let time = field(Some(time_index));
+        if date.is_empty() || time.is_empty() {
+            return Err(AppError::Validation(
+                "entry is missing a start or end time".to_string(),
+            ));
+        }
+        Ok(calendar::day_start_minute(parse_date(date)?, utc_offset_minutes) + parse_time(time)?)
+    };
+
+    let start_minute = moment(columns.start_date, columns.start_time)?;
+    let end_minute = moment(columns.end_date, columns.end_time)?;
+    if end_minute <= start_minute {
+        return Err(AppError::Validation(
+            "entry ends before it starts or lasts less than a minute".to_string(),
+        ));
+    }
+
+    let description = match field(columns.description) {
+        "" => field(columns.task),
+        description => description,
+    };
+    let tags = tag::sanitize_tags(field(columns.tags).split(','));
+
+    Ok(TimeEntry {
+        row,
+        project: field(columns.project).to_string(),
+        description: description.to_string(),
+        start_minute,
+        end_minute,
+        tags,
+    })
+}
+
+fn parse_date(value: &str) -> AppResult<i64> {
+    let invalid = || AppError::Validation(format!("invalid date '{value}'"));
+    let parts: Vec<&str> = value.split(['/', '.']).collect();
+    match parts.as_slice() {
+        [_] => calendar::parse_day(value),
+        [month, day, year] if value.contains('/') => {
+            calendar::parse_day(&format!("{year}-{month}-{day}")).map_err(|_| invalid())
+        }
+        [day, month, year] => {
+            calendar::parse_day(&format!("{year}-{month}-{day}")).map_err(|_| invalid())
+        }
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_time(value: &str) -> AppResult<EpochMinutes> {
+    let invalid = || AppError::Validation(format!("invalid time '{value}'"));
+    let upper = value.to_ascii_uppercase();
+    let (clock, meridiem) = match upper.split_once(' ') {
+        Some((clock, meridiem)) => (clock, Some(meridiem.trim())),
+        None => (upper.as_str(), None),
+    };
+    let mut parts = clock.split(':');
+    let hour: EpochMinutes = parts
+        .next()
+        .and_then(|part| part.parse().ok())
+        .ok_or_else(invalid)?;
+    let minute: EpochMinutes = parts
+        .next()
+        .and_then(|part| part.parse().ok())
+        .ok_or_else(invalid)?;
+    if parts
+        .next()
+        .is_some_and(|part| part.parse::<u32>().is_err())
+        || parts.next().is_some()
+    {
+        return Err(invalid());
+    }
+    let hour = match (meridiem, hour) {
+        (None, 0..=23) => hour,
+        (Some("AM"), 12) => 0,
+        (Some("PM"), 12) => 12,
+        (Some("AM"), 1..=11) => hour,
+        (Some("PM"), 1..=11) => hour + 12,
+        _ => return Err(invalid()),
+    };
+    if !(0..60).contains(&minute) {
+        return Err(invalid());
+    }
+    Ok(hour * 60 + minute)
+}
+
+fn csv_error(error: AppError) -> AppError {
+    AppError::Validation(format!("invalid csv: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse;
+    use crate::calendar;
+
+    #[test]
+    fn parses_toggl_and_clockify_exports() {
+        let day = calendar::parse_day("2026-10-12").expect("date should parse") * 24 * 60 - 480;
+
+        let toggl = parse(
+            "User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags,Amount ()\n\
+             ann,a@x.io,,Website,,\"Fix header, footer\",No,2026-10-12,09:00:00,2026-10-12,10:30:00,01:30:00,\"deep work, UI\",\n\
+             ann,a@x.io,,,,Email,No,2026-10-12,23:30:00,2026-10-13,00:15:00,00:45:00,,\n\
+             ann,a@x.io,,Website,,Running,No,2026-10-12,11:00:00,,,,,\n",
+            480,
+        )
+        .expect("toggl csv should parse");
+        assert_eq!(toggl.entries.len(), 2);
+        assert_eq!(toggl.entries[0].project, "Website");
+        assert_eq!(toggl.entries[0].description, "Fix header, footer");
+        assert_eq!(toggl.entries[0].start_minute, day + 9 * 60);
+        assert_eq!(toggl.entries[0].end_minute, day + 10 * 60 + 30);
+        assert_eq!(toggl.entries[0].tags, vec!["deep-work", "ui"]);
+        assert_eq!(toggl.entries[1].end_minute, day + 24 * 60 + 15);
+        assert_eq!(toggl.skipped.len(), 1);
+        assert_eq!(toggl.skipped[0].row, 4);
+
+        let clockify = parse(
+            "\u{feff}Project,Client,Description,Task,User,Group,Email,Tags,Billable,Start Date,Start Time,End Date,End Time,Duration (h),Duration (decimal)\n\
+             Website,,,Review,ann,,a@x.io,,Yes,10/12/2026,01:05:00 PM,10/12/2026,02:00 PM,00:55:00,0.92\n\
+             Website,,Bad,,ann,,a@x.io,,Yes,13/40/2026,01:00 PM,10/12/2026,02:00 PM,01:00:00,1.00\n",
+            480,
+        )
+        .expect("clockify csv should parse");
+        assert_eq!(clockify.entries.len(), 1);
+        assert_eq!(clockify.entries[0].description, "Review");
+        assert_eq!(clockify.entries[0].start_minute, day + 13 * 60 + 5);
+        assert_eq!(clockify.entries[0].end_minute, day + 14 * 60);
+        assert!(clockify.skipped[0].reason.contains("13/40/2026"));
+
+        assert!(parse("Project,Description\nx,y\n", 0).is_err());
+    }
+}
@@ -0,0 +1,307 @@
+use serde::Deserialize;
+
+use crate::calendar;
+use crate::error::{AppError, AppResult};
+use crate::model::{EpochMinutes, Mark};
+use crate::stats::TimerActivity;
+use crate::timesheet::SkippedRow;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interval {
+    pub row: usize,
+    pub start_minute: EpochMinutes,
+    pub end_minute: EpochMinutes,
+    pub tags: Vec<String>,
+    pub annotation: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ParsedIntervals {
+    pub entries: Vec<Interval>,
+    pub skipped: Vec<SkippedRow>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ExportedInterval {
+    start: String,
+    end: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    annotation: String,
+}
+
+pub fn parse(text: &str) -> AppResult<ParsedIntervals> {
+    let text = text.trim();
+    let mut parsed = ParsedIntervals::default();
+    let mut push = |row: usize, result: AppResult<Interval>| match result {
+        Ok(interval) => {
+            parsed.entries.push(interval);
+            Ok(())
+        }
+        Err(AppError::Validation(reason)) => {
+            parsed.skipped.push(SkippedRow { row, reason });
+            Ok(())
+        }
+        Err(error) => Err(error),
+    };
+
+    if text.starts_with('[') {
+        let intervals: Vec<ExportedInterval> = serde_json::from_str(text)
+            .map_err(|error| AppError::Validation(format!("invalid Timewarrior JSON: {error}")))?;
+        for (index, interval) in intervals.into_iter().enumerate() {
+            push(index + 1, from_export(index + 1, interval))?;
+        }
+    } else {
+        for (index, line) in text.lines().enumerate() {
+            if !line.trim().is_empty() {
+                push(index + 1, parse_line(index + 1, line))?;
+            }
+        }
+    }
+    Ok(parsed)
+}
+
+pub fn render(activities: &[TimerActivity<'_>]) -> String {
+    let mut segments: Vec<(&str, &Mark)> = activities
+        .iter()
+        .flat_map(|activity| {
+            activity
+                .marks
+                .iter()
+                .filter(|mark| mark.prev_marked_at_minute.is_some())
+                .map(|mark| (activity.timer.name.as_str(), mark))
+        })
+        .collect();
+    segments.sort_by_key(|(_, mark)| (mark.prev_marked_at_minute, mark.marked_at_minute));
+
+    let mut output = String::new();
+    for (timer_name, mark) in segments {
+        let Some(start) = mark.prev_marked_at_minute else {
+            continue;
+        };
+        output.push_str(&format!(
+            "inc {} - {} #",
+            calendar::format_utc_stamp(start),
+            calendar::format_utc_stamp(mark.marked_at_minute)
+        ));
+        for tag in std::iter::once(timer_name).chain(mark.tags.iter().map(String::as_str)) {
+            output.push(' ');
+            output.push_str(&quote_if_needed(tag));
+        }
+        let description = mark.description.trim();
+        if !description.is_empty() {
+            output.push_str(&format!(" # {}", quote(description)));
+        }
+        output.push('\n');
+    }
+    output
+}
+
+fn from_export(row: usize, interval: ExportedInterval) -> AppResult<Interval> {
+    let end = interval
+        .end
+        .ok_or_else(|| AppError::Validation("interval is still open".to_string()))?;
+    build(
+        row,
+        &interval.start,
+        &end,
+        interval.tags,
+        interval.annotation,
+    )
+}
+
+fn parse_line(row: usize, line: &str) -> AppResult<Interval> {
+    let (range, rest) = match line.split_once(" #") {
+        Some((range, rest)) => (range, rest),
+        None => (line, ""),
+    };
+    let range: Vec<&str> = range.split_whitespace().collect();
+    let (start, end) = match range.as_slice() {
+        ["inc", start, "-", end] => (*start, *end),
+        ["inc", _] => return Err(AppError::Validation("interval is still open".to_string())),
+        _ => {
+            return Err(AppError::Validation(format!(
+                "unrecognized interval line '{}'",
+                line.trim()
+            )))
+        }
+    };
+
+    let words = split_words(rest)?;
+    let mut sections = words.split(|(word, quoted)| word == "#" && !quoted);
+    let tags = sections
+        .next()
+        .unwrap_or_default()
+        .iter()
+        .map(|(word, _)| word.clone())
+        .collect();
+    let annotation = sections
+        .next()
+        .unwrap_or_default()
+        .iter()
+        .map(|(word, _)| word.as_str())
+        .collect::<Vec<&str>>()
+        .join(" ");
+    build(row, start, end, tags, annotation)
+}
+
+fn build(
+    row: usize,
+    start: &str,
+    end: &str,
+    tags: Vec<String>,
+    annotation: String,
+) -> AppResult<Interval> {
+    let start_minute = calendar::parse_utc_stamp(start)?;
+    let end_minute = calendar::parse_utc_stamp(end)?;
+    if end_minute <= start_minute {
+        return Err(AppError::Validation(
+            "interval ends before it starts or lasts less than a minute".to_string(),
+        ));
+    }
+    Ok(Interval {
+        row,
+        start_minute,
+        end_minute,
+        tags,
+        annotation,
+    })
+}
+
+fn split_words(text: &str) -> AppResult<Vec<(String, bool)>> {
+    let mut words = Vec::new();
+    let mut characters = text.chars().peekable();
+    while let Some(character) = characters.next() {
+        if character.is_whitespace() {
+            continue;
+        }
+        if character != '"' {
+            let mut word = character.to_string();
+            while let Some(next) = characters.next_if(|next| !next.is_whitespace()) {
+                word.push(next);
+            }
+            words.push((word, false));
+            continue;
+        }
+
+        let mut word = String::new();
+        loop {
+            match characters.next() {
+                Some('"') => break,
+                Some('\\') => match characters.next() {
+                    Some('n') => word.push('\n'),
+                    Some(escaped) => word.push(escaped),
+                    None => break,
+                },
+                Some(other) => word.push(other),
+                None => {
+                    return Err(AppError::Validation(
+                        "unterminated quote in interval".to_string(),
+                    ))
+                }
+            }
+        }
+        words.push((word, true));
+    }
+    Ok(words)
+}
+
+fn quote_if_needed(word: &str) -> String {
+    if word.is_empty() || word == "#" || word.contains(|c: char| c.is_whitespace() || c == '"') {
+        quote(word)
+    } else {
+        word.to_string()
+    }
+}
+
+fn quote(text: &str) -> String {
+    let mut quoted = String::from('"');
+    for character in text.chars() {
+        match character {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => {}
+            other => quoted.push(other),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, render};
+    use crate::model::{Mark, Timer};
+    use crate::stats::TimerActivity;
+
+    #[test]
+    fn round_trips_mark_segments_through_interval_lines() {
+        let timer = Timer {
+            id: "timer-1".to_string(),
+            name: "release v2".to_string(),
+            target_at_minute: 10_000,
+            created_at_minute: 0,
+            updated_at_minute: 0,
+            archived: false,
+            project_id: None,
+            tags: vec![],
+        };
+        let mark = |id: &str, prev: Option<i64>, marked: i64, description: &str| Mark {
+            id: id.to_string(),
+            timer_id: "timer-1".to_string(),
+            marked_at_minute: marked,
+            prev_marked_at_minute: prev,
+            duration_minutes: prev.map(|prev| marked - prev),
+            description: description.to_string(),
+            todo_ids: vec![],
+            tags: vec!["ui".to_string()],
+            category_id: None,
+        };
+        let marks = vec![
+            mark("mark-2", None, 60, "start"),
+            mark("mark-3", Some(60), 150, "fixed \"login\"\nadded tests"),
+        ];
+        let activities = [TimerActivity {
+            timer: &timer,
+            marks: &marks,
+            todos: &[],
+        }];
+
+        let data = render(&activities);
+        assert_eq!(
+            data,
+            "inc 19700101T010000Z - 19700101T023000Z # \"release v2\" ui # \"fixed \\\"login\\\"\\nadded tests\"\n"
+        );
+        let parsed = parse(&data).expect("intervals should parse");
+        assert_eq!(parsed.entries.len(), 1);
+        let interval = &parsed.entries[0];
+        assert_eq!((interval.start_minute, interval.end_minute), (60, 150));
+        assert_eq!(interval.tags, vec!["release v2", "ui"]);
+        assert_eq!(interval.annotation, marks[1].description);
+    }
+
+    #[test]
+    fn parses_export_json_and_skips_open_intervals() {
+        let parsed = parse(
+            "[{\"id\":2,\"start\":\"19700101T010000Z\",\"end\":\"19700101T020000Z\",\"tags\":[\"deep work\"],\"annotation\":\"notes\"},\
+              {\"id\":1,\"start\":\"19700101T030000Z\"}]",
+        )
+        .expect("json should parse");
+        assert_eq!(parsed.entries[0].tags, vec!["deep work"]);
+        assert_eq!(parsed.entries[0].annotation, "notes");
+        assert_eq!(parsed.skipped[0].row, 2);
+
+        let lines = parse(
+            "inc 19700101T030000Z\n\
+             inc 19700101T010000Z - 19700101T020000Z # # \"only note\"\n\
+             garbage\n",
+        )
+        .expect("lines should parse");
+        assert!(lines.entries[0].tags.is_empty());
+        assert_eq!(lines.entries[0].annotation, "only note");
+        let rows: Vec<usize> = lines.skipped.iter().map(|skipped| skipped.row).collect();
+        assert_eq!(rows, vec![1, 3]);
+    }
+}
@@ -0,0 +1,253 @@
+use crate::calendar;
+use crate::error::{AppError, AppResult};
+use crate::model::{EpochMinutes, Todo, TodoStatus};
+use crate::tag;
+
+const PRIORITY_TAG_PREFIX: &str = "pri-";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TodoTxtEntry {
+    pub title: String,
+    pub status: TodoStatus,
+    pub created_at_minute: Option<EpochMinutes>,
+    pub done_at_minute: Option<EpochMinutes>,
+    pub tags: Vec<String>,
+}
+
+pub fn parse(text: &str, utc_offset_minutes: EpochMinutes) -> AppResult<Vec<TodoTxtEntry>> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            parse_line(line, utc_offset_minutes).map_err(|error| match error {
+                AppError::Validation(message) => {
+                    AppError::Validation(format!("todo.txt line {}: {message}", index + 1))
+                }
+                other => other,
+            })
+        })
+        .collect()
+}
+
+pub fn render(todos: &[Todo], utc_offset_minutes: EpochMinutes) -> String {
+    todos
+        .iter()
+        .map(|todo| format!("{}\n", render_line(todo, utc_offset_minutes)))
+        .collect()
+}
+
+fn parse_line(line: &str, utc_offset_minutes: EpochMinutes) -> AppResult<TodoTxtEntry> {
+    let mut tokens = line.split_whitespace().peekable();
+    let done = tokens.next_if_eq(&"x").is_some();
+    let mut priority = tokens.peek().copied().and_then(parse_priority);
+    if priority.is_some() {
+        tokens.next();
+    }
+
+    let mut dates = Vec::new();
+    while dates.len() < if done { 2 } else { 1 } {
+        match tokens.next_if(|token| is_date(token)) {
+            Some(token) => dates.push(calendar::day_start_minute(
+                calendar::parse_day(token)?,
+                utc_offset_minutes,
+            )),
+            None => break,
+        }
+    }
+    let (done_at_minute, created_at_minute) = match (done, dates.as_slice()) {
+        (true, [completed]) => (Some(*completed), None),
+        (true, [completed, created]) => (Some(*completed), Some(*created)),
+        (_, [created]) => (None, Some(*created)),
+        _ => (None, None),
+    };
+
+    let mut words = Vec::new();
+    for token in tokens {
+        match token
+            .strip_prefix("pri:")
+            .and_then(|value| parse_priority(&format!("({value})")))
+        {
+            Some(letter) if done && priority.is_none() => priority = Some(letter),
+            _ => words.push(token),
+        }
+    }
+    let title = words.join(" ");
+    if title.is_empty() {
+        return Err(AppError::Validation("todo has no description".to_string()));
+    }
+
+    let mut tags = title_tags(&title);
+    if let Some(letter) = priority {
+        tags.push(format!(
+            "{PRIORITY_TAG_PREFIX}{}",
+            letter.to_ascii_lowercase()
+        ));
+    }
+    Ok(TodoTxtEntry {
+        title,
+        status: if done {
+            TodoStatus::Done
+        } else {
+            TodoStatus::Open
+        },
+        created_at_minute,
+        done_at_minute,
+        tags: tag::merge_tags(&tags, &[]),
+    })
+}
+
+fn render_line(todo: &Todo, utc_offset_minutes: EpochMinutes) -> String {
+    let format_day = |minute| calendar::format_day(calendar::day_index(minute, utc_offset_minutes));
+    let priority = todo.tags.iter().find_map(|tag| {
+        let mut letters = tag.strip_prefix(PRIORITY_TAG_PREFIX)?.chars();
+        match (letters.next(), letters.next()) {
+            (Some(letter), None) if letter.is_ascii_lowercase() => {
+                Some(letter.to_ascii_uppercase())
+            }
+            _ => None,
+        }
+    });
+
+    let mut parts = Vec::new();
+    if todo.status == TodoStatus::Done {
+        parts.push("x".to_string());
+        parts.push(format_day(
+            todo.done_at_minute.unwrap_or(todo.updated_at_minute),
+        ));
+    } else if let Some(letter) = priority {
+        parts.push(format!("({letter})"));
+    }
+    parts.push(format_day(todo.created_at_minute));
+    parts.push(todo.title.clone());
+
+    let title_tags = title_tags(&todo.title);
+    for tag in &todo.tags {
+        if !title_tags.contains(tag) && !tag.starts_with(PRIORITY_TAG_PREFIX) {
+            parts.push(format!("+{tag}"));
+        }
+    }
+    if let (TodoStatus::Done, Some(letter)) = (&todo.status, priority) {
+        parts.push(format!("pri:{letter}"));
+    }
+    parts.join(" ")
+}
+
+fn title_tags(title: &str) -> Vec<String> {
+    let mut tags = Vec::new();
+    for word in title.split_whitespace() {
+        let Some(name) = word.strip_prefix('+').or_else(|| word.strip_prefix('@')) else {
+            continue;
+        };
+        if let Ok(tag) = tag::normalize_tag(name) {
+            tags.push(tag);
+        }
+    }
+    tag::merge_tags(&tags, &tag::parse_tags(title))
+}
+
+fn parse_priority(token: &str) -> Option<char> {
+    let mut characters = token.chars();
+    match (
+        characters.next(),
+        characters.next(),
+        characters.next(),
+        characters.next(),
+    ) {
+        (Some('('), Some(letter), Some(')'), None) if letter.is_ascii_uppercase() => Some(letter),
+        _ => None,
+    }
+}
+
+fn is_date(token: &str) -> bool {
+    token.len() == 10 && calendar::parse_day(token).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, render};
+    use crate::calendar;
+    use crate::model::{Todo, TodoStatus};
+
+    #[test]
+    fn parses_completion_priority_dates_and_tags() {
+        let entries = parse(
+            "(A) 2026-10-01 call mom +family @phone due:2026-10-20\n\
+             \n\
+             x 2026-10-12 2026-10-02 ship release +work pri:B\n\
+             x plain done\n",
+            480,
+        )
+        .expect("todo.txt should parse");
+        let day =
+            |date: &str| calendar::parse_day(date).expect("date should parse") * 24 * 60 - 480;
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].title, "call mom +family @phone due:2026-10-20");
+        assert_eq!(entries[0].status, TodoStatus::Open);
+        assert_eq!(entries[0].created_at_minute, Some(day("2026-10-01")));
+        assert_eq!(entries[0].tags, vec!["family", "phone", "pri-a"]);
+        assert_eq!(entries[1].title, "ship release +work");
+        assert_eq!(entries[1].status, TodoStatus::Done);
+        assert_eq!(entries[1].done_at_minute, Some(day("2026-10-12")));
+        assert_eq!(entries[1].created_at_minute, Some(day("2026-10-02")));
+        assert_eq!(entries[1].tags, vec!["work", "pri-b"]);
+        assert_eq!(entries[2].done_at_minute, None);
+
+        let error = parse("ok\nx 2026-10-12\n", 0).expect_err("empty todo should fail");
+        assert!(error.to_string().contains("line 2"));
+    }
+
+    #[test]
+    fn round_trips_status_and_done_time() {
+        let day = calendar::parse_day("2026-10-12").expect("date should parse") * 24 * 60;
+        let todo = |id: &str, title: &str, status, done_at_minute, tags: &[&str]| Todo {
+            id: id.to_string(),
+            timer_id: "timer-1".to_string(),
+            title: title.to_string(),
+            status,
+            created_at_minute: day - 24 * 60 + 600,
+            updated_at_minute: day + 700,
+            done_at_minute,
+            tags: tags.iter().map(|tag| tag.to_string()).collect(),
+        };
+        let todos = vec![
+            todo(
+                "todo-1",
+                "write docs #docs",
+                TodoStatus::Open,
+                None,
+                &["docs", "pri-c"],
+            ),
+            todo(
+                "todo-2",
+                "ship +release",
+                TodoStatus::Done,
+                Some(day + 125),
+                &["release", "urgent", "pri-a"],
+            ),
+        ];
+
+        let text = render(&todos, 0);
+        assert_eq!(
+            text,
+            "(C) 2026-10-11 write docs #docs\n\
+             x 2026-10-12 2026-10-11 ship +release +urgent pri:A\n"
+        );
+
+        let entries = parse(&text, 0).expect("exported todo.txt should parse");
+        for (entry, todo) in entries.iter().zip(&todos) {
+            assert_eq!(entry.status, todo.status);
+            assert_eq!(
+                entry.done_at_minute,
+                todo.done_at_minute
+                    .map(|minute| calendar::day_start_minute(calendar::day_index(minute, 0), 0))
+            );
+            let mut expected = todo.tags.clone();
+            expected.sort();
+            let mut actual = entry.tags.clone();
+            actual.sort();
+            assert_eq!(actual, expected);
+        }
+        assert_eq!(entries[1].title, "ship +release +urgent");
+    }
+}
@@ -0,0 +1,223 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::calendar::{self, MINUTES_PER_DAY};
+use crate::error::{AppError, AppResult};
+use crate::model::{EpochMinutes, Mark, Timer, Todo, TodoStatus};
+use crate::stats::{TimerActivity, MAX_RANGE_DAYS};
+use crate::template::format_duration;
+
+const WEEKDAYS: [&str; 7] = ["周日", "周一", "周二", "周三", "周四", "周五", "周六"];
+
+pub fn render_markdown(
+    activities: &[TimerActivity<'_>],
+    from_date: &str,
+    to_date: &str,
+    utc_offset_minutes: EpochMinutes,
+) -> AppResult<String> {
+    let first_day = calendar::parse_day(from_date)?;
+    let last_day = calendar::parse_day(to_date)?;
+    if last_day < first_day {
+        return Err(AppError::Validation(format!(
+            "work log range end {to_date} is before its start {from_date}"
+        )));
+    }
+    if last_day - first_day + 1 > MAX_RANGE_DAYS {
+        return Err(AppError::Validation(format!(
+            "work log range cannot exceed {MAX_RANGE_DAYS} days"
+        )));
+    }
+
+    let range_start = calendar::day_start_minute(first_day, utc_offset_minutes);
+    let range_end = calendar::day_start_minute(last_day + 1, utc_offset_minutes);
+    let mut days: BTreeMap<i64, Vec<(&Timer, &Mark)>> = BTreeMap::new();
+    let mut minutes_by_day: BTreeMap<i64, EpochMinutes> = BTreeMap::new();
+    let mut todos: HashMap<&str, &Todo> = HashMap::new();
+    for activity in activities {
+        todos.extend(activity.todos.iter().map(|todo| (todo.id.as_str(), todo)));
+        for mark in activity.marks {
+            let day = calendar::day_index(mark.marked_at_minute, utc_offset_minutes);
+            if (first_day..=last_day).contains(&day) {
+                days.entry(day).or_default().push((activity.timer, mark));
+            }
+
+            // Split sessions across local days the same way daily stats does.
+            let Some(prev_marked_at_minute) = mark.prev_marked_at_minute else {
+                continue;
+            };
+            let start = prev_marked_at_minute.max(range_start);
+            let end = mark.marked_at_minute.min(range_end);
+            for (day, minutes) in calendar::split_by_day(start, end, utc_offset_minutes) {
+                *minutes_by_day.entry(day).or_default() += minutes;
+                days.entry(day).or_default();
+            }
+        }
+    }
+
+    let mut output = format!(
+        "# 工作日志 {} ~ {}\n",
+        calendar::format_day(first_day),
+        calendar::format_day(last_day)
+    );
+    if days.is_empty() {
+        output.push_str("\n_此范围内没有 Mark_\n");
+        return Ok(output);
+    }
+
+    let mut overall = 0;
+    for (day, mut entries) in days {
+        entries.sort_by(|left, right| {
+            left.1
+                .marked_at_minute
+                .cmp(&right.1.marked_at_minute)
+                .then(left.1.id.cmp(&right.1.id))
+        });
+        let total = minutes_by_day.get(&day).copied().unwrap_or_default();
+        overall += total;
+
+        output.push_str(&format!(
+            "\n## {} {} · 合计 {}\n\n",
+            calendar::format_day(day),
+            WEEKDAYS[(day + 4).rem_euclid(7) as usize],
+            format_duration(total)
+        ));
+        for (timer, mark) in entries {
+            push_mark(&mut output, timer, mark, &todos, day, utc_offset_minutes);
+        }
+    }
+    output.push_str(&format!("\n**总计：{}**\n", format_duration(overall)));
+    Ok(output)
+}
+
+fn push_mark(
+    output: &mut String,
+    timer: &Timer,
+    mark: &Mark,
+    todos: &HashMap<&str, &Todo>,
+    day: i64,
+    utc_offset_minutes: EpochMinutes,
+) {
+    let end = format_clock(mark.marked_at_minute, utc_offset_minutes);
+    let span = match mark.prev_marked_at_minute {
+        Some(start) if calendar::day_index(start, utc_offset_minutes) != day => {
+            let (_, month, day_of_month) =
+                calendar::civil_from_day(calendar::day_index(start, utc_offset_minutes));
+            format!(
+                "{month:02}-{day_of_month:02} {}–{end}",
+                format_clock(start, utc_offset_minutes)
+            )
+        }
+        Some(start) => format!("{}–{end}", format_clock(start, utc_offset_minutes)),
+        None => end,
+    };
+    let duration = mark
+        .duration_minutes
+        .map(|minutes| format!("（{}）", format_duration(minutes)))
+        .unwrap_or_default();
+
+    let mut lines = mark.description.trim().lines();
+    let first_line = lines.next().unwrap_or("_（无描述）_");
+    output.push_str(&format!(
+        "- {span}{duration} **{}**：{first_line}\n",
+        timer.name
+    ));
+    for line in lines {
+        output.push_str(&format!("  {line}\n"));
+    }
+    for todo in mark
+        .todo_ids
+        .iter()
+        .filter_map(|todo_id| todos.get(todo_id.as_str()))
+    {
+        let checkbox = if todo.status == TodoStatus::Done {
+            "x"
+        } else {
+            " "
+        };
+        output.push_str(&format!("  - [{checkbox}] {}\n", todo.title));
+    }
+}
+
+fn format_clock(minute: EpochMinutes, utc_offset_minutes: EpochMinutes) -> String {
+    let minute_of_day = (minute + utc_offset_minutes).rem_euclid(MINUTES_PER_DAY);
+    format!("{:02}:{:02}", minute_of_day / 60, minute_of_day % 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::render_markdown;
+    use crate::calendar;
+    use crate::model::{Mark, Timer, Todo, TodoStatus};
+    use crate::stats::TimerActivity;
+
+    #[test]
+    fn renders_marks_grouped_by_day_with_todos_and_totals() {
+        let day = calendar::parse_day("2026-10-12").expect("date should parse");
+        let at = |offset_days: i64, hour: i64, minute: i64| {
+            (day + offset_days) * 24 * 60 + hour * 60 + minute - 480
+        };
+        let timer = Timer {
+            id: "timer-1".to_string(),
+            name: "release".to_string(),
+            target_at_minute: at(7, 0, 0),
+            created_at_minute: at(-1, 9, 0),
+            updated_at_minute: at(-1, 9, 0),
+            archived: false,
+            project_id: None,
+            tags: vec![],
+        };
+        let todos = vec![Todo {
+            id: "todo-2".to_string(),
+            timer_id: "timer-1".to_string(),
+            title: "fix login".to_string(),
+            status: TodoStatus::Done,
+            created_at_minute: at(0, 8, 0),
+            updated_at_minute: at(0, 10, 0),
+            done_at_minute: Some(at(0, 10, 0)),
+            tags: vec![],
+        }];
+        let mark = |id: &str, prev: Option<i64>, marked: i64, description: &str, todo_ids| Mark {
+            id: id.to_string(),
+            timer_id: "timer-1".to_string(),
+            marked_at_minute: marked,
+            prev_marked_at_minute: prev,
+            duration_minutes: prev.map(|prev| marked - prev),
+            description: description.to_string(),
+            todo_ids,
+            tags: vec![],
+            category_id: None,
+        };
+        let marks = vec![
+            mark("mark-3", None, at(0, 9, 0), "start", vec![]),
+            mark(
+                "mark-4",
+                Some(at(0, 9, 0)),
+                at(0, 10, 30),
+                "fixed login\nadded tests",
+                vec!["todo-2".to_string()],
+            ),
+            mark("mark-5", Some(at(0, 23, 30)), at(1, 0, 15), "", vec![]),
+            mark("mark-6", Some(at(8, 9, 0)), at(9, 9, 0), "outside", vec![]),
+        ];
+        let activities = [TimerActivity {
+            timer: &timer,
+            marks: &marks,
+            todos: &todos,
+        }];
+
+        let markdown = render_markdown(&activities, "2026-10-12", "2026-10-18", 480)
+            .expect("work log should render");
+        assert_eq!(
+            markdown,
+            "# 工作日志 2026-10-12 ~ 2026-10-18\n\
+             \n## 2026-10-12 周一 · 合计 2小时\n\n\
+             - 09:00 **release**：start\n\
+             - 09:00–10:30（1小时30分） **release**：fixed login\n\
+             \x20 added tests\n\
+             \x20 - [x] fix login\n\
+             \n## 2026-10-13 周二 · 合计 15分钟\n\n\
+             - 10-12 23:30–00:15（45分钟） **release**：_（无描述）_\n\
+             \n**总计：2小时15分**\n"
+        );
+        assert!(render_markdown(&activities, "2026-10-18", "2026-10-12", 0).is_err());
+    }
+}